@@ -0,0 +1,658 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info, warn};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::db::repositories::{FlashcardRepository, TagRepository};
+use crate::db::models::flashcard::{Card, CardReview, CardState, Deck, ReviewGrade, SchedulerKind};
+use crate::db::models::tag::Tag;
+use crate::services::anki::{
+    self, AnkiCard, AnkiDeck, AnkiError, AnkiMedia, AnkiModel, AnkiNote, AnkiPackage, AnkiReview,
+    CARD_TYPE_NEW, CARD_TYPE_REVIEW, QUEUE_NEW, QUEUE_REVIEW,
+};
+use crate::services::{recurrence, spaced_repetition};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+/// Colour given to tags that only existed in an imported package.
+const IMPORTED_TAG_COLOR: &str = "#9e9e9e";
+/// Note types written into exported packages.
+const BASIC_MODEL_ID: i64 = 1_700_000_000_001;
+const CLOZE_MODEL_ID: i64 = 1_700_000_000_002;
+
+#[derive(Debug, Error, Serialize)]
+pub enum AnkiCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("File error: {0}")]
+    FileError(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for AnkiCommandError {
+    fn from(err: RusqliteError) -> Self {
+        AnkiCommandError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<AnkiError> for AnkiCommandError {
+    fn from(err: AnkiError) -> Self {
+        match err {
+            AnkiError::Io(err) => AnkiCommandError::FileError(err.to_string()),
+            err => AnkiCommandError::InvalidInput(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnkiImportSummary {
+    pub decks: Vec<Deck>,
+    pub cards: usize,
+    pub reviews: usize,
+    pub media: usize,
+    /// Cards already imported before, or whose note couldn't be read.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnkiExportSummary {
+    pub path: String,
+    pub decks: usize,
+    pub notes: usize,
+    pub cards: usize,
+    pub reviews: usize,
+    pub media: usize,
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Study Studio scheduling state for an Anki card. Anki's ease and interval
+/// carry over to SM-2 as they are; for FSRS the interval stands in for the
+/// stability, which it equals at the default 90% retention.
+fn card_state(card: &AnkiCard, reviews: &[&AnkiReview]) -> CardState {
+    if card.card_type == CARD_TYPE_NEW {
+        return CardState::default();
+    }
+
+    let interval_days = card.interval.max(0) as u32;
+    let repetitions = if reviews.is_empty() {
+        card.reps.saturating_sub(card.lapses).max(1)
+    } else {
+        reviews.iter().rev().take_while(|review| review.ease > 1).count() as u32
+    };
+
+    CardState {
+        repetitions,
+        lapses: card.lapses,
+        interval_days,
+        ease_factor: if card.factor > 0 {
+            (card.factor as f64 / 1000.0).max(spaced_repetition::MIN_EASE_FACTOR)
+        } else {
+            CardState::default().ease_factor
+        },
+        stability: interval_days as f64,
+        difficulty: if interval_days > 0 { spaced_repetition::FSRS_WEIGHTS[4] } else { 0.0 },
+    }
+}
+
+/// When an Anki card was last reviewed. Packages can leave out the review
+/// history; a review card then counts as reviewed on the day its current
+/// interval started.
+fn last_reviewed_at(card: &AnkiCard, due_date: NaiveDate, reviews: &[&AnkiReview]) -> Option<DateTime<Utc>> {
+    if let Some(review) = reviews.last() {
+        return Some(review.reviewed_at);
+    }
+    if card.card_type != CARD_TYPE_REVIEW {
+        return None;
+    }
+
+    let now = Utc::now();
+    let reviewed = (due_date - Duration::days(card.interval.max(0)))
+        .and_hms_opt(12, 0, 0)
+        .and_then(|noon| Local.from_local_datetime(&noon).earliest())
+        .map(|noon| noon.with_timezone(&Utc))
+        .unwrap_or(now);
+    Some(reviewed.min(now))
+}
+
+fn review_grade(ease: u32) -> Option<ReviewGrade> {
+    match ease {
+        1 => Some(ReviewGrade::Again),
+        2 => Some(ReviewGrade::Hard),
+        3 => Some(ReviewGrade::Good),
+        4 => Some(ReviewGrade::Easy),
+        _ => None,
+    }
+}
+
+fn local_day(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&Local).date_naive()
+}
+
+/// An Anki package read from disk, before the database is locked: unzipping
+/// a large collection with its media can take a while.
+pub struct AnkiImport {
+    path: String,
+    package: AnkiPackage,
+}
+
+impl AnkiImport {
+    pub fn read(path: String) -> Result<Self, AnkiCommandError> {
+        let package = anki::read_package(Path::new(&path)).map_err(|err| {
+            error!("Failed to read Anki package {}: {}", path, err);
+            AnkiCommandError::from(err)
+        })?;
+        Ok(Self { path, package })
+    }
+
+    /// Copies the package media into `media_dir`, keeping files that are
+    /// already there, and adds how many were written to the summary.
+    pub fn store_media(&self, media_dir: &Path, summary: AnkiImportSummary) -> Result<AnkiImportSummary, AnkiCommandError> {
+        let media = &self.package.media;
+        if !media.is_empty() {
+            fs::create_dir_all(media_dir).map_err(|err| {
+                error!("Failed to create media directory {:?}: {}", media_dir, err);
+                AnkiCommandError::FileError(err.to_string())
+            })?;
+        }
+
+        let mut written = 0;
+        for file in media {
+            let Some(name) = Path::new(&file.name).file_name() else {
+                continue;
+            };
+            let target = media_dir.join(name);
+            if target.exists() {
+                continue;
+            }
+            fs::write(&target, &file.data).map_err(|err| {
+                error!("Failed to write media file {:?}: {}", target, err);
+                AnkiCommandError::FileError(err.to_string())
+            })?;
+            written += 1;
+        }
+
+        info!("Copied {} media files from {}", written, self.path);
+        Ok(AnkiImportSummary { media: written, ..summary })
+    }
+}
+
+/// Decks and cards read for an Anki export, ready to be laid out and written
+/// without the database.
+pub struct AnkiExport {
+    decks: Vec<Deck>,
+    cards: Vec<(Card, Vec<CardReview>)>,
+    output_path: String,
+    media_dir: PathBuf,
+}
+
+impl AnkiExport {
+    pub fn write(self) -> Result<WrittenAnkiExport, AnkiCommandError> {
+        let AnkiExport { decks, cards, output_path, media_dir } = self;
+        let (package, keys) = AnkiCommands::build_package(&decks, &cards, &media_dir);
+
+        let destination = PathBuf::from(&output_path);
+        if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|err| {
+                error!("Failed to create export folder {:?}: {}", parent, err);
+                AnkiCommandError::FileError(err.to_string())
+            })?;
+        }
+        anki::write_package(&destination, &package).map_err(|err| {
+            error!("Failed to write Anki package {}: {}", output_path, err);
+            AnkiCommandError::from(err)
+        })?;
+
+        info!("Exported {} cards to {}", package.cards.len(), output_path);
+        let summary = AnkiExportSummary {
+            path: output_path,
+            decks: package.decks.len(),
+            notes: package.notes.len(),
+            cards: package.cards.len(),
+            reviews: package.reviews.len(),
+            media: package.media.len(),
+        };
+        Ok(WrittenAnkiExport { summary, keys })
+    }
+}
+
+/// A written Anki package with the `(card_id, guid, ord)` each card went out
+/// as, still to be recorded in the database.
+pub struct WrittenAnkiExport {
+    summary: AnkiExportSummary,
+    keys: Vec<(i64, String, u32)>,
+}
+
+pub struct AnkiCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> AnkiCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Maps Anki tag names onto library tags, creating the missing ones.
+    fn resolve_tags(conn: &Connection, mut tags: Vec<Tag>, notes: &[AnkiNote]) -> Result<Vec<Tag>, AnkiCommandError> {
+        for title in notes.iter().flat_map(|note| note.tags.iter()) {
+            if tags.iter().any(|tag| &tag.title == title) {
+                continue;
+            }
+            let tag = Tag {
+                id: None,
+                title: title.clone(),
+                color: IMPORTED_TAG_COLOR.to_string(),
+                icon: None,
+            };
+            let id = TagRepository::insert_tag(conn, &tag)?;
+            tags.push(Tag { id: Some(id as i32), ..tag });
+        }
+
+        Ok(tags)
+    }
+
+    /// Decks to import into, by Anki deck id. Decks are matched by name and
+    /// created when missing.
+    fn resolve_decks(conn: &Connection, mut known: Vec<Deck>, package: &AnkiPackage) -> Result<HashMap<i64, Deck>, AnkiCommandError> {
+        let used: HashSet<i64> = package.cards.iter().map(|card| card.deck_id).collect();
+        let mut decks = HashMap::new();
+
+        for deck_id in used {
+            let name = package.decks.iter()
+                .find(|deck| deck.id == deck_id)
+                .map(|deck| deck.name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "Anki import".to_string());
+
+            let deck = match known.iter().find(|deck| deck.name == name) {
+                Some(deck) => deck.clone(),
+                None => {
+                    let deck = Deck {
+                        id: None,
+                        name,
+                        description: None,
+                        scheduler: SchedulerKind::Sm2,
+                        created_at: None,
+                    };
+                    let id = FlashcardRepository::insert_deck(conn, &deck)?;
+                    let deck = Deck { id: Some(id), ..deck };
+                    known.push(deck.clone());
+                    deck
+                }
+            };
+            decks.insert(deck_id, deck);
+        }
+
+        Ok(decks)
+    }
+
+    /// Imports an Anki package: cards go into decks of the same name, with
+    /// their note tags, scheduling state and review history. Media files are
+    /// copied into `media_dir`. Cards imported before are skipped, so
+    /// importing a package twice is harmless. Field contents are kept as
+    /// Anki stores them, i.e. as HTML.
+    pub fn import_anki_package_method(&mut self, path: String, media_dir: &Path) -> Result<AnkiImportSummary, AnkiCommandError> {
+        let import = AnkiImport::read(path)?;
+        let summary = self.import_package_cards_method(&import)?;
+        import.store_media(media_dir, summary)
+    }
+
+    /// The database part of an import; the media files are copied by
+    /// `AnkiImport::store_media`, which needs no database access.
+    pub fn import_package_cards_method(&mut self, import: &AnkiImport) -> Result<AnkiImportSummary, AnkiCommandError> {
+        let AnkiImport { path, package } = import;
+        info!("Importing Anki package {}", path);
+
+        let repository = FlashcardRepository::new(self.conn);
+        let existing: HashSet<(String, u32)> = repository.get_anki_keys()?.into_iter().collect();
+        let known_decks = repository.get_decks()?;
+        let known_tags = TagRepository::new(self.conn).get_all_tags()?;
+
+        let notes: HashMap<i64, &AnkiNote> = package.notes.iter().map(|note| (note.id, note)).collect();
+        let models: HashMap<i64, &AnkiModel> = package.models.iter().map(|model| (model.id, model)).collect();
+        let mut reviews_by_card: HashMap<i64, Vec<&AnkiReview>> = HashMap::new();
+        for review in &package.reviews {
+            reviews_by_card.entry(review.card_id).or_default().push(review);
+        }
+
+        let today = today();
+        let tx = self.conn.transaction()?;
+        let decks = Self::resolve_decks(&tx, known_decks, package)?;
+        let tags = Self::resolve_tags(&tx, known_tags, &package.notes)?;
+        let mut imported = 0;
+        let mut reviews = 0;
+        let mut skipped = 0;
+
+        for anki_card in &package.cards {
+            let note = notes.get(&anki_card.note_id);
+            let model = note.and_then(|note| models.get(&note.model_id));
+            let (Some(note), Some(model), Some(deck)) = (note, model, decks.get(&anki_card.deck_id)) else {
+                skipped += 1;
+                continue;
+            };
+            if existing.contains(&(note.guid.clone(), anki_card.ord)) {
+                skipped += 1;
+                continue;
+            }
+
+            let (front, back) = model.render(note, anki_card.ord);
+            if front.trim().is_empty() {
+                skipped += 1;
+                continue;
+            }
+
+            let card_reviews: Vec<&AnkiReview> = reviews_by_card.get(&anki_card.id)
+                .map(|reviews| reviews.iter().filter(|review| review_grade(review.ease).is_some()).copied().collect())
+                .unwrap_or_default();
+            let due_date = anki_card.due_date(package.created, today);
+            let card = Card {
+                id: None,
+                deck_id: deck.id.unwrap_or_default(),
+                front,
+                back,
+                book_id: None,
+                page_number: None,
+                due_date: recurrence::format_date(due_date),
+                state: card_state(anki_card, &card_reviews),
+                created_at: None,
+                last_reviewed_at: last_reviewed_at(anki_card, due_date, &card_reviews),
+                annotation_id: None,
+                cloze_text: model.is_cloze.then(|| note.fields.first().cloned()).flatten(),
+                anki_guid: Some(note.guid.clone()),
+                anki_ord: Some(anki_card.ord),
+                tags: Some(
+                    note.tags.iter()
+                        .filter_map(|title| tags.iter().find(|tag| &tag.title == title).cloned())
+                        .collect(),
+                ),
+            };
+            let card_id = FlashcardRepository::insert_card(&tx, &card)?;
+
+            let mut previous: Option<DateTime<Utc>> = None;
+            for review in card_reviews {
+                let elapsed_days = previous
+                    .map(|previous| (local_day(review.reviewed_at) - local_day(previous)).num_days().max(0) as u32)
+                    .unwrap_or(0);
+                FlashcardRepository::insert_review(&tx, &CardReview {
+                    id: None,
+                    card_id,
+                    grade: review_grade(review.ease).unwrap_or(ReviewGrade::Good),
+                    scheduler: SchedulerKind::Sm2,
+                    elapsed_days,
+                    interval_days: review.interval.max(0) as u32,
+                    reviewed_at: review.reviewed_at,
+                })?;
+                previous = Some(review.reviewed_at);
+                reviews += 1;
+            }
+            imported += 1;
+        }
+
+        tx.commit()?;
+
+        info!("Imported {} cards and {} reviews from {} ({} skipped)", imported, reviews, path, skipped);
+        let mut decks: Vec<Deck> = decks.into_values().collect();
+        decks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(AnkiImportSummary { decks, cards: imported, reviews, media: 0, skipped })
+    }
+
+    /// Writes the decks (every deck when `deck_ids` is empty) to an Anki
+    /// package at `output_path`, with tags, scheduling and review history and
+    /// the media files the cards refer to. Cloze cards sharing an Anki note
+    /// are written back as one note; other cards become Basic notes.
+    pub fn export_anki_package_method(
+        &mut self,
+        deck_ids: Vec<i64>,
+        output_path: String,
+        media_dir: &Path,
+    ) -> Result<AnkiExportSummary, AnkiCommandError> {
+        let written = self.prepare_anki_export_method(deck_ids, output_path, media_dir)?.write()?;
+        self.record_anki_export_method(written)
+    }
+
+    /// Reads the decks and cards to export; the package is written by
+    /// `AnkiExport::write`, which needs no database access.
+    pub fn prepare_anki_export_method(
+        &mut self,
+        deck_ids: Vec<i64>,
+        output_path: String,
+        media_dir: &Path,
+    ) -> Result<AnkiExport, AnkiCommandError> {
+        info!("Exporting decks {:?} to {}", deck_ids, output_path);
+
+        let repository = FlashcardRepository::new(self.conn);
+        let decks = if deck_ids.is_empty() {
+            repository.get_decks()?
+        } else {
+            let mut decks = Vec::new();
+            for deck_id in &deck_ids {
+                let deck = repository.get_deck_by_id(*deck_id)?.ok_or_else(|| {
+                    let msg = format!("Deck with ID {} not found", deck_id);
+                    error!("{}", msg);
+                    AnkiCommandError::InvalidInput(msg)
+                })?;
+                decks.push(deck);
+            }
+            decks
+        };
+
+        let mut cards = Vec::new();
+        for deck in &decks {
+            for card in repository.get_cards_by_deck_id(deck.id.unwrap_or_default())? {
+                let reviews = repository.get_reviews_by_card_id(card.id.unwrap_or_default())?;
+                cards.push((card, reviews));
+            }
+        }
+
+        Ok(AnkiExport { decks, cards, output_path, media_dir: media_dir.to_path_buf() })
+    }
+
+    /// Remembers which note each exported card went out as, so importing the
+    /// package back doesn't duplicate it.
+    pub fn record_anki_export_method(&mut self, written: WrittenAnkiExport) -> Result<AnkiExportSummary, AnkiCommandError> {
+        let WrittenAnkiExport { summary, keys } = written;
+
+        let tx = self.conn.transaction()?;
+        for (card_id, guid, ord) in &keys {
+            FlashcardRepository::set_anki_key(&tx, *card_id, guid, *ord)?;
+        }
+        tx.commit()?;
+
+        Ok(summary)
+    }
+
+    /// Lays out the cards as an Anki collection. Also returns the
+    /// `(card_id, guid, ord)` each card was written as.
+    fn build_package(
+        decks: &[Deck],
+        cards: &[(Card, Vec<CardReview>)],
+        media_dir: &Path,
+    ) -> (AnkiPackage, Vec<(i64, String, u32)>) {
+        let now = Utc::now();
+        let base_id = now.timestamp_millis();
+        let today = today();
+
+        // Review cards count their due day from the collection's creation,
+        // so it has to come before every due date.
+        let first_day = cards.iter()
+            .filter_map(|(card, _)| recurrence::parse_date(&card.due_date).ok())
+            .chain(std::iter::once(today))
+            .min()
+            .unwrap_or(today);
+        let created = first_day.and_hms_opt(4, 0, 0)
+            .and_then(|start| Local.from_local_datetime(&start).earliest())
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or(now);
+
+        let mut package = AnkiPackage {
+            created,
+            models: vec![AnkiModel::basic(BASIC_MODEL_ID), AnkiModel::cloze(CLOZE_MODEL_ID)],
+            decks: decks.iter().enumerate().map(|(index, deck)| AnkiDeck {
+                id: base_id + index as i64 + 1,
+                name: deck.name.clone(),
+            }).collect(),
+            ..AnkiPackage::default()
+        };
+        let deck_ids: HashMap<i64, i64> = decks.iter()
+            .zip(&package.decks)
+            .map(|(deck, anki_deck)| (deck.id.unwrap_or_default(), anki_deck.id))
+            .collect();
+
+        let mut keys = Vec::new();
+        let mut note_ids: HashMap<String, i64> = HashMap::new();
+        let mut media_names: Vec<String> = Vec::new();
+        let mut last_review_id = 0;
+
+        for (index, (card, reviews)) in cards.iter().enumerate() {
+            let card_id = card.id.unwrap_or_default();
+            let guid = card.anki_guid.clone().unwrap_or_else(|| format!("ss-{}", card_id));
+            let (guid, ord) = match (&card.cloze_text, card.anki_ord.unwrap_or(0)) {
+                (Some(_), ord) => (guid, ord),
+                (None, 0) => (guid, 0),
+                // Basic notes only have one card, so other templates get a note each.
+                (None, ord) => (format!("{}-{}", guid, ord), 0),
+            };
+
+            let note_id = match note_ids.get(&guid) {
+                Some(note_id) => *note_id,
+                None => {
+                    let note_id = base_id + package.notes.len() as i64;
+                    let (model_id, fields) = match &card.cloze_text {
+                        Some(text) => (CLOZE_MODEL_ID, vec![text.clone(), String::new()]),
+                        None => (BASIC_MODEL_ID, vec![card.front.clone(), card.back.clone()]),
+                    };
+                    package.notes.push(AnkiNote {
+                        id: note_id,
+                        guid: guid.clone(),
+                        model_id,
+                        tags: card.tags.iter().flatten().map(|tag| tag.title.replace(' ', "_")).collect(),
+                        fields,
+                    });
+                    note_ids.insert(guid.clone(), note_id);
+                    note_id
+                }
+            };
+
+            for name in anki::media_references(&card.front).into_iter().chain(anki::media_references(&card.back)) {
+                // Media names come from card HTML; only bare file names may
+                // be read from the media folder, as when importing.
+                if Path::new(&name).file_name() != Some(OsStr::new(&name)) {
+                    warn!("Skipping media reference {:?} outside the media folder", name);
+                    continue;
+                }
+                if !media_names.contains(&name) && media_dir.join(&name).is_file() {
+                    media_names.push(name);
+                }
+            }
+
+            let anki_card_id = base_id + index as i64;
+            let factor = (card.state.ease_factor * 1000.0).round() as i64;
+            let reviewed = card.last_reviewed_at.is_some();
+            package.cards.push(AnkiCard {
+                id: anki_card_id,
+                note_id,
+                deck_id: deck_ids.get(&card.deck_id).copied().unwrap_or(1),
+                ord,
+                card_type: if reviewed { CARD_TYPE_REVIEW } else { CARD_TYPE_NEW },
+                queue: if reviewed { QUEUE_REVIEW } else { QUEUE_NEW },
+                due: if reviewed {
+                    recurrence::parse_date(&card.due_date)
+                        .map(|due| (due - first_day).num_days())
+                        .unwrap_or(0)
+                } else {
+                    index as i64 + 1
+                },
+                interval: card.state.interval_days as i64,
+                factor,
+                reps: reviews.len() as u32,
+                lapses: card.state.lapses,
+            });
+
+            let mut last_interval = 0;
+            for (position, review) in reviews.iter().enumerate() {
+                let review_id = review.reviewed_at.timestamp_millis().max(last_review_id + 1);
+                last_review_id = review_id;
+                package.reviews.push(AnkiReview {
+                    card_id: anki_card_id,
+                    reviewed_at: Utc.timestamp_millis_opt(review_id).single().unwrap_or(review.reviewed_at),
+                    ease: review.grade.rating(),
+                    interval: review.interval_days as i64,
+                    last_interval,
+                    factor,
+                    kind: if position == 0 { 0 } else { 1 },
+                });
+                last_interval = review.interval_days as i64;
+            }
+
+            keys.push((card_id, guid, ord));
+        }
+
+        package.media = media_names.into_iter()
+            .filter_map(|name| {
+                let data = fs::read(media_dir.join(&name)).ok()?;
+                Some(AnkiMedia { name, data })
+            })
+            .collect();
+
+        (package, keys)
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn import_anki_package_command(
+    app_state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<AnkiImportSummary, InvokeError> {
+    let import = match AnkiImport::read(path) {
+        Ok(import) => import,
+        Err(err) => return Err(InvokeError::from(err)),
+    };
+
+    let summary = {
+        let mut conn = app_state.db_conn.lock().unwrap();
+        let mut anki_commands = AnkiCommands::new(&mut conn);
+        anki_commands.import_package_cards_method(&import)
+    };
+
+    match summary.and_then(|summary| import.store_media(&app_state.media_dir, summary)) {
+        Ok(summary) => Ok(summary),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn export_anki_package_command(
+    app_state: tauri::State<'_, AppState>,
+    deck_ids: Vec<i64>,
+    output_path: String,
+) -> Result<AnkiExportSummary, InvokeError> {
+    let export = {
+        let mut conn = app_state.db_conn.lock().unwrap();
+        let mut anki_commands = AnkiCommands::new(&mut conn);
+        anki_commands.prepare_anki_export_method(deck_ids, output_path, &app_state.media_dir)
+    };
+
+    let summary = export.and_then(AnkiExport::write).and_then(|written| {
+        let mut conn = app_state.db_conn.lock().unwrap();
+        let mut anki_commands = AnkiCommands::new(&mut conn);
+        anki_commands.record_anki_export_method(written)
+    });
+
+    match summary {
+        Ok(summary) => Ok(summary),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
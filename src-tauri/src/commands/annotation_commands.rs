@@ -0,0 +1,420 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use crate::db::repositories::{AnnotationRepository, BookRepository};
+use crate::db::models::annotation::{Annotation, AnnotationKind, AnnotationRect, DEFAULT_ANNOTATION_COLOR};
+use crate::db::models::tag::Tag;
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+#[derive(Debug, Error, Serialize)]
+pub enum AnnotationCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for AnnotationCommandError {
+    fn from(err: RusqliteError) -> Self {
+        AnnotationCommandError::DatabaseError(err.to_string())
+    }
+}
+
+/// Anchor and content sent by the reader when creating an annotation.
+#[derive(Debug, Default)]
+pub struct AnnotationInput {
+    pub kind: Option<String>,
+    pub page_number: Option<i32>,
+    pub chapter_index: Option<i32>,
+    pub location: Option<String>,
+    pub rects: Option<Vec<AnnotationRect>>,
+    pub start_offset: Option<i32>,
+    pub end_offset: Option<i32>,
+    pub selected_text: Option<String>,
+    pub color: Option<String>,
+    pub note: Option<String>,
+}
+
+pub struct AnnotationCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> AnnotationCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn parse_kind(kind: &str) -> Result<AnnotationKind, AnnotationCommandError> {
+        AnnotationKind::parse(kind).ok_or_else(|| {
+            let msg = format!("Unknown annotation kind '{}'", kind);
+            error!("{}", msg);
+            AnnotationCommandError::InvalidInput(msg)
+        })
+    }
+
+    fn validate(annotation: &Annotation) -> Result<(), AnnotationCommandError> {
+        let invalid = |msg: &str| {
+            error!("{}", msg);
+            Err(AnnotationCommandError::InvalidInput(msg.to_string()))
+        };
+
+        if annotation.page_number.is_none() && annotation.chapter_index.is_none() && annotation.location.is_none() {
+            return invalid("A page, chapter or location must be provided");
+        }
+        if annotation.page_number.is_some_and(|page| page < 1) {
+            return invalid("Page numbers start at 1");
+        }
+        if let (Some(start), Some(end)) = (annotation.start_offset, annotation.end_offset) {
+            if start < 0 || end < start {
+                return invalid("Text offsets must satisfy 0 <= start <= end");
+            }
+        }
+        if annotation.rects.as_ref().is_some_and(|rects| rects.iter().any(|r| r.width < 0.0 || r.height < 0.0)) {
+            return invalid("Annotation rectangles must have a non-negative size");
+        }
+        if annotation.kind != AnnotationKind::Note
+            && annotation.selected_text.as_deref().unwrap_or("").trim().is_empty()
+            && annotation.rects.as_deref().unwrap_or_default().is_empty()
+        {
+            return invalid("Highlights need the selected text or its rectangles");
+        }
+        if annotation.kind == AnnotationKind::Note && annotation.note.as_deref().unwrap_or("").trim().is_empty() {
+            return invalid("A note annotation needs some text");
+        }
+        if annotation.color.trim().is_empty() {
+            return invalid("Color cannot be empty");
+        }
+        Ok(())
+    }
+
+    pub fn create_annotation_method(
+        &mut self,
+        book_id: i64,
+        input: AnnotationInput,
+        tags: Vec<Tag>,
+    ) -> Result<Annotation, AnnotationCommandError> {
+        info!("Creating annotation for book {}", book_id);
+
+        let kind = match input.kind.as_deref() {
+            Some(kind) => Self::parse_kind(kind)?,
+            None => AnnotationKind::Highlight,
+        };
+
+        let annotation = Annotation {
+            id: None,
+            book_id,
+            kind,
+            page_number: input.page_number,
+            chapter_index: input.chapter_index,
+            location: input.location,
+            rects: input.rects,
+            start_offset: input.start_offset,
+            end_offset: input.end_offset,
+            selected_text: input.selected_text,
+            color: input.color.unwrap_or_else(|| DEFAULT_ANNOTATION_COLOR.to_string()),
+            note: input.note,
+            created_at: None,
+            updated_at: None,
+            pdf_ref: None,
+            tags: Some(tags),
+        };
+        Self::validate(&annotation)?;
+
+        if BookRepository::new(self.conn).get_book_by_id(book_id)?.is_none() {
+            let msg = format!("Book with ID {} not found", book_id);
+            error!("{}", msg);
+            return Err(AnnotationCommandError::InvalidInput(msg));
+        }
+
+        let mut repository = AnnotationRepository::new(self.conn);
+        let id = match repository.create_annotation(&annotation) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("Failed to create annotation for book {}: {}", book_id, err);
+                return Err(AnnotationCommandError::DatabaseError(err.to_string()));
+            }
+        };
+
+        info!("Annotation created with ID {}", id);
+        repository.get_annotation_by_id(id)?.ok_or_else(|| {
+            AnnotationCommandError::UnexpectedError(format!("Annotation {} vanished after insert", id))
+        })
+    }
+
+    pub fn update_annotation_method(
+        &mut self,
+        id: i64,
+        kind: Option<String>,
+        color: Option<String>,
+        note: Option<String>,
+        clear_note: bool,
+    ) -> Result<Annotation, AnnotationCommandError> {
+        info!("Updating annotation {}", id);
+
+        if clear_note && note.is_some() {
+            let msg = "A note cannot be set and cleared at once".to_string();
+            error!("{}", msg);
+            return Err(AnnotationCommandError::InvalidInput(msg));
+        }
+
+        let mut repository = AnnotationRepository::new(self.conn);
+        let mut annotation = repository.get_annotation_by_id(id)?.ok_or_else(|| {
+            let msg = format!("Annotation with ID {} not found", id);
+            error!("{}", msg);
+            AnnotationCommandError::InvalidInput(msg)
+        })?;
+
+        if let Some(kind) = kind {
+            annotation.kind = Self::parse_kind(&kind)?;
+        }
+        if let Some(color) = color {
+            annotation.color = color;
+        }
+        if clear_note {
+            annotation.note = None;
+        } else if let Some(note) = note {
+            annotation.note = Some(note);
+        }
+        Self::validate(&annotation)?;
+
+        if let Err(err) = repository.update_annotation(&annotation) {
+            error!("Failed to update annotation {}: {}", id, err);
+            return Err(AnnotationCommandError::DatabaseError(err.to_string()));
+        }
+
+        repository.get_annotation_by_id(id)?.ok_or_else(|| {
+            AnnotationCommandError::UnexpectedError(format!("Annotation {} vanished after update", id))
+        })
+    }
+
+    pub fn delete_annotation_method(&mut self, id: i64) -> Result<String, AnnotationCommandError> {
+        info!("Deleting annotation {}", id);
+
+        match AnnotationRepository::new(self.conn).delete_annotation(id) {
+            Ok(0) => {
+                let msg = format!("Annotation with ID {} not found", id);
+                error!("{}", msg);
+                Err(AnnotationCommandError::InvalidInput(msg))
+            }
+            Ok(_) => {
+                let success_msg = format!("Annotation {} deleted successfully", id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to delete annotation {}: {}", id, err);
+                Err(AnnotationCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn get_annotation_method(&mut self, id: i64) -> Result<Option<Annotation>, AnnotationCommandError> {
+        info!("Fetching annotation {}", id);
+        Ok(AnnotationRepository::new(self.conn).get_annotation_by_id(id)?)
+    }
+
+    pub fn list_book_annotations_method(
+        &mut self,
+        book_id: i64,
+        page_number: Option<i32>,
+    ) -> Result<Vec<Annotation>, AnnotationCommandError> {
+        info!("Listing annotations for book {}", book_id);
+
+        match AnnotationRepository::new(self.conn).get_annotations_by_book_id(book_id, page_number) {
+            Ok(annotations) => Ok(annotations),
+            Err(err) => {
+                error!("Failed to list annotations for book {}: {}", book_id, err);
+                Err(AnnotationCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn add_tags_to_annotation_method(
+        &mut self,
+        annotation_id: i64,
+        tags: Vec<Tag>,
+    ) -> Result<String, AnnotationCommandError> {
+        info!("Adding tags to annotation {}", annotation_id);
+
+        if tags.is_empty() {
+            let msg = "No tags provided".to_string();
+            error!("{}", msg);
+            return Err(AnnotationCommandError::InvalidInput(msg));
+        }
+
+        match AnnotationRepository::new(self.conn).add_tags_to_annotation(annotation_id, tags) {
+            Ok(_) => {
+                let success_msg = format!("Tags added successfully to annotation {}", annotation_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to add tags to annotation {}: {}", annotation_id, err);
+                Err(AnnotationCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn remove_tags_from_annotation_method(
+        &mut self,
+        annotation_id: i64,
+        tags: Vec<Tag>,
+    ) -> Result<String, AnnotationCommandError> {
+        info!("Removing tags from annotation {}", annotation_id);
+
+        if tags.is_empty() {
+            let msg = "No tags provided".to_string();
+            error!("{}", msg);
+            return Err(AnnotationCommandError::InvalidInput(msg));
+        }
+
+        match AnnotationRepository::new(self.conn).remove_tags_from_annotation(annotation_id, tags) {
+            Ok(_) => {
+                let success_msg = format!("Tags removed successfully from annotation {}", annotation_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to remove tags from annotation {}: {}", annotation_id, err);
+                Err(AnnotationCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_annotation_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+    kind: Option<String>,
+    page_number: Option<i32>,
+    chapter_index: Option<i32>,
+    location: Option<String>,
+    rects: Option<Vec<AnnotationRect>>,
+    start_offset: Option<i32>,
+    end_offset: Option<i32>,
+    selected_text: Option<String>,
+    color: Option<String>,
+    note: Option<String>,
+    tags: Option<Vec<Tag>>,
+) -> Result<Annotation, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    let input = AnnotationInput {
+        kind,
+        page_number,
+        chapter_index,
+        location,
+        rects,
+        start_offset,
+        end_offset,
+        selected_text,
+        color,
+        note,
+    };
+
+    match annotation_commands.create_annotation_method(book_id, input, tags.unwrap_or_default()) {
+        Ok(annotation) => Ok(annotation),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn update_annotation_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i64,
+    kind: Option<String>,
+    color: Option<String>,
+    note: Option<String>,
+    clear_note: Option<bool>,
+) -> Result<Annotation, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    match annotation_commands.update_annotation_method(id, kind, color, note, clear_note.unwrap_or(false)) {
+        Ok(annotation) => Ok(annotation),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_annotation_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    match annotation_commands.delete_annotation_method(id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_annotation_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<Option<Annotation>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    match annotation_commands.get_annotation_method(id) {
+        Ok(annotation) => Ok(annotation),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_book_annotations_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+    page_number: Option<i32>,
+) -> Result<Vec<Annotation>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    match annotation_commands.list_book_annotations_method(book_id, page_number) {
+        Ok(annotations) => Ok(annotations),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn add_tags_to_annotation_command(
+    app_state: tauri::State<'_, AppState>,
+    annotation_id: i64,
+    tags: Vec<Tag>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    match annotation_commands.add_tags_to_annotation_method(annotation_id, tags) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn remove_tags_from_annotation_command(
+    app_state: tauri::State<'_, AppState>,
+    annotation_id: i64,
+    tags: Vec<Tag>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut annotation_commands = AnnotationCommands::new(&mut conn);
+
+    match annotation_commands.remove_tags_from_annotation_method(annotation_id, tags) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
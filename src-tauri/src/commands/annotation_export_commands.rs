@@ -0,0 +1,433 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::db::repositories::{
+    AnnotationRepository, BookRepository, ChapterRepository, DocumentRepository, TagRepository,
+};
+use crate::db::models::{annotation::Annotation, book::Book, chapter::Chapter, tag::Tag};
+use crate::services::{
+    export_file_name, parse_json, render, write_annotated_pdf, AnnotationExport, ExportFormat,
+    PdfError, ANNOTATION_EXPORT_VERSION,
+};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+#[derive(Debug, Error, Serialize)]
+pub enum AnnotationExportCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("File error: {0}")]
+    FileError(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for AnnotationExportCommandError {
+    fn from(err: RusqliteError) -> Self {
+        AnnotationExportCommandError::DatabaseError(err.to_string())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedAnnotationFile {
+    pub book_id: i64,
+    pub path: String,
+    pub annotation_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnotationImportSummary {
+    pub book_id: i64,
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+/// An annotated PDF export whose book has been checked and whose
+/// annotations have been read, ready to be written without the database.
+pub struct AnnotatedPdfExport {
+    book_id: i64,
+    source: PathBuf,
+    destination: PathBuf,
+    output_path: String,
+    annotations: Vec<Annotation>,
+}
+
+impl AnnotatedPdfExport {
+    pub fn write(self) -> Result<ExportedAnnotationFile, AnnotationExportCommandError> {
+        let AnnotatedPdfExport { book_id, source, destination, output_path, annotations } = self;
+
+        if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|err| {
+                error!("Failed to create export folder {:?}: {}", parent, err);
+                AnnotationExportCommandError::FileError(err.to_string())
+            })?;
+        }
+
+        match write_annotated_pdf(&source, &destination, &annotations) {
+            Ok(annotation_count) => {
+                info!("Embedded {} annotations into {:?}", annotation_count, destination);
+                Ok(ExportedAnnotationFile { book_id, path: output_path, annotation_count })
+            }
+            Err(PdfError::Encrypted) => {
+                let msg = PdfError::Encrypted.to_string();
+                error!("{}", msg);
+                Err(AnnotationExportCommandError::InvalidInput(msg))
+            }
+            Err(err) => {
+                error!("Failed to write annotated PDF for book {}: {}", book_id, err);
+                Err(AnnotationExportCommandError::FileError(err.to_string()))
+            }
+        }
+    }
+}
+
+pub struct AnnotationExportCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> AnnotationExportCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn get_book(&mut self, book_id: i64) -> Result<Book, AnnotationExportCommandError> {
+        BookRepository::new(self.conn).get_book_by_id(book_id)?.ok_or_else(|| {
+            let msg = format!("Book with ID {} not found", book_id);
+            error!("{}", msg);
+            AnnotationExportCommandError::InvalidInput(msg)
+        })
+    }
+
+    fn document_hash(&mut self, book: &Book) -> Result<Option<String>, AnnotationExportCommandError> {
+        let document = match book.document_id {
+            Some(document_id) => DocumentRepository::new(self.conn)
+                .get_by_id(document_id as i32)
+                .map_err(AnnotationExportCommandError::DatabaseError)?,
+            None => None,
+        };
+        Ok(document.map(|d| d.hash).filter(|hash| !hash.is_empty()))
+    }
+
+    fn build_export(
+        &mut self,
+        book: Book,
+        annotations: Vec<Annotation>,
+    ) -> Result<(AnnotationExport, Vec<Chapter>), AnnotationExportCommandError> {
+        let document_hash = self.document_hash(&book)?;
+        let chapters = match book.document_id {
+            Some(document_id) => ChapterRepository::new(self.conn).get_chapters_by_document_id(document_id)?,
+            None => Vec::new(),
+        };
+
+        let export = AnnotationExport {
+            format_version: ANNOTATION_EXPORT_VERSION,
+            book_title: book.title,
+            book_author: book.author,
+            document_hash,
+            book_tags: book.tags.unwrap_or_default(),
+            annotations,
+        };
+        Ok((export, chapters))
+    }
+
+    /// Writes one file per book into `output_dir`. With a tag, every book that
+    /// has annotations under that tag gets its own file.
+    pub fn export_annotations_method(
+        &mut self,
+        book_id: Option<i64>,
+        tag_id: Option<i32>,
+        format: String,
+        output_dir: String,
+    ) -> Result<Vec<ExportedAnnotationFile>, AnnotationExportCommandError> {
+        info!("Exporting annotations as {}", format);
+
+        let format = ExportFormat::parse(&format).ok_or_else(|| {
+            let msg = format!("Unknown export format '{}'", format);
+            error!("{}", msg);
+            AnnotationExportCommandError::InvalidInput(msg)
+        })?;
+
+        let mut by_book: Vec<(i64, Vec<Annotation>)> = Vec::new();
+        match (book_id, tag_id) {
+            (Some(book_id), None) => {
+                self.get_book(book_id)?;
+                let annotations = AnnotationRepository::new(self.conn).get_annotations_by_book_id(book_id, None)?;
+                by_book.push((book_id, annotations));
+            }
+            (None, Some(tag_id)) => {
+                for annotation in AnnotationRepository::new(self.conn).get_annotations_by_tag_id(tag_id)? {
+                    match by_book.last_mut() {
+                        Some((id, annotations)) if *id == annotation.book_id => annotations.push(annotation),
+                        _ => by_book.push((annotation.book_id, vec![annotation])),
+                    }
+                }
+            }
+            _ => {
+                let msg = "Provide either a book or a tag to export".to_string();
+                error!("{}", msg);
+                return Err(AnnotationExportCommandError::InvalidInput(msg));
+            }
+        }
+
+        let output_dir = PathBuf::from(output_dir);
+        fs::create_dir_all(&output_dir).map_err(|err| {
+            error!("Failed to create export folder {:?}: {}", output_dir, err);
+            AnnotationExportCommandError::FileError(err.to_string())
+        })?;
+
+        let mut used_names = HashSet::new();
+        let mut written = Vec::new();
+
+        for (book_id, annotations) in by_book {
+            let book = self.get_book(book_id)?;
+            let mut file_name = export_file_name(&book.title, format);
+            if !used_names.insert(file_name.clone()) {
+                file_name = export_file_name(&format!("{} ({})", book.title, book_id), format);
+                used_names.insert(file_name.clone());
+            }
+
+            let annotation_count = annotations.len();
+            let (export, chapters) = self.build_export(book, annotations)?;
+            let contents = render(&export, &chapters, format)
+                .map_err(AnnotationExportCommandError::UnexpectedError)?;
+
+            let path = output_dir.join(file_name);
+            fs::write(&path, contents).map_err(|err| {
+                error!("Failed to write {:?}: {}", path, err);
+                AnnotationExportCommandError::FileError(err.to_string())
+            })?;
+
+            info!("Exported {} annotations of book {} to {:?}", annotation_count, book_id, path);
+            written.push(ExportedAnnotationFile {
+                book_id,
+                path: path.to_string_lossy().to_string(),
+                annotation_count,
+            });
+        }
+
+        Ok(written)
+    }
+
+    /// Writes a copy of the book's PDF with its annotations embedded, so any
+    /// PDF viewer shows them. The managed file in the library is only read.
+    pub fn export_annotated_pdf_method(
+        &mut self,
+        book_id: i64,
+        output_path: String,
+    ) -> Result<ExportedAnnotationFile, AnnotationExportCommandError> {
+        self.prepare_annotated_pdf_method(book_id, output_path)?.write()
+    }
+
+    /// Checks the book and reads its annotations; the PDF itself is written
+    /// by `AnnotatedPdfExport::write`, which needs no database access.
+    pub fn prepare_annotated_pdf_method(
+        &mut self,
+        book_id: i64,
+        output_path: String,
+    ) -> Result<AnnotatedPdfExport, AnnotationExportCommandError> {
+        info!("Exporting annotated PDF of book {} to {}", book_id, output_path);
+
+        let book = self.get_book(book_id)?;
+        let document = match book.document_id {
+            Some(document_id) => DocumentRepository::new(self.conn)
+                .get_by_id(document_id as i32)
+                .map_err(AnnotationExportCommandError::DatabaseError)?,
+            None => None,
+        };
+        let document = document.ok_or_else(|| {
+            let msg = format!("Book with ID {} has no stored file", book_id);
+            error!("{}", msg);
+            AnnotationExportCommandError::InvalidInput(msg)
+        })?;
+
+        if document.mime_type != "application/pdf" {
+            let msg = format!("Book with ID {} is not a PDF", book_id);
+            error!("{}", msg);
+            return Err(AnnotationExportCommandError::InvalidInput(msg));
+        }
+
+        let source = PathBuf::from(&document.file_path);
+        let destination = PathBuf::from(&output_path);
+        let same_file = match (fs::canonicalize(&source), fs::canonicalize(&destination)) {
+            (Ok(source), Ok(destination)) => source == destination,
+            _ => source == destination,
+        };
+        if same_file {
+            let msg = "The export cannot overwrite the library copy of the book".to_string();
+            error!("{}", msg);
+            return Err(AnnotationExportCommandError::InvalidInput(msg));
+        }
+
+        let annotations = AnnotationRepository::new(self.conn).get_annotations_by_book_id(book_id, None)?;
+        Ok(AnnotatedPdfExport { book_id, source, destination, output_path, annotations })
+    }
+
+    /// Loads a JSON export back. The target book is `book_id` when given,
+    /// otherwise the book whose stored file matches the exported hash.
+    /// Annotations already present are skipped, so importing twice is harmless.
+    pub fn import_annotations_method(
+        &mut self,
+        path: String,
+        book_id: Option<i64>,
+    ) -> Result<AnnotationImportSummary, AnnotationExportCommandError> {
+        info!("Importing annotations from {}", path);
+
+        let contents = fs::read_to_string(Path::new(&path)).map_err(|err| {
+            error!("Failed to read {}: {}", path, err);
+            AnnotationExportCommandError::FileError(err.to_string())
+        })?;
+        let export = parse_json(&contents).map_err(|err| {
+            error!("Invalid annotation export {}: {}", path, err);
+            AnnotationExportCommandError::InvalidInput(err)
+        })?;
+
+        let book_id = match book_id {
+            Some(book_id) => self.get_book(book_id)?.id,
+            None => self.find_book_for_export(&export)?,
+        };
+        // Annotations read from the exported book's PDF only point into that file.
+        let book = self.get_book(book_id)?;
+        let same_file = export.document_hash.is_some() && self.document_hash(&book)? == export.document_hash;
+
+        let library_tags = TagRepository::new(self.conn).get_all_tags()?;
+        let existing = AnnotationRepository::new(self.conn).get_annotations_by_book_id(book_id, None)?;
+
+        let tx = self.conn.transaction()?;
+        let tags_by_title = Self::resolve_tags(&tx, library_tags, &export)?;
+        let mut imported = 0;
+        let mut skipped = 0;
+
+        for mut annotation in export.annotations {
+            if existing.iter().any(|other| same_annotation(other, &annotation)) {
+                skipped += 1;
+                continue;
+            }
+
+            annotation.id = None;
+            annotation.book_id = book_id;
+            if !same_file {
+                annotation.pdf_ref = None;
+            }
+            annotation.tags = annotation.tags.map(|tags| {
+                tags.iter()
+                    .filter_map(|tag| tags_by_title.iter().find(|t| t.title == tag.title).cloned())
+                    .collect()
+            });
+            AnnotationRepository::insert_annotation(&tx, &annotation)?;
+            imported += 1;
+        }
+
+        tx.commit()?;
+
+        info!("Imported {} annotations into book {} ({} skipped)", imported, book_id, skipped);
+        Ok(AnnotationImportSummary { book_id, imported, skipped })
+    }
+
+    fn find_book_for_export(&mut self, export: &AnnotationExport) -> Result<i64, AnnotationExportCommandError> {
+        let not_found = || {
+            let msg = format!("No book in the library matches '{}'; choose one to import into", export.book_title);
+            error!("{}", msg);
+            AnnotationExportCommandError::InvalidInput(msg)
+        };
+
+        let hash = export.document_hash.as_deref().ok_or_else(not_found)?;
+        let document = DocumentRepository::new(self.conn)
+            .find_by_hash(hash)
+            .map_err(AnnotationExportCommandError::DatabaseError)?
+            .ok_or_else(not_found)?;
+
+        BookRepository::new(self.conn)
+            .get_book_by_document_id(document.id as i64)?
+            .map(|book| book.id)
+            .ok_or_else(not_found)
+    }
+
+    /// Maps exported tag titles onto library tags, creating the missing ones
+    /// in the import transaction.
+    fn resolve_tags(
+        conn: &Connection,
+        mut tags: Vec<Tag>,
+        export: &AnnotationExport,
+    ) -> Result<Vec<Tag>, AnnotationExportCommandError> {
+        for tag in export.annotations.iter().flat_map(|a| a.tags.iter().flatten()) {
+            if tags.iter().any(|t| t.title == tag.title) {
+                continue;
+            }
+            let id = TagRepository::insert_tag(conn, tag)?;
+            tags.push(Tag { id: Some(id as i32), ..tag.clone() });
+        }
+
+        Ok(tags)
+    }
+}
+
+fn same_annotation(a: &Annotation, b: &Annotation) -> bool {
+    a.kind == b.kind
+        && a.page_number == b.page_number
+        && a.chapter_index == b.chapter_index
+        && a.location == b.location
+        && a.start_offset == b.start_offset
+        && a.end_offset == b.end_offset
+        && a.selected_text == b.selected_text
+        && a.note == b.note
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn export_annotations_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: Option<i64>,
+    tag_id: Option<i32>,
+    format: String,
+    output_dir: String,
+) -> Result<Vec<ExportedAnnotationFile>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut export_commands = AnnotationExportCommands::new(&mut conn);
+
+    match export_commands.export_annotations_method(book_id, tag_id, format, output_dir) {
+        Ok(files) => Ok(files),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn export_annotated_pdf_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+    output_path: String,
+) -> Result<ExportedAnnotationFile, InvokeError> {
+    let export = {
+        let mut conn = app_state.db_conn.lock().unwrap();
+        let mut export_commands = AnnotationExportCommands::new(&mut conn);
+        export_commands.prepare_annotated_pdf_method(book_id, output_path)
+    };
+
+    match export.and_then(AnnotatedPdfExport::write) {
+        Ok(file) => Ok(file),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn import_annotations_command(
+    app_state: tauri::State<'_, AppState>,
+    path: String,
+    book_id: Option<i64>,
+) -> Result<AnnotationImportSummary, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut export_commands = AnnotationExportCommands::new(&mut conn);
+
+    match export_commands.import_annotations_method(path, book_id) {
+        Ok(summary) => Ok(summary),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
@@ -53,6 +53,7 @@ impl<'a> BookCommands<'a> {
             title,
             author,
             file_path,
+            document_id: None,
             tags: None,
         };
 
@@ -98,6 +99,7 @@ impl<'a> BookCommands<'a> {
             title: title.unwrap_or(existing_book.title),
             author: author.or(existing_book.author),
             file_path: file_path.or(existing_book.file_path),
+            document_id: existing_book.document_id,
             tags: existing_book.tags,
         };
 
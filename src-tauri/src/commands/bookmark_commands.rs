@@ -0,0 +1,320 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use crate::db::repositories::{BookRepository, BookmarkRepository};
+use crate::db::models::bookmark::Bookmark;
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+const DEFAULT_BOOKMARK_LIMIT: i64 = 50;
+
+#[derive(Debug, Error, Serialize)]
+pub enum BookmarkCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for BookmarkCommandError {
+    fn from(err: RusqliteError) -> Self {
+        BookmarkCommandError::DatabaseError(err.to_string())
+    }
+}
+
+pub struct BookmarkCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> BookmarkCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn get_existing(&mut self, id: i64) -> Result<Bookmark, BookmarkCommandError> {
+        BookmarkRepository::new(self.conn).get_bookmark_by_id(id)?.ok_or_else(|| {
+            let msg = format!("Bookmark with ID {} not found", id);
+            error!("{}", msg);
+            BookmarkCommandError::InvalidInput(msg)
+        })
+    }
+
+    fn validate(bookmark: &Bookmark) -> Result<(), BookmarkCommandError> {
+        if bookmark.name.trim().is_empty() {
+            let msg = "You must provide a name".to_string();
+            error!("{}", msg);
+            return Err(BookmarkCommandError::InvalidInput(msg));
+        }
+
+        if bookmark.page_number.is_none() && bookmark.chapter_index.is_none() && bookmark.location.is_none() {
+            let msg = "A page, chapter or location must be provided".to_string();
+            error!("{}", msg);
+            return Err(BookmarkCommandError::InvalidInput(msg));
+        }
+
+        if bookmark.page_number.is_some_and(|page| page < 1) {
+            let msg = "Page numbers start at 1".to_string();
+            error!("{}", msg);
+            return Err(BookmarkCommandError::InvalidInput(msg));
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_bookmark_method(
+        &mut self,
+        book_id: i64,
+        name: String,
+        page_number: Option<i32>,
+        chapter_index: Option<i32>,
+        location: Option<String>,
+        note: Option<String>,
+        color: Option<String>,
+    ) -> Result<Bookmark, BookmarkCommandError> {
+        info!("Creating bookmark '{}' in book {}", name, book_id);
+
+        let bookmark = Bookmark {
+            id: None,
+            book_id,
+            book_title: None,
+            name: name.trim().to_string(),
+            page_number,
+            chapter_index,
+            location,
+            note,
+            color,
+            created_at: None,
+            last_opened_at: None,
+        };
+        Self::validate(&bookmark)?;
+
+        if BookRepository::new(self.conn).get_book_by_id(book_id)?.is_none() {
+            let msg = format!("Book with ID {} not found", book_id);
+            error!("{}", msg);
+            return Err(BookmarkCommandError::InvalidInput(msg));
+        }
+
+        let id = match BookmarkRepository::new(self.conn).create_bookmark(&bookmark) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("Failed to create bookmark in book {}: {}", book_id, err);
+                return Err(BookmarkCommandError::DatabaseError(err.to_string()));
+            }
+        };
+
+        info!("Bookmark created with ID {}", id);
+        self.get_existing(id)
+    }
+
+    pub fn update_bookmark_method(
+        &mut self,
+        id: i64,
+        name: Option<String>,
+        note: Option<String>,
+        color: Option<String>,
+        clear_note: bool,
+        clear_color: bool,
+    ) -> Result<Bookmark, BookmarkCommandError> {
+        info!("Updating bookmark {}", id);
+
+        if (clear_note && note.is_some()) || (clear_color && color.is_some()) {
+            let msg = "A field cannot be set and cleared at once".to_string();
+            error!("{}", msg);
+            return Err(BookmarkCommandError::InvalidInput(msg));
+        }
+
+        let mut bookmark = self.get_existing(id)?;
+        if let Some(name) = name {
+            bookmark.name = name.trim().to_string();
+        }
+        if clear_note {
+            bookmark.note = None;
+        } else if let Some(note) = note {
+            bookmark.note = Some(note);
+        }
+        if clear_color {
+            bookmark.color = None;
+        } else if let Some(color) = color {
+            bookmark.color = Some(color);
+        }
+        Self::validate(&bookmark)?;
+
+        if let Err(err) = BookmarkRepository::new(self.conn).update_bookmark(&bookmark) {
+            error!("Failed to update bookmark {}: {}", id, err);
+            return Err(BookmarkCommandError::DatabaseError(err.to_string()));
+        }
+
+        self.get_existing(id)
+    }
+
+    pub fn delete_bookmark_method(&mut self, id: i64) -> Result<String, BookmarkCommandError> {
+        info!("Deleting bookmark {}", id);
+
+        match BookmarkRepository::new(self.conn).delete_bookmark(id) {
+            Ok(0) => {
+                let msg = format!("Bookmark with ID {} not found", id);
+                error!("{}", msg);
+                Err(BookmarkCommandError::InvalidInput(msg))
+            }
+            Ok(_) => {
+                let success_msg = format!("Bookmark {} deleted successfully", id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to delete bookmark {}: {}", id, err);
+                Err(BookmarkCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Returns the bookmark so the reader can jump to it, and bumps it to the
+    /// top of the recent list.
+    pub fn open_bookmark_method(&mut self, id: i64) -> Result<Bookmark, BookmarkCommandError> {
+        info!("Opening bookmark {}", id);
+
+        if BookmarkRepository::new(self.conn).mark_opened(id)? == 0 {
+            let msg = format!("Bookmark with ID {} not found", id);
+            error!("{}", msg);
+            return Err(BookmarkCommandError::InvalidInput(msg));
+        }
+
+        self.get_existing(id)
+    }
+
+    pub fn list_book_bookmarks_method(&mut self, book_id: i64) -> Result<Vec<Bookmark>, BookmarkCommandError> {
+        info!("Listing bookmarks of book {}", book_id);
+
+        match BookmarkRepository::new(self.conn).get_bookmarks_by_book_id(book_id) {
+            Ok(bookmarks) => Ok(bookmarks),
+            Err(err) => {
+                error!("Failed to list bookmarks of book {}: {}", book_id, err);
+                Err(BookmarkCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn list_recent_bookmarks_method(&mut self, limit: Option<i64>) -> Result<Vec<Bookmark>, BookmarkCommandError> {
+        info!("Listing recent bookmarks");
+
+        let limit = limit.unwrap_or(DEFAULT_BOOKMARK_LIMIT).max(1);
+
+        match BookmarkRepository::new(self.conn).get_recent_bookmarks(limit) {
+            Ok(bookmarks) => Ok(bookmarks),
+            Err(err) => {
+                error!("Failed to list recent bookmarks: {}", err);
+                Err(BookmarkCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_bookmark_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+    name: String,
+    page_number: Option<i32>,
+    chapter_index: Option<i32>,
+    location: Option<String>,
+    note: Option<String>,
+    color: Option<String>,
+) -> Result<Bookmark, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut bookmark_commands = BookmarkCommands::new(&mut conn);
+
+    match bookmark_commands.create_bookmark_method(book_id, name, page_number, chapter_index, location, note, color) {
+        Ok(bookmark) => Ok(bookmark),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn update_bookmark_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i64,
+    name: Option<String>,
+    note: Option<String>,
+    color: Option<String>,
+    clear_note: Option<bool>,
+    clear_color: Option<bool>,
+) -> Result<Bookmark, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut bookmark_commands = BookmarkCommands::new(&mut conn);
+
+    match bookmark_commands.update_bookmark_method(
+        id,
+        name,
+        note,
+        color,
+        clear_note.unwrap_or(false),
+        clear_color.unwrap_or(false),
+    ) {
+        Ok(bookmark) => Ok(bookmark),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_bookmark_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut bookmark_commands = BookmarkCommands::new(&mut conn);
+
+    match bookmark_commands.delete_bookmark_method(id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn open_bookmark_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<Bookmark, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut bookmark_commands = BookmarkCommands::new(&mut conn);
+
+    match bookmark_commands.open_bookmark_method(id) {
+        Ok(bookmark) => Ok(bookmark),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_book_bookmarks_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+) -> Result<Vec<Bookmark>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut bookmark_commands = BookmarkCommands::new(&mut conn);
+
+    match bookmark_commands.list_book_bookmarks_method(book_id) {
+        Ok(bookmarks) => Ok(bookmarks),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_recent_bookmarks_command(
+    app_state: tauri::State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<Bookmark>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut bookmark_commands = BookmarkCommands::new(&mut conn);
+
+    match bookmark_commands.list_recent_bookmarks_method(limit) {
+        Ok(bookmarks) => Ok(bookmarks),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
@@ -0,0 +1,143 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use std::path::Path;
+use crate::db::repositories::{BookRepository, ChapterRepository, DocumentRepository};
+use crate::db::models::{chapter::Chapter, document::Document};
+use crate::services::read_chapter_text;
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+#[derive(Debug, Error, Serialize)]
+pub enum ChapterCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("File error: {0}")]
+    FileError(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for ChapterCommandError {
+    fn from(err: RusqliteError) -> Self {
+        ChapterCommandError::DatabaseError(err.to_string())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChapterText {
+    pub chapter: Chapter,
+    pub text: String,
+}
+
+pub struct ChapterCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ChapterCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn document_for_book(&mut self, book_id: i64) -> Result<Document, ChapterCommandError> {
+        let book = BookRepository::new(self.conn).get_book_by_id(book_id)?.ok_or_else(|| {
+            let msg = format!("Book with ID {} not found", book_id);
+            error!("{}", msg);
+            ChapterCommandError::InvalidInput(msg)
+        })?;
+
+        let document_id = book.document_id.ok_or_else(|| {
+            let msg = format!("Book with ID {} has no stored file", book_id);
+            error!("{}", msg);
+            ChapterCommandError::InvalidInput(msg)
+        })?;
+
+        DocumentRepository::new(self.conn)
+            .get_by_id(document_id as i32)
+            .map_err(ChapterCommandError::DatabaseError)?
+            .ok_or_else(|| {
+                let msg = format!("Document with ID {} not found", document_id);
+                error!("{}", msg);
+                ChapterCommandError::InvalidInput(msg)
+            })
+    }
+
+    pub fn list_book_chapters_method(&mut self, book_id: i64) -> Result<Vec<Chapter>, ChapterCommandError> {
+        info!("Listing chapters of book {}", book_id);
+
+        let document = self.document_for_book(book_id)?;
+
+        match ChapterRepository::new(self.conn).get_chapters_by_document_id(document.id as i64) {
+            Ok(chapters) => {
+                info!("Found {} chapters for book {}", chapters.len(), book_id);
+                Ok(chapters)
+            }
+            Err(err) => {
+                error!("Failed to list chapters of book {}: {}", book_id, err);
+                Err(ChapterCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn get_chapter_text_method(
+        &mut self,
+        book_id: i64,
+        chapter_index: i32,
+    ) -> Result<ChapterText, ChapterCommandError> {
+        info!("Fetching chapter {} of book {}", chapter_index, book_id);
+
+        let document = self.document_for_book(book_id)?;
+
+        let chapter = ChapterRepository::new(self.conn)
+            .get_chapter(document.id as i64, chapter_index)?
+            .ok_or_else(|| {
+                let msg = format!("Chapter {} not found in book {}", chapter_index, book_id);
+                error!("{}", msg);
+                ChapterCommandError::InvalidInput(msg)
+            })?;
+
+        match read_chapter_text(Path::new(&document.file_path), &chapter.href) {
+            Ok(text) => Ok(ChapterText { chapter, text }),
+            Err(err) => {
+                error!("Failed to read chapter {} of book {}: {}", chapter_index, book_id, err);
+                Err(ChapterCommandError::FileError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn list_book_chapters_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+) -> Result<Vec<Chapter>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut chapter_commands = ChapterCommands::new(&mut conn);
+
+    match chapter_commands.list_book_chapters_method(book_id) {
+        Ok(chapters) => Ok(chapters),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_chapter_text_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+    chapter_index: i32,
+) -> Result<ChapterText, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut chapter_commands = ChapterCommands::new(&mut conn);
+
+    match chapter_commands.get_chapter_text_method(book_id, chapter_index) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
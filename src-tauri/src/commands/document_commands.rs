@@ -2,6 +2,8 @@ use crate::db::repositories::document_repository::DocumentRepository;
 use crate::db::models::document::Document;
 use rusqlite::Connection;
 use chrono::Utc;
+use crate::AppState;
+use tauri::ipc::InvokeError;
 
 pub struct DocumentCommands<'a> {
     repo: DocumentRepository<'a>,
@@ -13,7 +15,10 @@ impl<'a> DocumentCommands<'a> {
         Self { repo }
     }
 
-    pub fn create_document(&self, document: Document) -> Result<(), String> {
+    pub fn create_document(&self, document: Document) -> Result<i64, String> {
+        if document.title.trim().is_empty() {
+            return Err("You must provide a title".to_string());
+        }
         self.repo.create(&document)
     }
 
@@ -34,12 +39,7 @@ impl<'a> DocumentCommands<'a> {
     }
 
     pub fn search_documents_by_title(&self, title: &str) -> Result<Vec<Document>, String> {
-        let all_documents = self.repo.get_all()?;
-        let filtered_documents: Vec<Document> = all_documents
-            .into_iter()
-            .filter(|doc| doc.title.to_lowercase().contains(&title.to_lowercase()))
-            .collect();
-        Ok(filtered_documents)
+        self.repo.search_by_title(title)
     }
 
     pub fn mark_document_as_accessed(&self, id: i32) -> Result<(), String> {
@@ -50,5 +50,136 @@ impl<'a> DocumentCommands<'a> {
             Err("Document not found".to_string())
         }
     }
-    
+}
+
+// Tauri commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_document_command(
+    app_state: tauri::State<'_, AppState>,
+    title: String,
+    original_filename: String,
+    stored_filename: String,
+    file_path: String,
+    file_size: String,
+    mime_type: String,
+    hash: String,
+    page_count: i32,
+    thumbnail_path: Option<String>,
+    tags: Option<Vec<String>>,
+    subject: Option<String>,
+    keywords: Option<Vec<String>>,
+    language: Option<String>,
+    identifiers: Option<Vec<String>>,
+) -> Result<i64, InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    let document = Document {
+        id: 0,
+        title,
+        original_filename,
+        stored_filename,
+        file_path,
+        file_size,
+        mime_type,
+        hash,
+        page_count,
+        created_at: Some(Utc::now()),
+        last_accessed: None,
+        thumbnail_path,
+        tags,
+        subject,
+        keywords,
+        language,
+        identifiers,
+    };
+
+    match document_commands.create_document(document) {
+        Ok(id) => Ok(id),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_document_by_id_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+) -> Result<Option<Document>, InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    match document_commands.get_document_by_id(id) {
+        Ok(document) => Ok(document),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_all_documents_command(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<Document>, InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    match document_commands.list_all_documents() {
+        Ok(documents) => Ok(documents),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn update_document_command(
+    app_state: tauri::State<'_, AppState>,
+    document: Document,
+) -> Result<(), InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    match document_commands.update_document(document) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_document_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+) -> Result<(), InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    match document_commands.delete_document(id) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn search_documents_by_title_command(
+    app_state: tauri::State<'_, AppState>,
+    title: String,
+) -> Result<Vec<Document>, InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    match document_commands.search_documents_by_title(&title) {
+        Ok(documents) => Ok(documents),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn mark_document_as_accessed_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+) -> Result<(), InvokeError> {
+    let conn = app_state.db_conn.lock().unwrap();
+    let document_commands = DocumentCommands::new(&conn);
+
+    match document_commands.mark_document_as_accessed(id) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(InvokeError::from(err)),
+    }
 }
@@ -0,0 +1,642 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use chrono::{Duration, Local, NaiveDate, Utc};
+use crate::db::repositories::{AnnotationRepository, BookRepository, FlashcardRepository};
+use crate::db::models::flashcard::{
+    Card, CardReview, CardState, ClozeSpan, Deck, DeckStatistics, ReviewGrade, SchedulerKind,
+};
+use crate::db::models::tag::Tag;
+use crate::services::{cloze, recurrence, spaced_repetition};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+/// How many due cards are returned when no limit is given.
+const DEFAULT_DUE_LIMIT: i64 = 100;
+/// Days of review history the deck retention rate covers.
+const RETENTION_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Error, Serialize)]
+pub enum FlashcardCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for FlashcardCommandError {
+    fn from(err: RusqliteError) -> Self {
+        FlashcardCommandError::DatabaseError(err.to_string())
+    }
+}
+
+fn invalid_input(msg: String) -> FlashcardCommandError {
+    error!("{}", msg);
+    FlashcardCommandError::InvalidInput(msg)
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+fn parse_scheduler(scheduler: &str) -> Result<SchedulerKind, FlashcardCommandError> {
+    SchedulerKind::parse(scheduler)
+        .ok_or_else(|| invalid_input(format!("Invalid scheduler provided: {}", scheduler)))
+}
+
+pub struct FlashcardCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> FlashcardCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn get_deck(&mut self, deck_id: i64) -> Result<Deck, FlashcardCommandError> {
+        FlashcardRepository::new(self.conn)
+            .get_deck_by_id(deck_id)?
+            .ok_or_else(|| invalid_input(format!("Deck with ID {} not found", deck_id)))
+    }
+
+    fn get_card(&mut self, card_id: i64) -> Result<Card, FlashcardCommandError> {
+        FlashcardRepository::new(self.conn)
+            .get_card_by_id(card_id)?
+            .ok_or_else(|| invalid_input(format!("Card with ID {} not found", card_id)))
+    }
+
+    /// Tags a card made from `book_id` starts out with.
+    fn book_tags(&mut self, book_id: Option<i64>) -> Result<Option<Vec<Tag>>, FlashcardCommandError> {
+        match book_id {
+            Some(book_id) => Ok(Some(BookRepository::new(self.conn).get_tags_by_book_id(book_id)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn validate_deck_name(&mut self, name: &str, deck_id: Option<i64>) -> Result<(), FlashcardCommandError> {
+        if name.trim().is_empty() {
+            return Err(invalid_input("Deck name cannot be empty".to_string()));
+        }
+
+        let existing = FlashcardRepository::new(self.conn).get_deck_by_name(name.trim())?;
+        if existing.is_some_and(|deck| deck.id != deck_id) {
+            return Err(invalid_input(format!("A deck named '{}' already exists", name.trim())));
+        }
+        Ok(())
+    }
+
+    fn validate_card(&mut self, card: &Card) -> Result<(), FlashcardCommandError> {
+        if card.front.trim().is_empty() || card.back.trim().is_empty() {
+            return Err(invalid_input("Both sides of a card must have text".to_string()));
+        }
+
+        self.get_deck(card.deck_id)?;
+
+        if let Some(book_id) = card.book_id {
+            if BookRepository::new(self.conn).get_book_by_id(book_id)?.is_none() {
+                return Err(invalid_input(format!("Book with ID {} not found", book_id)));
+            }
+        }
+        match card.page_number {
+            Some(page) if page < 1 => Err(invalid_input(format!("Invalid page number: {}", page))),
+            Some(_) if card.book_id.is_none() => {
+                Err(invalid_input("A page number needs a source book".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn create_deck_method(
+        &mut self,
+        name: String,
+        description: Option<String>,
+        scheduler: Option<String>,
+    ) -> Result<Deck, FlashcardCommandError> {
+        info!("Creating deck: {}", name);
+
+        self.validate_deck_name(&name, None)?;
+        let deck = Deck {
+            id: None,
+            name: name.trim().to_string(),
+            description,
+            scheduler: match scheduler.as_deref() {
+                Some(scheduler) => parse_scheduler(scheduler)?,
+                None => SchedulerKind::Sm2,
+            },
+            created_at: None,
+        };
+
+        match FlashcardRepository::new(self.conn).create_deck(&deck) {
+            Ok(deck_id) => {
+                info!("Deck created with ID: {}", deck_id);
+                self.get_deck(deck_id)
+            }
+            Err(err) => {
+                error!("Failed to create deck: {}", err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Renames a deck or switches its scheduler. Cards keep their state, so a
+    /// switch takes effect from each card's next review.
+    pub fn update_deck_method(
+        &mut self,
+        deck_id: i64,
+        name: Option<String>,
+        description: Option<String>,
+        scheduler: Option<String>,
+    ) -> Result<Deck, FlashcardCommandError> {
+        info!("Updating deck {}", deck_id);
+
+        let mut deck = self.get_deck(deck_id)?;
+        if let Some(name) = name {
+            self.validate_deck_name(&name, Some(deck_id))?;
+            deck.name = name.trim().to_string();
+        }
+        if description.is_some() {
+            deck.description = description;
+        }
+        if let Some(scheduler) = scheduler {
+            deck.scheduler = parse_scheduler(&scheduler)?;
+        }
+
+        match FlashcardRepository::new(self.conn).update_deck(&deck) {
+            Ok(_) => Ok(deck),
+            Err(err) => {
+                error!("Failed to update deck {}: {}", deck_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Deletes the deck with all its cards and their review history.
+    pub fn delete_deck_method(&mut self, deck_id: i64) -> Result<String, FlashcardCommandError> {
+        info!("Deleting deck {}", deck_id);
+
+        match FlashcardRepository::new(self.conn).delete_deck(deck_id) {
+            Ok(0) => Err(invalid_input(format!("Deck with ID {} not found", deck_id))),
+            Ok(_) => {
+                let success_msg = format!("Deck {} deleted successfully", deck_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to delete deck {}: {}", deck_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn list_decks_method(&mut self) -> Result<Vec<Deck>, FlashcardCommandError> {
+        info!("Listing decks");
+
+        match FlashcardRepository::new(self.conn).get_decks() {
+            Ok(decks) => Ok(decks),
+            Err(err) => {
+                error!("Failed to list decks: {}", err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Adds a card to a deck. New cards are due right away and carry the tags
+    /// of their source book.
+    pub fn create_card_method(
+        &mut self,
+        deck_id: i64,
+        front: String,
+        back: String,
+        book_id: Option<i64>,
+        page_number: Option<i32>,
+    ) -> Result<Card, FlashcardCommandError> {
+        info!("Creating card in deck {}", deck_id);
+
+        let card = Card {
+            id: None,
+            deck_id,
+            front,
+            back,
+            book_id,
+            page_number,
+            due_date: recurrence::format_date(today()),
+            state: CardState::default(),
+            created_at: None,
+            last_reviewed_at: None,
+            annotation_id: None,
+            cloze_text: None,
+            anki_guid: None,
+            anki_ord: None,
+            tags: None,
+        };
+        self.save_new_card(card)
+    }
+
+    fn save_new_card(&mut self, mut card: Card) -> Result<Card, FlashcardCommandError> {
+        self.validate_card(&card)?;
+        card.tags = self.book_tags(card.book_id)?;
+
+        match FlashcardRepository::new(self.conn).create_card(&card) {
+            Ok(card_id) => {
+                info!("Card created with ID: {}", card_id);
+                self.get_card(card_id)
+            }
+            Err(err) => {
+                error!("Failed to create card in deck {}: {}", card.deck_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Makes a cloze card from a highlight, blanking out `spans` of its text.
+    /// The card points back at the highlight, its book and page.
+    pub fn create_cloze_card_method(
+        &mut self,
+        annotation_id: i64,
+        deck_id: i64,
+        spans: Vec<ClozeSpan>,
+    ) -> Result<Card, FlashcardCommandError> {
+        info!("Creating cloze card from annotation {} in deck {}", annotation_id, deck_id);
+
+        let annotation = AnnotationRepository::new(self.conn)
+            .get_annotation_by_id(annotation_id)?
+            .ok_or_else(|| invalid_input(format!("Annotation with ID {} not found", annotation_id)))?;
+        let text = annotation.selected_text
+            .filter(|text| !text.trim().is_empty())
+            .ok_or_else(|| invalid_input(format!("Annotation {} has no highlighted text", annotation_id)))?;
+
+        let markup = cloze::cloze_markup(&text, &spans).map_err(invalid_input)?;
+        let (front, back) = cloze::render_cloze(&markup);
+
+        let card = Card {
+            id: None,
+            deck_id,
+            front,
+            back,
+            book_id: Some(annotation.book_id),
+            page_number: annotation.page_number,
+            due_date: recurrence::format_date(today()),
+            state: CardState::default(),
+            created_at: None,
+            last_reviewed_at: None,
+            annotation_id: Some(annotation_id),
+            cloze_text: Some(markup),
+            anki_guid: None,
+            anki_ord: None,
+            tags: None,
+        };
+        self.save_new_card(card)
+    }
+
+    /// Cards made from a highlight, so the reader can show which passages
+    /// already have one.
+    pub fn list_annotation_cards_method(&mut self, annotation_id: i64) -> Result<Vec<Card>, FlashcardCommandError> {
+        info!("Listing cards made from annotation {}", annotation_id);
+
+        match FlashcardRepository::new(self.conn).get_cards_by_annotation_id(annotation_id) {
+            Ok(cards) => Ok(cards),
+            Err(err) => {
+                error!("Failed to list cards of annotation {}: {}", annotation_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Edits a card's text, source or deck. Its schedule is left as it is.
+    /// Rewriting either side of a cloze card turns it into a plain card.
+    pub fn update_card_method(
+        &mut self,
+        card_id: i64,
+        deck_id: Option<i64>,
+        front: Option<String>,
+        back: Option<String>,
+        book_id: Option<i64>,
+        page_number: Option<i32>,
+    ) -> Result<Card, FlashcardCommandError> {
+        info!("Updating card {}", card_id);
+
+        let mut card = self.get_card(card_id)?;
+        if let Some(deck_id) = deck_id {
+            card.deck_id = deck_id;
+        }
+        if front.is_some() || back.is_some() {
+            card.cloze_text = None;
+        }
+        if let Some(front) = front {
+            card.front = front;
+        }
+        if let Some(back) = back {
+            card.back = back;
+        }
+        if book_id.is_some() {
+            card.book_id = book_id;
+        }
+        if page_number.is_some() {
+            card.page_number = page_number;
+        }
+        self.validate_card(&card)?;
+
+        match FlashcardRepository::new(self.conn).update_card_content(&card) {
+            Ok(_) => Ok(card),
+            Err(err) => {
+                error!("Failed to update card {}: {}", card_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn delete_card_method(&mut self, card_id: i64) -> Result<String, FlashcardCommandError> {
+        info!("Deleting card {}", card_id);
+
+        match FlashcardRepository::new(self.conn).delete_card(card_id) {
+            Ok(0) => Err(invalid_input(format!("Card with ID {} not found", card_id))),
+            Ok(_) => {
+                let success_msg = format!("Card {} deleted successfully", card_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to delete card {}: {}", card_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Cards due today or earlier, most overdue first. Without `deck_id`,
+    /// cards from every deck are returned.
+    pub fn get_due_cards_method(
+        &mut self,
+        deck_id: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Card>, FlashcardCommandError> {
+        info!("Fetching due cards");
+
+        if let Some(deck_id) = deck_id {
+            self.get_deck(deck_id)?;
+        }
+        let limit = limit.unwrap_or(DEFAULT_DUE_LIMIT);
+        if limit < 1 {
+            return Err(invalid_input(format!("Invalid limit: {}", limit)));
+        }
+
+        let today = recurrence::format_date(today());
+        match FlashcardRepository::new(self.conn).get_due_cards(deck_id, &today, limit) {
+            Ok(cards) => Ok(cards),
+            Err(err) => {
+                error!("Failed to fetch due cards: {}", err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Grades a review of the card with its deck's scheduler, logs the review
+    /// and returns the card with its next due date.
+    pub fn grade_card_method(&mut self, card_id: i64, grade: String) -> Result<Card, FlashcardCommandError> {
+        info!("Grading card {} as {}", card_id, grade);
+
+        let grade = ReviewGrade::parse(&grade)
+            .ok_or_else(|| invalid_input(format!("Invalid grade provided: {}", grade)))?;
+        let mut card = self.get_card(card_id)?;
+        let deck = self.get_deck(card.deck_id)?;
+
+        let now = Utc::now();
+        let today = now.with_timezone(&Local).date_naive();
+        let elapsed_days = card.last_reviewed_at
+            .map(|reviewed_at| (today - reviewed_at.with_timezone(&Local).date_naive()).num_days().max(0) as u32)
+            .unwrap_or(0);
+
+        card.state = spaced_repetition::review(deck.scheduler, &card.state, grade, elapsed_days);
+        card.due_date = recurrence::format_date(today + Duration::days(card.state.interval_days as i64));
+        card.last_reviewed_at = Some(now);
+
+        let review = CardReview {
+            id: None,
+            card_id,
+            grade,
+            scheduler: deck.scheduler,
+            elapsed_days,
+            interval_days: card.state.interval_days,
+            reviewed_at: now,
+        };
+
+        match FlashcardRepository::new(self.conn).record_review(&card, &review) {
+            Ok(_) => {
+                info!("Card {} is next due on {}", card_id, card.due_date);
+                Ok(card)
+            }
+            Err(err) => {
+                error!("Failed to record review of card {}: {}", card_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn get_deck_statistics_method(&mut self, deck_id: i64) -> Result<DeckStatistics, FlashcardCommandError> {
+        info!("Fetching statistics for deck {}", deck_id);
+
+        self.get_deck(deck_id)?;
+        let today = today();
+        let since = today - Duration::days(RETENTION_WINDOW_DAYS - 1);
+
+        match FlashcardRepository::new(self.conn).get_deck_statistics(
+            deck_id,
+            &recurrence::format_date(today),
+            &recurrence::format_date(since),
+        ) {
+            Ok(statistics) => Ok(statistics),
+            Err(err) => {
+                error!("Failed to fetch statistics for deck {}: {}", deck_id, err);
+                Err(FlashcardCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn create_deck_command(
+    app_state: tauri::State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+    scheduler: Option<String>,
+) -> Result<Deck, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.create_deck_method(name, description, scheduler) {
+        Ok(deck) => Ok(deck),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn update_deck_command(
+    app_state: tauri::State<'_, AppState>,
+    deck_id: i64,
+    name: Option<String>,
+    description: Option<String>,
+    scheduler: Option<String>,
+) -> Result<Deck, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.update_deck_method(deck_id, name, description, scheduler) {
+        Ok(deck) => Ok(deck),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_deck_command(
+    app_state: tauri::State<'_, AppState>,
+    deck_id: i64,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.delete_deck_method(deck_id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_decks_command(app_state: tauri::State<'_, AppState>) -> Result<Vec<Deck>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.list_decks_method() {
+        Ok(decks) => Ok(decks),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn create_card_command(
+    app_state: tauri::State<'_, AppState>,
+    deck_id: i64,
+    front: String,
+    back: String,
+    book_id: Option<i64>,
+    page_number: Option<i32>,
+) -> Result<Card, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.create_card_method(deck_id, front, back, book_id, page_number) {
+        Ok(card) => Ok(card),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn create_cloze_card_command(
+    app_state: tauri::State<'_, AppState>,
+    annotation_id: i64,
+    deck_id: i64,
+    spans: Vec<ClozeSpan>,
+) -> Result<Card, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.create_cloze_card_method(annotation_id, deck_id, spans) {
+        Ok(card) => Ok(card),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_annotation_cards_command(
+    app_state: tauri::State<'_, AppState>,
+    annotation_id: i64,
+) -> Result<Vec<Card>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.list_annotation_cards_method(annotation_id) {
+        Ok(cards) => Ok(cards),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn update_card_command(
+    app_state: tauri::State<'_, AppState>,
+    card_id: i64,
+    deck_id: Option<i64>,
+    front: Option<String>,
+    back: Option<String>,
+    book_id: Option<i64>,
+    page_number: Option<i32>,
+) -> Result<Card, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.update_card_method(card_id, deck_id, front, back, book_id, page_number) {
+        Ok(card) => Ok(card),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_card_command(
+    app_state: tauri::State<'_, AppState>,
+    card_id: i64,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.delete_card_method(card_id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_due_cards_command(
+    app_state: tauri::State<'_, AppState>,
+    deck_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<Card>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.get_due_cards_method(deck_id, limit) {
+        Ok(cards) => Ok(cards),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn grade_card_command(
+    app_state: tauri::State<'_, AppState>,
+    card_id: i64,
+    grade: String,
+) -> Result<Card, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.grade_card_method(card_id, grade) {
+        Ok(card) => Ok(card),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_deck_statistics_command(
+    app_state: tauri::State<'_, AppState>,
+    deck_id: i64,
+) -> Result<DeckStatistics, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut flashcard_commands = FlashcardCommands::new(&mut conn);
+
+    match flashcard_commands.get_deck_statistics_method(deck_id) {
+        Ok(statistics) => Ok(statistics),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
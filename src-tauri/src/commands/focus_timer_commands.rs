@@ -0,0 +1,248 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use std::sync::Arc;
+use crate::db::repositories::{BookRepository, PomodoroRepository, TaskRepository, UserRepository};
+use crate::db::models::pomodoro::Pomodoro;
+use crate::services::focus_timer::{FocusNotifier, FocusTimer, FocusTimerConfig, FocusTimerError, FocusTimerStatus};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+use tauri::Emitter;
+
+const DEFAULT_POMODORO_LIMIT: i64 = 50;
+
+#[derive(Debug, Error, Serialize)]
+pub enum FocusTimerCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for FocusTimerCommandError {
+    fn from(err: RusqliteError) -> Self {
+        FocusTimerCommandError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<FocusTimerError> for FocusTimerCommandError {
+    fn from(err: FocusTimerError) -> Self {
+        error!("{}", err);
+        FocusTimerCommandError::InvalidInput(err.to_string())
+    }
+}
+
+fn invalid_input(msg: String) -> FocusTimerCommandError {
+    error!("{}", msg);
+    FocusTimerCommandError::InvalidInput(msg)
+}
+
+pub struct FocusTimerCommands<'a> {
+    conn: &'a mut Connection,
+    timer: &'a FocusTimer,
+}
+
+impl<'a> FocusTimerCommands<'a> {
+    pub fn new(conn: &'a mut Connection, timer: &'a FocusTimer) -> Self {
+        Self { conn, timer }
+    }
+
+    fn resolve_user_id(&mut self, user_id: Option<i32>) -> Result<i32, FocusTimerCommandError> {
+        if let Some(user_id) = user_id {
+            return Ok(user_id);
+        }
+
+        UserRepository::new(self.conn)
+            .get_active_user_id()?
+            .ok_or_else(|| invalid_input("No active user found".to_string()))
+    }
+
+    /// Starts work/break cycles for the user, optionally counting the
+    /// pomodoros towards a task or a book. `notifier` hears about every phase
+    /// change and the end of the run.
+    pub fn start_focus_timer_method(
+        &mut self,
+        user_id: Option<i32>,
+        task_id: Option<i32>,
+        book_id: Option<i64>,
+        config: FocusTimerConfig,
+        notifier: FocusNotifier,
+    ) -> Result<FocusTimerStatus, FocusTimerCommandError> {
+        let user_id = self.resolve_user_id(user_id)?;
+        info!("Starting focus timer for user {} with {:?}", user_id, config);
+
+        if let Some(task_id) = task_id {
+            if TaskRepository::new(self.conn).get_task_by_id(task_id)?.is_none() {
+                return Err(invalid_input(format!("Task with ID {} not found", task_id)));
+            }
+        }
+        if let Some(book_id) = book_id {
+            if BookRepository::new(self.conn).get_book_by_id(book_id)?.is_none() {
+                return Err(invalid_input(format!("Book with ID {} not found", book_id)));
+            }
+        }
+
+        Ok(self.timer.start(user_id, task_id, book_id, config, notifier)?)
+    }
+
+    /// The running timer, for a UI re-attaching after a reload.
+    pub fn get_focus_timer_method(&mut self) -> Result<Option<FocusTimerStatus>, FocusTimerCommandError> {
+        Ok(self.timer.status())
+    }
+
+    pub fn pause_focus_timer_method(&mut self) -> Result<FocusTimerStatus, FocusTimerCommandError> {
+        info!("Pausing focus timer");
+        Ok(self.timer.pause()?)
+    }
+
+    pub fn resume_focus_timer_method(&mut self) -> Result<FocusTimerStatus, FocusTimerCommandError> {
+        info!("Resuming focus timer");
+        Ok(self.timer.resume()?)
+    }
+
+    pub fn skip_focus_phase_method(&mut self) -> Result<FocusTimerStatus, FocusTimerCommandError> {
+        info!("Skipping focus timer phase");
+        Ok(self.timer.skip()?)
+    }
+
+    pub fn stop_focus_timer_method(&mut self) -> Result<FocusTimerStatus, FocusTimerCommandError> {
+        info!("Stopping focus timer");
+        Ok(self.timer.stop()?)
+    }
+
+    /// Completed pomodoros, most recent first.
+    pub fn list_pomodoros_method(
+        &mut self,
+        user_id: Option<i32>,
+        task_id: Option<i32>,
+        book_id: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Pomodoro>, FocusTimerCommandError> {
+        let user_id = self.resolve_user_id(user_id)?;
+
+        match PomodoroRepository::new(self.conn).get_pomodoros(user_id, task_id, book_id, limit.unwrap_or(DEFAULT_POMODORO_LIMIT)) {
+            Ok(pomodoros) => Ok(pomodoros),
+            Err(err) => {
+                error!("Failed to list pomodoros: {}", err);
+                Err(FocusTimerCommandError::from(err))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn start_focus_timer_command(
+    app_handle: tauri::AppHandle,
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    task_id: Option<i32>,
+    book_id: Option<i64>,
+    work_seconds: Option<u32>,
+    short_break_seconds: Option<u32>,
+    long_break_seconds: Option<u32>,
+    long_break_every: Option<u32>,
+    cycles: Option<u32>,
+) -> Result<FocusTimerStatus, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    let defaults = FocusTimerConfig::default();
+    let config = FocusTimerConfig {
+        work_seconds: work_seconds.unwrap_or(defaults.work_seconds),
+        short_break_seconds: short_break_seconds.unwrap_or(defaults.short_break_seconds),
+        long_break_seconds: long_break_seconds.unwrap_or(defaults.long_break_seconds),
+        long_break_every: long_break_every.unwrap_or(defaults.long_break_every),
+        cycles,
+    };
+    let notifier: FocusNotifier = Arc::new(move |event, status| {
+        if let Err(err) = app_handle.emit(event, status) {
+            error!("Failed to emit {}: {}", event, err);
+        }
+    });
+
+    match timer_commands.start_focus_timer_method(user_id, task_id, book_id, config, notifier) {
+        Ok(status) => Ok(status),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_focus_timer_command(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Option<FocusTimerStatus>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    match timer_commands.get_focus_timer_method() {
+        Ok(status) => Ok(status),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn pause_focus_timer_command(app_state: tauri::State<'_, AppState>) -> Result<FocusTimerStatus, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    match timer_commands.pause_focus_timer_method() {
+        Ok(status) => Ok(status),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn resume_focus_timer_command(app_state: tauri::State<'_, AppState>) -> Result<FocusTimerStatus, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    match timer_commands.resume_focus_timer_method() {
+        Ok(status) => Ok(status),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn skip_focus_phase_command(app_state: tauri::State<'_, AppState>) -> Result<FocusTimerStatus, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    match timer_commands.skip_focus_phase_method() {
+        Ok(status) => Ok(status),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn stop_focus_timer_command(app_state: tauri::State<'_, AppState>) -> Result<FocusTimerStatus, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    match timer_commands.stop_focus_timer_method() {
+        Ok(status) => Ok(status),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_pomodoros_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    task_id: Option<i32>,
+    book_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<Pomodoro>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut timer_commands = FocusTimerCommands::new(&mut conn, &app_state.focus_timer);
+
+    match timer_commands.list_pomodoros_method(user_id, task_id, book_id, limit) {
+        Ok(pomodoros) => Ok(pomodoros),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
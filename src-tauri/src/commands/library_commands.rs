@@ -0,0 +1,564 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info, warn};
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::db::repositories::{AnnotationRepository, BookRepository, ChapterRepository, DocumentRepository};
+use crate::db::models::{book::Book, chapter::Chapter, document::Document, tag::Tag};
+use crate::services::{
+    detect_file_type, hash_file, read_epub, read_metadata, read_pdf_annotations, spawn_text_indexing,
+    spawn_document_thumbnail, EpubBook, LibraryError, LibraryStore, PdfAnnotation, PdfMetadata,
+    StoredFile, ThumbnailService,
+};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+#[derive(Debug, Error, Serialize)]
+pub enum LibraryCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("File error: {0}")]
+    FileError(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for LibraryCommandError {
+    fn from(err: RusqliteError) -> Self {
+        LibraryCommandError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<LibraryError> for LibraryCommandError {
+    fn from(err: LibraryError) -> Self {
+        match err {
+            LibraryError::SourceNotFound(_) | LibraryError::UnsupportedFileType(_) => {
+                LibraryCommandError::InvalidInput(err.to_string())
+            }
+            LibraryError::Io(_) => LibraryCommandError::FileError(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedBook {
+    pub book_id: i64,
+    pub document_id: i64,
+    pub title: String,
+    pub author: Option<String>,
+    pub page_count: i32,
+    pub annotation_count: usize,
+    pub stored_filename: String,
+    pub file_path: String,
+}
+
+/// Result of an import: either a new library entry or the entry that already
+/// holds a file with the same content.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported(ImportedBook),
+    Duplicate {
+        /// `None` when the file is known only as a document with no book.
+        book_id: Option<i64>,
+        document_id: i64,
+        attached: bool,
+        message: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookThumbnail {
+    pub book_id: i64,
+    pub path: String,
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// What could be read from the file itself, whatever its format.
+#[derive(Debug, Default)]
+struct ExtractedMetadata {
+    title: Option<String>,
+    author: Option<String>,
+    subject: Option<String>,
+    keywords: Vec<String>,
+    language: Option<String>,
+    identifiers: Vec<String>,
+    page_count: u32,
+    chapters: Vec<Chapter>,
+    annotations: Vec<PdfAnnotation>,
+}
+
+impl From<PdfMetadata> for ExtractedMetadata {
+    fn from(metadata: PdfMetadata) -> Self {
+        Self {
+            title: metadata.title,
+            author: metadata.author,
+            subject: metadata.subject,
+            keywords: metadata.keywords,
+            page_count: metadata.page_count,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<EpubBook> for ExtractedMetadata {
+    fn from(book: EpubBook) -> Self {
+        let chapters: Vec<Chapter> = book
+            .chapters
+            .into_iter()
+            .map(|chapter| Chapter {
+                id: None,
+                document_id: 0,
+                chapter_index: chapter.index,
+                title: chapter.title,
+                href: chapter.href,
+            })
+            .collect();
+
+        Self {
+            title: book.metadata.title,
+            author: Some(book.metadata.creators.join(", ")).filter(|a| !a.is_empty()),
+            subject: book.metadata.subject,
+            keywords: Vec::new(),
+            language: book.metadata.language,
+            identifiers: book.metadata.identifiers,
+            // Reflowable text has no fixed pages; 0 marks the count unknown so
+            // planning and progress go by chapter instead.
+            page_count: 0,
+            chapters,
+            annotations: Vec::new(),
+        }
+    }
+}
+
+/// A file about to be imported. Everything the import needs from the file is
+/// read here, before the database is locked: parsing a PDF and recovering the
+/// text under its highlights can take a while.
+pub struct ImportSource {
+    path: PathBuf,
+    hash: String,
+    metadata: ExtractedMetadata,
+}
+
+impl ImportSource {
+    pub fn read(source_path: &str) -> Result<Self, LibraryCommandError> {
+        if source_path.trim().is_empty() {
+            let msg = "You must provide a file path".to_string();
+            error!("{}", msg);
+            return Err(LibraryCommandError::InvalidInput(msg));
+        }
+
+        let path = PathBuf::from(source_path);
+        if !path.is_file() {
+            let msg = format!("File not found: {}", source_path);
+            error!("{}", msg);
+            return Err(LibraryCommandError::InvalidInput(msg));
+        }
+
+        let (_, mime_type) = detect_file_type(&path).map_err(|err| {
+            error!("Cannot import {}: {}", source_path, err);
+            LibraryCommandError::from(err)
+        })?;
+
+        let hash = hash_file(&path).map_err(|err| {
+            error!("Failed to read {}: {}", source_path, err);
+            LibraryCommandError::FileError(err.to_string())
+        })?;
+
+        let metadata = read_file_metadata(&path, mime_type);
+        Ok(Self { path, hash, metadata })
+    }
+}
+
+pub struct LibraryCommands<'a> {
+    conn: &'a mut Connection,
+    store: LibraryStore,
+}
+
+impl<'a> LibraryCommands<'a> {
+    pub fn new(conn: &'a mut Connection, library_dir: &Path) -> Self {
+        let store = LibraryStore::new(library_dir);
+        Self { conn, store }
+    }
+
+    pub fn import_book_method(
+        &mut self,
+        source: ImportSource,
+        title: Option<String>,
+        author: Option<String>,
+        tags: Vec<Tag>,
+        attach_to_existing: bool,
+    ) -> Result<ImportOutcome, LibraryCommandError> {
+        info!("Starting the import of {:?}", source.path);
+
+        let ImportSource { path, hash, metadata } = source;
+
+        let existing = DocumentRepository::new(self.conn)
+            .find_by_hash(&hash)
+            .map_err(LibraryCommandError::DatabaseError)?;
+        if let Some(document) = existing {
+            return self.handle_duplicate(document, title, author, tags, attach_to_existing);
+        }
+
+        let stored = self.store.store_file_with_hash(&path, hash).map_err(|err| {
+            error!("Failed to copy {:?} into the library: {}", path, err);
+            LibraryCommandError::from(err)
+        })?;
+
+        match self.insert_records(&stored, metadata, title, author, tags) {
+            Ok(imported) => {
+                info!(
+                    "Imported {} as book {} (document {})",
+                    stored.original_filename, imported.book_id, imported.document_id
+                );
+                Ok(ImportOutcome::Imported(imported))
+            }
+            Err(err) => {
+                error!("Failed to register {}: {}", stored.original_filename, err);
+                if let Err(discard_err) = self.store.discard(&stored) {
+                    error!("Failed to remove {:?}: {}", stored.file_path, discard_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn handle_duplicate(
+        &mut self,
+        document: Document,
+        title: Option<String>,
+        author: Option<String>,
+        tags: Vec<Tag>,
+        attach_to_existing: bool,
+    ) -> Result<ImportOutcome, LibraryCommandError> {
+        let document_id = document.id as i64;
+        let existing_book = BookRepository::new(self.conn).get_book_by_document_id(document_id)?;
+
+        let title = title.filter(|t| !t.trim().is_empty());
+        let author = author.filter(|a| !a.trim().is_empty());
+
+        let book_id = match existing_book {
+            Some(book) if !attach_to_existing => book.id,
+            None if !attach_to_existing => {
+                let msg = format!("File already in library as document #{} with no book", document_id);
+                info!("{}", msg);
+                return Ok(ImportOutcome::Duplicate {
+                    book_id: None,
+                    document_id,
+                    attached: false,
+                    message: msg,
+                });
+            }
+            existing_book => self.attach_duplicate(document, existing_book, title, author, tags)?,
+        };
+
+        let msg = format!("Already in library as book #{}", book_id);
+        info!("{}", msg);
+        Ok(ImportOutcome::Duplicate {
+            book_id: Some(book_id),
+            document_id,
+            attached: attach_to_existing,
+            message: msg,
+        })
+    }
+
+    /// Merges what the user typed into the book that already holds the file,
+    /// and carries the tags over to its document, all in one transaction.
+    fn attach_duplicate(
+        &mut self,
+        mut document: Document,
+        existing_book: Option<Book>,
+        title: Option<String>,
+        author: Option<String>,
+        tags: Vec<Tag>,
+    ) -> Result<i64, LibraryCommandError> {
+        let document_id = document.id as i64;
+        let tx = self.conn.transaction()?;
+
+        let book_id = match existing_book {
+            Some(book) => {
+                BookRepository::merge_into_book(&tx, book.id, title.as_deref(), author.as_deref(), &tags)?;
+                book.id
+            }
+            // The document outlived its book; re-attaching recreates the entry.
+            None => {
+                let book = Book {
+                    id: 0,
+                    title: title.unwrap_or_else(|| document.title.clone()),
+                    author,
+                    file_path: Some(document.file_path.clone()),
+                    document_id: Some(document_id),
+                    tags: Some(tags.clone()),
+                };
+                BookRepository::insert_book(&tx, &book)?
+            }
+        };
+
+        if !tags.is_empty() {
+            let mut tag_titles = document.tags.take().unwrap_or_default();
+            for tag in &tags {
+                if !tag_titles.contains(&tag.title) {
+                    tag_titles.push(tag.title.clone());
+                }
+            }
+            document.tags = Some(tag_titles);
+            DocumentRepository::new(&tx)
+                .update(&document)
+                .map_err(LibraryCommandError::DatabaseError)?;
+        }
+
+        tx.commit()?;
+        Ok(book_id)
+    }
+
+    fn insert_records(
+        &mut self,
+        stored: &StoredFile,
+        metadata: ExtractedMetadata,
+        title: Option<String>,
+        author: Option<String>,
+        tags: Vec<Tag>,
+    ) -> Result<ImportedBook, LibraryCommandError> {
+        // Values typed by the user win over what the file claims about itself.
+        let title = title
+            .filter(|t| !t.trim().is_empty())
+            .or(metadata.title)
+            .unwrap_or_else(|| title_from_filename(&stored.original_filename));
+        let author = author.filter(|a| !a.trim().is_empty()).or(metadata.author);
+        let page_count = metadata.page_count as i32;
+        let file_path = stored.file_path.to_string_lossy().to_string();
+
+        let document = Document {
+            id: 0,
+            title: title.clone(),
+            original_filename: stored.original_filename.clone(),
+            stored_filename: stored.stored_filename.clone(),
+            file_path: file_path.clone(),
+            file_size: stored.file_size.to_string(),
+            mime_type: stored.mime_type.clone(),
+            hash: stored.hash.clone(),
+            page_count,
+            created_at: Some(Utc::now()),
+            last_accessed: None,
+            thumbnail_path: None,
+            tags: Some(tags.iter().map(|tag| tag.title.clone()).collect()),
+            subject: metadata.subject,
+            keywords: Some(metadata.keywords).filter(|k| !k.is_empty()),
+            language: metadata.language,
+            identifiers: Some(metadata.identifiers).filter(|i| !i.is_empty()),
+        };
+
+        let tx = self.conn.transaction()?;
+
+        let document_id = DocumentRepository::new(&tx)
+            .create(&document)
+            .map_err(LibraryCommandError::DatabaseError)?;
+
+        let book = Book {
+            id: 0,
+            title: title.clone(),
+            author: author.clone(),
+            file_path: Some(file_path.clone()),
+            document_id: Some(document_id),
+            tags: Some(tags),
+        };
+        let book_id = BookRepository::insert_book(&tx, &book)?;
+
+        ChapterRepository::new(&tx).insert_chapters(document_id, &metadata.chapters)?;
+
+        let annotation_count = metadata.annotations.len();
+        for annotation in metadata.annotations {
+            AnnotationRepository::insert_annotation(&tx, &annotation.into_annotation(book_id))?;
+        }
+
+        tx.commit()?;
+
+        Ok(ImportedBook {
+            book_id,
+            document_id,
+            title,
+            author,
+            page_count,
+            annotation_count,
+            stored_filename: stored.stored_filename.clone(),
+            file_path,
+        })
+    }
+}
+
+/// Returns a book's cover, rendering it first if it is not cached. Rendering a
+/// PDF can take a while, so the database is only locked for the lookups.
+pub fn get_book_thumbnail(
+    db_conn: &Mutex<Connection>,
+    thumbnails: &ThumbnailService,
+    book_id: i64,
+    include_bytes: bool,
+) -> Result<Option<BookThumbnail>, LibraryCommandError> {
+    info!("Fetching thumbnail for book {}", book_id);
+
+    let document = {
+        let mut conn = db_conn.lock().unwrap();
+        let book = match BookRepository::new(&mut conn).get_book_by_id(book_id)? {
+            Some(book) => book,
+            None => {
+                let msg = format!("Book with ID {} not found", book_id);
+                error!("{}", msg);
+                return Err(LibraryCommandError::InvalidInput(msg));
+            }
+        };
+
+        let Some(document_id) = book.document_id else {
+            return Ok(None);
+        };
+
+        DocumentRepository::new(&conn)
+            .get_by_id(document_id as i32)
+            .map_err(LibraryCommandError::DatabaseError)?
+    };
+    let Some(document) = document else {
+        return Ok(None);
+    };
+
+    let cached = document
+        .thumbnail_path
+        .clone()
+        .filter(|path| Path::new(path).is_file());
+
+    let path = match cached {
+        Some(path) => path,
+        None => {
+            let generated = thumbnails.generate(
+                Path::new(&document.file_path),
+                &document.mime_type,
+                &document.hash,
+            );
+            match generated {
+                Ok(path) => {
+                    let path = path.to_string_lossy().to_string();
+                    let conn = db_conn.lock().unwrap();
+                    DocumentRepository::new(&conn)
+                        .set_thumbnail_path(document.id, Some(&path))
+                        .map_err(LibraryCommandError::DatabaseError)?;
+                    path
+                }
+                Err(err) => {
+                    warn!("Could not generate thumbnail for book {}: {}", book_id, err);
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    let bytes = if include_bytes {
+        Some(fs::read(&path).map_err(|err| LibraryCommandError::FileError(err.to_string()))?)
+    } else {
+        None
+    };
+
+    Ok(Some(BookThumbnail { book_id, path, bytes }))
+}
+
+/// Metadata is best effort: an unreadable file is still imported, just without it.
+fn read_file_metadata(path: &Path, mime_type: &str) -> ExtractedMetadata {
+    let extracted = match mime_type {
+        "application/pdf" => read_metadata(path)
+            .map(|metadata| ExtractedMetadata {
+                annotations: read_existing_annotations(path),
+                ..ExtractedMetadata::from(metadata)
+            })
+            .map_err(|err| err.to_string()),
+        "application/epub+zip" => read_epub(path)
+            .map(ExtractedMetadata::from)
+            .map_err(|err| err.to_string()),
+        _ => Ok(ExtractedMetadata::default()),
+    };
+
+    extracted.unwrap_or_else(|err| {
+        warn!("Could not read metadata from {:?}: {}", path, err);
+        ExtractedMetadata::default()
+    })
+}
+
+/// Highlights and notes made in other readers; losing them is not worth
+/// failing the import over.
+fn read_existing_annotations(path: &Path) -> Vec<PdfAnnotation> {
+    match read_pdf_annotations(path) {
+        Ok(annotations) => {
+            if !annotations.is_empty() {
+                info!("Found {} annotations in {:?}", annotations.len(), path);
+            }
+            annotations
+        }
+        Err(err) => {
+            warn!("Could not read annotations from {:?}: {}", path, err);
+            Vec::new()
+        }
+    }
+}
+
+fn title_from_filename(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.to_string())
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn import_book_command(
+    app_state: tauri::State<'_, AppState>,
+    source_path: String,
+    title: Option<String>,
+    author: Option<String>,
+    tags: Option<Vec<Tag>>,
+    attach_to_existing: Option<bool>,
+) -> Result<ImportOutcome, InvokeError> {
+    let source = match ImportSource::read(&source_path) {
+        Ok(source) => source,
+        Err(err) => return Err(InvokeError::from(err)),
+    };
+
+    let result = {
+        let mut conn = app_state.db_conn.lock().unwrap();
+        let mut library_commands = LibraryCommands::new(&mut conn, &app_state.library_dir);
+
+        library_commands.import_book_method(
+            source,
+            title,
+            author,
+            tags.unwrap_or_default(),
+            attach_to_existing.unwrap_or(false),
+        )
+    };
+
+    match result {
+        Ok(outcome) => {
+            if let ImportOutcome::Imported(imported) = &outcome {
+                spawn_document_thumbnail(app_state.db_conn(), app_state.thumbnails(), imported.document_id as i32);
+                spawn_text_indexing(app_state.db_conn());
+            }
+            Ok(outcome)
+        }
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_book_thumbnail_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: i64,
+    include_bytes: Option<bool>,
+) -> Result<Option<BookThumbnail>, InvokeError> {
+    match get_book_thumbnail(&app_state.db_conn, &app_state.thumbnails(), book_id, include_bytes.unwrap_or(false)) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
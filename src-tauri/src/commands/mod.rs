@@ -2,9 +2,37 @@ pub mod document_commands;
 pub mod tag_commands;
 pub mod user_commands;
 pub mod book_commands;
+pub mod library_commands;
+pub mod chapter_commands;
+pub mod search_commands;
+pub mod reading_progress_commands;
+pub mod reading_session_commands;
+pub mod annotation_commands;
+pub mod annotation_export_commands;
+pub mod bookmark_commands;
+pub mod task_commands;
+pub mod reading_plan_commands;
+pub mod flashcard_commands;
+pub mod anki_commands;
+pub mod quiz_commands;
+pub mod focus_timer_commands;
 
 pub use document_commands::*;
 pub use tag_commands::*;
 pub use user_commands::{create_user_command, check_if_there_is_active_user_status_command};
 pub use book_commands::*;
+pub use library_commands::*;
+pub use chapter_commands::*;
+pub use search_commands::*;
+pub use reading_progress_commands::*;
+pub use reading_session_commands::*;
+pub use annotation_commands::*;
+pub use annotation_export_commands::*;
+pub use bookmark_commands::*;
+pub use task_commands::*;
+pub use reading_plan_commands::*;
+pub use flashcard_commands::*;
+pub use anki_commands::*;
+pub use quiz_commands::*;
+pub use focus_timer_commands::*;
 
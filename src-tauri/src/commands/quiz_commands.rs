@@ -0,0 +1,551 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use chrono::{DateTime, Duration, Utc};
+use crate::db::repositories::{
+    BookRepository, ChapterRepository, FlashcardRepository, QuizRepository, TagRepository, UserRepository,
+};
+use crate::db::models::flashcard::Card;
+use crate::db::models::quiz::{QuestionKind, Quiz, QuizAnswer, QuizAttempt, QuizSolution, TopicScore};
+use crate::services::quiz;
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+/// How many questions a quiz gets when no count is given.
+const DEFAULT_QUESTION_COUNT: u32 = 10;
+const MAX_QUESTION_COUNT: u32 = 200;
+/// Answers a tag needs before it shows up among the weakest topics, so one
+/// unlucky question doesn't dominate the report.
+const MIN_TOPIC_ANSWERS: u32 = 3;
+const DEFAULT_TOPIC_LIMIT: usize = 5;
+
+#[derive(Debug, Error, Serialize)]
+pub enum QuizCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for QuizCommandError {
+    fn from(err: RusqliteError) -> Self {
+        QuizCommandError::DatabaseError(err.to_string())
+    }
+}
+
+fn invalid_input(msg: String) -> QuizCommandError {
+    error!("{}", msg);
+    QuizCommandError::InvalidInput(msg)
+}
+
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Where a quiz draws its cards from and how it is asked. Exactly one of
+/// `deck_id`, `tag_id` and `book_id` must be given.
+#[derive(Debug, Default)]
+pub struct QuizInput {
+    pub title: Option<String>,
+    pub deck_id: Option<i64>,
+    pub tag_id: Option<i64>,
+    pub book_id: Option<i64>,
+    pub chapter_index: Option<i32>,
+    pub question_count: Option<u32>,
+    /// `multiple_choice`, `true_false` and/or `typed`; all three by default.
+    pub kinds: Option<Vec<String>>,
+    pub time_limit_seconds: Option<u32>,
+}
+
+pub struct QuizCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> QuizCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn resolve_user_id(&mut self, user_id: Option<i32>) -> Result<i32, QuizCommandError> {
+        if let Some(user_id) = user_id {
+            return Ok(user_id);
+        }
+
+        UserRepository::new(self.conn)
+            .get_active_user_id()?
+            .ok_or_else(|| invalid_input("No active user found".to_string()))
+    }
+
+    fn parse_kinds(kinds: Option<Vec<String>>) -> Result<Vec<QuestionKind>, QuizCommandError> {
+        let Some(kinds) = kinds.filter(|kinds| !kinds.is_empty()) else {
+            return Ok(vec![QuestionKind::MultipleChoice, QuestionKind::TrueFalse, QuestionKind::Typed]);
+        };
+
+        let mut parsed = Vec::new();
+        for kind in kinds {
+            let kind = QuestionKind::parse(&kind)
+                .ok_or_else(|| invalid_input(format!("Unknown question kind '{}'", kind)))?;
+            if !parsed.contains(&kind) {
+                parsed.push(kind);
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn get_quiz(&mut self, quiz_id: i64) -> Result<Quiz, QuizCommandError> {
+        QuizRepository::new(self.conn)
+            .get_quiz_by_id(quiz_id)?
+            .ok_or_else(|| invalid_input(format!("Quiz with ID {} not found", quiz_id)))
+    }
+
+    fn get_attempt(&mut self, attempt_id: i64) -> Result<QuizAttempt, QuizCommandError> {
+        QuizRepository::new(self.conn)
+            .get_attempt_by_id(attempt_id)?
+            .ok_or_else(|| invalid_input(format!("Quiz attempt with ID {} not found", attempt_id)))
+    }
+
+    /// Cards `input` points at, with the title a quiz over them gets by default.
+    fn source_cards(&mut self, input: &QuizInput) -> Result<(Vec<Card>, String), QuizCommandError> {
+        if input.chapter_index.is_some() && input.book_id.is_none() {
+            return Err(invalid_input("A chapter needs a source book".to_string()));
+        }
+
+        match (input.deck_id, input.tag_id, input.book_id) {
+            (Some(deck_id), None, None) => {
+                let repository = FlashcardRepository::new(self.conn);
+                let deck = repository
+                    .get_deck_by_id(deck_id)?
+                    .ok_or_else(|| invalid_input(format!("Deck with ID {} not found", deck_id)))?;
+                Ok((repository.get_cards_by_deck_id(deck_id)?, deck.name))
+            }
+            (None, Some(tag_id), None) => {
+                let tag = TagRepository::new(self.conn)
+                    .get_all_tags()?
+                    .into_iter()
+                    .find(|tag| tag.id.map(i64::from) == Some(tag_id))
+                    .ok_or_else(|| invalid_input(format!("Tag with ID {} not found", tag_id)))?;
+                Ok((FlashcardRepository::new(self.conn).get_cards_by_tag_id(tag_id)?, tag.title))
+            }
+            (None, None, Some(book_id)) => {
+                let book = BookRepository::new(self.conn)
+                    .get_book_by_id(book_id)?
+                    .ok_or_else(|| invalid_input(format!("Book with ID {} not found", book_id)))?;
+
+                let title = match (input.chapter_index, book.document_id) {
+                    (Some(index), Some(document_id)) => {
+                        let chapter = ChapterRepository::new(self.conn).get_chapter(document_id, index)?;
+                        match chapter.and_then(|chapter| chapter.title) {
+                            Some(chapter_title) => format!("{}: {}", book.title, chapter_title),
+                            None => format!("{}: chapter {}", book.title, index + 1),
+                        }
+                    }
+                    (Some(index), None) => format!("{}: chapter {}", book.title, index + 1),
+                    (None, _) => book.title,
+                };
+                Ok((FlashcardRepository::new(self.conn).get_cards_by_book_id(book_id, input.chapter_index)?, title))
+            }
+            _ => Err(invalid_input("Choose exactly one deck, tag or book to quiz on".to_string())),
+        }
+    }
+
+    /// Ends an attempt, scoring every question of the quiz: unanswered ones
+    /// count as wrong.
+    fn close_attempt(&mut self, mut attempt: QuizAttempt, finished_at: DateTime<Utc>) -> Result<QuizAttempt, QuizCommandError> {
+        let attempt_id = attempt.id.unwrap_or_default();
+        let answers = QuizRepository::new(self.conn).get_answers_by_attempt_id(attempt_id)?;
+        attempt.correct_count = answers.iter().filter(|answer| answer.is_correct).count() as u32;
+        attempt.score = Some(if attempt.question_count == 0 {
+            0.0
+        } else {
+            attempt.correct_count as f64 / attempt.question_count as f64
+        });
+        attempt.finished_at = Some(finished_at);
+        attempt.answers = Some(answers);
+
+        QuizRepository::new(self.conn).finish_attempt(&attempt)?;
+        Ok(attempt)
+    }
+
+    /// Closes an open attempt whose time has run out, as of when it ran out.
+    fn expire_if_due(&mut self, attempt: QuizAttempt) -> Result<QuizAttempt, QuizCommandError> {
+        match attempt.expires_at {
+            Some(expires_at) if attempt.finished_at.is_none() && expires_at <= Utc::now() => {
+                info!("Quiz attempt {:?} ran out of time", attempt.id);
+                self.close_attempt(attempt, expires_at)
+            }
+            _ => Ok(attempt),
+        }
+    }
+
+    /// Adds the expected answers to an attempt that has ended.
+    fn reveal_solutions(&mut self, mut attempt: QuizAttempt) -> Result<QuizAttempt, QuizCommandError> {
+        if attempt.finished_at.is_none() {
+            return Ok(attempt);
+        }
+
+        let questions = QuizRepository::new(self.conn).get_questions_by_quiz_id(attempt.quiz_id)?;
+        attempt.solutions = Some(
+            questions.into_iter()
+                .map(|question| QuizSolution { question_id: question.id.unwrap_or_default(), answer: question.answer })
+                .collect(),
+        );
+        Ok(attempt)
+    }
+
+    /// Builds a quiz from a deck, a tag or a book (optionally one chapter)
+    /// and saves its questions, so every attempt at it asks the same ones.
+    pub fn create_quiz_method(&mut self, input: QuizInput) -> Result<Quiz, QuizCommandError> {
+        info!("Creating quiz {:?}", input);
+
+        let count = input.question_count.unwrap_or(DEFAULT_QUESTION_COUNT);
+        if count == 0 || count > MAX_QUESTION_COUNT {
+            return Err(invalid_input(format!(
+                "A quiz has between 1 and {} questions, got {}",
+                MAX_QUESTION_COUNT, count
+            )));
+        }
+        if input.time_limit_seconds == Some(0) {
+            return Err(invalid_input("The time limit must be at least one second".to_string()));
+        }
+        let kinds = Self::parse_kinds(input.kinds.clone())?;
+
+        let (cards, default_title) = self.source_cards(&input)?;
+        let questions = quiz::build_questions(&cards, &kinds, count as usize, &mut quiz::QuizRng::new(random_seed()));
+        if questions.is_empty() {
+            return Err(invalid_input("There are no cards to quiz on".to_string()));
+        }
+
+        let title = input.title
+            .map(|title| title.trim().to_string())
+            .filter(|title| !title.is_empty())
+            .unwrap_or(default_title);
+        let quiz = Quiz {
+            id: None,
+            title,
+            deck_id: input.deck_id,
+            tag_id: input.tag_id,
+            book_id: input.book_id,
+            chapter_index: input.chapter_index,
+            time_limit_seconds: input.time_limit_seconds,
+            created_at: Some(Utc::now()),
+            questions: Some(questions),
+        };
+
+        match QuizRepository::new(self.conn).create_quiz(&quiz) {
+            Ok(quiz_id) => {
+                info!("Quiz created with ID: {}", quiz_id);
+                self.get_quiz(quiz_id)
+            }
+            Err(err) => {
+                error!("Failed to create quiz: {}", err);
+                Err(QuizCommandError::from(err))
+            }
+        }
+    }
+
+    pub fn get_quiz_method(&mut self, quiz_id: i64) -> Result<Quiz, QuizCommandError> {
+        self.get_quiz(quiz_id)
+    }
+
+    pub fn list_quizzes_method(&mut self) -> Result<Vec<Quiz>, QuizCommandError> {
+        match QuizRepository::new(self.conn).get_quizzes() {
+            Ok(quizzes) => Ok(quizzes),
+            Err(err) => {
+                error!("Failed to list quizzes: {}", err);
+                Err(QuizCommandError::from(err))
+            }
+        }
+    }
+
+    pub fn delete_quiz_method(&mut self, quiz_id: i64) -> Result<String, QuizCommandError> {
+        info!("Deleting quiz with ID: {}", quiz_id);
+
+        match QuizRepository::new(self.conn).delete_quiz(quiz_id)? {
+            0 => Err(invalid_input(format!("Quiz with ID {} not found", quiz_id))),
+            _ => Ok(format!("Quiz {} deleted", quiz_id)),
+        }
+    }
+
+    /// Starts a user's attempt at a quiz; the clock starts now for timed quizzes.
+    pub fn start_quiz_attempt_method(&mut self, user_id: Option<i32>, quiz_id: i64) -> Result<QuizAttempt, QuizCommandError> {
+        let user_id = self.resolve_user_id(user_id)?;
+        info!("Starting quiz {} for user {}", quiz_id, user_id);
+
+        let quiz = self.get_quiz(quiz_id)?;
+        let started_at = Utc::now();
+        let attempt = QuizAttempt {
+            id: None,
+            quiz_id,
+            user_id,
+            started_at,
+            expires_at: quiz.time_limit_seconds.map(|seconds| started_at + Duration::seconds(seconds as i64)),
+            finished_at: None,
+            correct_count: 0,
+            question_count: quiz.questions.map(|questions| questions.len() as u32).unwrap_or_default(),
+            score: None,
+            answers: None,
+            solutions: None,
+        };
+
+        let attempt_id = QuizRepository::new(self.conn).create_attempt(&attempt)?;
+        self.get_attempt(attempt_id)
+    }
+
+    /// Checks and records the answer to one question. Each question can be
+    /// answered once per attempt; answers after the time limit are refused.
+    pub fn answer_quiz_question_method(
+        &mut self,
+        attempt_id: i64,
+        question_id: i64,
+        response: String,
+    ) -> Result<QuizAnswer, QuizCommandError> {
+        info!("Answering question {} in quiz attempt {}", question_id, attempt_id);
+
+        let attempt = self.get_attempt(attempt_id)?;
+        let attempt = self.expire_if_due(attempt)?;
+        if attempt.finished_at.is_some() {
+            let reason = if attempt.expires_at.is_some_and(|expires_at| attempt.finished_at >= Some(expires_at)) {
+                "ran out of time"
+            } else {
+                "has already ended"
+            };
+            return Err(invalid_input(format!("Quiz attempt {} {}", attempt_id, reason)));
+        }
+        if attempt.answers.iter().flatten().any(|answer| answer.question_id == question_id) {
+            return Err(invalid_input(format!("Question {} has already been answered", question_id)));
+        }
+
+        let question = QuizRepository::new(self.conn)
+            .get_questions_by_quiz_id(attempt.quiz_id)?
+            .into_iter()
+            .find(|question| question.id == Some(question_id))
+            .ok_or_else(|| invalid_input(format!(
+                "Question {} is not part of quiz {}",
+                question_id, attempt.quiz_id
+            )))?;
+
+        let (is_correct, similarity) = quiz::check_answer(&question, &response);
+        let mut answer = QuizAnswer {
+            id: None,
+            attempt_id,
+            question_id,
+            response,
+            is_correct,
+            similarity,
+            answered_at: Utc::now(),
+        };
+
+        answer.id = Some(QuizRepository::new(self.conn).insert_answer(&answer)?);
+        Ok(answer)
+    }
+
+    /// Ends an attempt and returns its score with the answers to every
+    /// question. Finishing an attempt that has already ended returns it
+    /// unchanged.
+    pub fn finish_quiz_attempt_method(&mut self, attempt_id: i64) -> Result<QuizAttempt, QuizCommandError> {
+        info!("Finishing quiz attempt {}", attempt_id);
+
+        let attempt = self.get_attempt(attempt_id)?;
+        let mut attempt = self.expire_if_due(attempt)?;
+        if attempt.finished_at.is_none() {
+            attempt = self.close_attempt(attempt, Utc::now())?;
+        }
+        self.reveal_solutions(attempt)
+    }
+
+    pub fn get_quiz_attempt_method(&mut self, attempt_id: i64) -> Result<QuizAttempt, QuizCommandError> {
+        let attempt = self.get_attempt(attempt_id)?;
+        let attempt = self.expire_if_due(attempt)?;
+        self.reveal_solutions(attempt)
+    }
+
+    /// A user's attempts, newest first, optionally at a single quiz.
+    pub fn list_quiz_attempts_method(
+        &mut self,
+        user_id: Option<i32>,
+        quiz_id: Option<i64>,
+    ) -> Result<Vec<QuizAttempt>, QuizCommandError> {
+        let user_id = self.resolve_user_id(user_id)?;
+
+        let attempts = QuizRepository::new(self.conn).get_attempts(user_id, quiz_id)?;
+        attempts.into_iter().map(|attempt| self.expire_if_due(attempt)).collect()
+    }
+
+    /// Tags the user answers worst on, lowest accuracy first.
+    pub fn get_weakest_topics_method(
+        &mut self,
+        user_id: Option<i32>,
+        limit: Option<usize>,
+    ) -> Result<Vec<TopicScore>, QuizCommandError> {
+        let user_id = self.resolve_user_id(user_id)?;
+
+        let mut scores = QuizRepository::new(self.conn).get_topic_scores(user_id, MIN_TOPIC_ANSWERS)?;
+        scores.truncate(limit.unwrap_or(DEFAULT_TOPIC_LIMIT));
+        Ok(scores)
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_quiz_command(
+    app_state: tauri::State<'_, AppState>,
+    title: Option<String>,
+    deck_id: Option<i64>,
+    tag_id: Option<i64>,
+    book_id: Option<i64>,
+    chapter_index: Option<i32>,
+    question_count: Option<u32>,
+    kinds: Option<Vec<String>>,
+    time_limit_seconds: Option<u32>,
+) -> Result<Quiz, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    let input = QuizInput {
+        title,
+        deck_id,
+        tag_id,
+        book_id,
+        chapter_index,
+        question_count,
+        kinds,
+        time_limit_seconds,
+    };
+
+    match quiz_commands.create_quiz_method(input) {
+        Ok(quiz) => Ok(quiz),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_quiz_command(app_state: tauri::State<'_, AppState>, quiz_id: i64) -> Result<Quiz, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.get_quiz_method(quiz_id) {
+        Ok(quiz) => Ok(quiz),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_quizzes_command(app_state: tauri::State<'_, AppState>) -> Result<Vec<Quiz>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.list_quizzes_method() {
+        Ok(quizzes) => Ok(quizzes),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_quiz_command(app_state: tauri::State<'_, AppState>, quiz_id: i64) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.delete_quiz_method(quiz_id) {
+        Ok(message) => Ok(message),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn start_quiz_attempt_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    quiz_id: i64,
+) -> Result<QuizAttempt, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.start_quiz_attempt_method(user_id, quiz_id) {
+        Ok(attempt) => Ok(attempt),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn answer_quiz_question_command(
+    app_state: tauri::State<'_, AppState>,
+    attempt_id: i64,
+    question_id: i64,
+    response: String,
+) -> Result<QuizAnswer, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.answer_quiz_question_method(attempt_id, question_id, response) {
+        Ok(answer) => Ok(answer),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn finish_quiz_attempt_command(
+    app_state: tauri::State<'_, AppState>,
+    attempt_id: i64,
+) -> Result<QuizAttempt, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.finish_quiz_attempt_method(attempt_id) {
+        Ok(attempt) => Ok(attempt),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_quiz_attempt_command(
+    app_state: tauri::State<'_, AppState>,
+    attempt_id: i64,
+) -> Result<QuizAttempt, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.get_quiz_attempt_method(attempt_id) {
+        Ok(attempt) => Ok(attempt),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_quiz_attempts_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    quiz_id: Option<i64>,
+) -> Result<Vec<QuizAttempt>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.list_quiz_attempts_method(user_id, quiz_id) {
+        Ok(attempts) => Ok(attempts),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_weakest_topics_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    limit: Option<usize>,
+) -> Result<Vec<TopicScore>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut quiz_commands = QuizCommands::new(&mut conn);
+
+    match quiz_commands.get_weakest_topics_method(user_id, limit) {
+        Ok(scores) => Ok(scores),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
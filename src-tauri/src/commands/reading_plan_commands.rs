@@ -0,0 +1,513 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+use log::{error, info};
+use chrono::{Local, NaiveDate, Utc};
+use crate::db::repositories::{
+    BookRepository, ChapterRepository, DocumentRepository, PlanSessionTask, ReadingPlanRepository,
+    ReadingProgressRepository, TaskRepository, UserRepository,
+};
+use crate::db::models::{
+    book::Book,
+    chapter::Chapter,
+    reading_plan::{PlanUnit, PlannedSession, ReadingPlan, ReadingPlanPreview},
+    task::{Task, TaskStatus},
+};
+use crate::services::{reading_planner, recurrence};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+#[derive(Debug, Error, Serialize)]
+pub enum ReadingPlanCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for ReadingPlanCommandError {
+    fn from(err: RusqliteError) -> Self {
+        ReadingPlanCommandError::DatabaseError(err.to_string())
+    }
+}
+
+/// What a plan is built from: the book and the range of units it spans.
+struct PlanSource {
+    book: Book,
+    unit: PlanUnit,
+    first_unit: i32,
+    last_unit: i32,
+    chapters: Vec<Chapter>,
+}
+
+impl PlanSource {
+    fn session_title(&self, start: i32, end: i32) -> String {
+        match self.unit {
+            PlanUnit::Pages if start == end => format!("Read p. {}", start),
+            PlanUnit::Pages => format!("Read pp. {}-{}", start, end),
+            PlanUnit::Chapters if start == end => {
+                let title = self.chapters.iter()
+                    .find(|chapter| chapter.chapter_index == start)
+                    .and_then(|chapter| chapter.title.as_deref())
+                    .map(str::trim)
+                    .filter(|title| !title.is_empty());
+                match title {
+                    Some(title) => format!("Read ch. {}: {}", start + 1, title),
+                    None => format!("Read ch. {}", start + 1),
+                }
+            }
+            PlanUnit::Chapters => format!("Read ch. {}-{}", start + 1, end + 1),
+        }
+    }
+}
+
+fn invalid_input(msg: String) -> ReadingPlanCommandError {
+    error!("{}", msg);
+    ReadingPlanCommandError::InvalidInput(msg)
+}
+
+/// How often the background rebalancing checks whether the day has changed.
+const REBALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Rebalances plans that fell behind, once at launch and then once per day,
+/// so a plan missed while the app stays open catches up the next morning.
+/// The check runs hourly rather than sleeping until midnight, which would
+/// drift across suspends.
+pub fn spawn_daily_rebalance(db_conn: Arc<Mutex<Connection>>) {
+    thread::spawn(move || {
+        let mut last_run: Option<NaiveDate> = None;
+        loop {
+            let day = today();
+            if last_run != Some(day) {
+                let result = {
+                    let mut conn = db_conn.lock().unwrap();
+                    ReadingPlanCommands::new(&mut conn).rebalance_overdue_plans_method()
+                };
+                match result {
+                    Ok(0) => {}
+                    Ok(rebalanced) => info!("Rebalanced {} reading plan(s)", rebalanced),
+                    Err(err) => error!("Failed to rebalance reading plans: {}", err),
+                }
+                last_run = Some(day);
+            }
+            thread::sleep(REBALANCE_CHECK_INTERVAL);
+        }
+    });
+}
+
+pub struct ReadingPlanCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ReadingPlanCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn resolve_user_id(&mut self, user_id: Option<i32>) -> Result<i32, ReadingPlanCommandError> {
+        if let Some(user_id) = user_id {
+            return Ok(user_id);
+        }
+
+        UserRepository::new(self.conn)
+            .get_active_user_id()?
+            .ok_or_else(|| invalid_input("No active user found".to_string()))
+    }
+
+    fn plan_source(&mut self, book_id: i64, unit: Option<PlanUnit>) -> Result<PlanSource, ReadingPlanCommandError> {
+        let book = BookRepository::new(self.conn)
+            .get_book_by_id(book_id)?
+            .ok_or_else(|| invalid_input(format!("Book with ID {} not found", book_id)))?;
+
+        let document_id = book.document_id
+            .ok_or_else(|| invalid_input(format!("Book {} has no stored document to plan from", book_id)))?;
+
+        let page_count = DocumentRepository::new(self.conn)
+            .get_by_id(document_id as i32)
+            .map_err(ReadingPlanCommandError::DatabaseError)?
+            .map(|document| document.page_count)
+            .unwrap_or(0);
+        let chapters = ChapterRepository::new(self.conn).get_chapters_by_document_id(document_id)?;
+
+        let unit = unit.unwrap_or(if page_count > 0 { PlanUnit::Pages } else { PlanUnit::Chapters });
+        let (first_unit, last_unit) = match unit {
+            PlanUnit::Pages if page_count > 0 => (1, page_count),
+            PlanUnit::Chapters if !chapters.is_empty() => (
+                chapters[0].chapter_index,
+                chapters[chapters.len() - 1].chapter_index,
+            ),
+            _ => {
+                return Err(invalid_input(format!(
+                    "Book {} has no {} to plan with",
+                    book_id,
+                    unit.as_str()
+                )));
+            }
+        };
+
+        Ok(PlanSource { book, unit, first_unit, last_unit, chapters })
+    }
+
+    /// Where the user currently is in the book, in plan units.
+    fn current_position(&mut self, user_id: i32, source: &PlanSource) -> Result<Option<i32>, ReadingPlanCommandError> {
+        let progress = ReadingProgressRepository::new(self.conn).get_progress(user_id, source.book.id)?;
+        Ok(progress.and_then(|progress| match source.unit {
+            PlanUnit::Pages => progress.current_page,
+            PlanUnit::Chapters => progress.chapter_index,
+        }))
+    }
+
+    /// Splits `start_unit..=last_unit` over the user's available days from
+    /// `from` to `target`.
+    fn schedule(
+        &mut self,
+        user_id: i32,
+        source: &PlanSource,
+        start_unit: i32,
+        from: NaiveDate,
+        target: NaiveDate,
+    ) -> Result<Vec<PlannedSession>, ReadingPlanCommandError> {
+        if target < from {
+            return Err(invalid_input(format!(
+                "The target date {} is before {}",
+                recurrence::format_date(target),
+                recurrence::format_date(from)
+            )));
+        }
+
+        let weekdays = UserRepository::new(self.conn).get_available_days(user_id)?;
+        if weekdays.is_empty() {
+            return Err(invalid_input(format!("User {} has no available days set", user_id)));
+        }
+
+        let dates = reading_planner::available_dates(from, target, &weekdays);
+        if dates.is_empty() {
+            return Err(invalid_input(format!(
+                "None of the user's available days fall between {} and {}",
+                recurrence::format_date(from),
+                recurrence::format_date(target)
+            )));
+        }
+
+        Ok(reading_planner::split_evenly(start_unit, source.last_unit, &dates)
+            .into_iter()
+            .map(|(date, start, end)| PlannedSession {
+                date: recurrence::format_date(date),
+                start_unit: start,
+                end_unit: end,
+                title: source.session_title(start, end),
+            })
+            .collect())
+    }
+
+    fn session_tasks(source: &PlanSource, sessions: &[PlannedSession]) -> Vec<PlanSessionTask> {
+        let created_at = Utc::now().to_rfc3339();
+        sessions
+            .iter()
+            .map(|session| {
+                let task = Task {
+                    id: 0,
+                    title: session.title.clone(),
+                    description: None,
+                    status: TaskStatus::Todo,
+                    created_at: created_at.clone(),
+                    due_date: Some(session.date.clone()),
+                    completed_at: None,
+                    document_id: source.book.document_id,
+                    parent_id: None,
+                    position: 0,
+                    tags: Vec::new(),
+                    recurrence: None,
+                };
+                (task, session.start_unit, session.end_unit)
+            })
+            .collect()
+    }
+
+    /// Works out the plan without saving anything. Reading starts from the
+    /// user's current position in the book, on `start_date` (today by default).
+    pub fn preview_reading_plan_method(
+        &mut self,
+        user_id: Option<i32>,
+        book_id: i64,
+        target_date: String,
+        start_date: Option<String>,
+        unit: Option<String>,
+    ) -> Result<ReadingPlanPreview, ReadingPlanCommandError> {
+        info!("Previewing a reading plan for book {} until {}", book_id, target_date);
+
+        let user_id = self.resolve_user_id(user_id)?;
+        let unit = unit
+            .map(|unit| PlanUnit::parse(&unit).ok_or_else(|| invalid_input(format!("Invalid unit provided: {}", unit))))
+            .transpose()?;
+        let target = recurrence::parse_date(&target_date).map_err(invalid_input)?;
+        let from = match start_date.as_deref() {
+            Some(date) => recurrence::parse_date(date).map_err(invalid_input)?,
+            None => today(),
+        };
+
+        let source = self.plan_source(book_id, unit)?;
+        let start_unit = self.current_position(user_id, &source)?
+            .unwrap_or(source.first_unit)
+            .clamp(source.first_unit, source.last_unit);
+
+        let sessions = self.schedule(user_id, &source, start_unit, from, target)?;
+
+        Ok(ReadingPlanPreview {
+            book_id,
+            unit: source.unit,
+            target_date,
+            sessions,
+        })
+    }
+
+    /// Saves the previewed plan as a parent task with one dated subtask per
+    /// reading session.
+    pub fn create_reading_plan_method(
+        &mut self,
+        user_id: Option<i32>,
+        book_id: i64,
+        target_date: String,
+        start_date: Option<String>,
+        unit: Option<String>,
+    ) -> Result<ReadingPlan, ReadingPlanCommandError> {
+        let user_id = self.resolve_user_id(user_id)?;
+        let preview = self.preview_reading_plan_method(Some(user_id), book_id, target_date, start_date, unit)?;
+        let source = self.plan_source(book_id, Some(preview.unit))?;
+
+        let root = Task {
+            id: 0,
+            title: format!("Read {}", source.book.title),
+            description: None,
+            status: TaskStatus::Todo,
+            created_at: Utc::now().to_rfc3339(),
+            due_date: Some(preview.target_date.clone()),
+            completed_at: None,
+            document_id: source.book.document_id,
+            parent_id: None,
+            position: TaskRepository::new(self.conn).next_position(None)?,
+            tags: Vec::new(),
+            recurrence: None,
+        };
+        let plan = ReadingPlan {
+            id: None,
+            user_id,
+            book_id,
+            task_id: 0,
+            unit: preview.unit,
+            target_date: preview.target_date.clone(),
+            created_at: None,
+        };
+
+        let sessions = Self::session_tasks(&source, &preview.sessions);
+        let plan_id = match ReadingPlanRepository::new(self.conn).create_plan(&plan, &root, sessions) {
+            Ok(plan_id) => plan_id,
+            Err(err) => {
+                error!("Failed to create reading plan for book {}: {}", book_id, err);
+                return Err(ReadingPlanCommandError::DatabaseError(err.to_string()));
+            }
+        };
+
+        info!("Reading plan {} created with {} sessions", plan_id, preview.sessions.len());
+        ReadingPlanRepository::new(self.conn)
+            .get_plan(plan_id)?
+            .ok_or_else(|| ReadingPlanCommandError::UnexpectedError(format!("Reading plan {} vanished", plan_id)))
+    }
+
+    /// Reschedules what is left of a plan from today on: finished sessions and
+    /// the user's reading position decide where to resume, and the sessions
+    /// that aren't done move onto the new schedule. Pass `target_date` to move
+    /// the deadline.
+    pub fn rebalance_reading_plan_method(
+        &mut self,
+        plan_id: i64,
+        target_date: Option<String>,
+    ) -> Result<ReadingPlanPreview, ReadingPlanCommandError> {
+        info!("Rebalancing reading plan {}", plan_id);
+
+        let plan = ReadingPlanRepository::new(self.conn)
+            .get_plan(plan_id)?
+            .ok_or_else(|| invalid_input(format!("Reading plan with ID {} not found", plan_id)))?;
+        let target_date = target_date.unwrap_or_else(|| plan.target_date.clone());
+        let target = recurrence::parse_date(&target_date).map_err(invalid_input)?;
+
+        let source = self.plan_source(plan.book_id, Some(plan.unit))?;
+        let items = ReadingPlanRepository::new(self.conn).get_items(plan_id)?;
+
+        let read_until = items.iter()
+            .filter(|item| item.status == TaskStatus::Done)
+            .map(|item| item.end_unit + 1)
+            .max();
+        let start_unit = [Some(source.first_unit), read_until, self.current_position(plan.user_id, &source)?]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(source.first_unit);
+
+        let open: Vec<i32> = items.iter()
+            .filter(|item| matches!(item.status, TaskStatus::Todo | TaskStatus::InProgress | TaskStatus::Blocked))
+            .map(|item| item.task_id)
+            .collect();
+
+        let sessions = if start_unit > source.last_unit {
+            Vec::new()
+        } else {
+            self.schedule(plan.user_id, &source, start_unit, today(), target)?
+        };
+
+        let session_tasks = Self::session_tasks(&source, &sessions);
+        if let Err(err) = ReadingPlanRepository::new(self.conn).reschedule(&plan, &open, session_tasks, &target_date) {
+            error!("Failed to rebalance reading plan {}: {}", plan_id, err);
+            return Err(ReadingPlanCommandError::DatabaseError(err.to_string()));
+        }
+
+        info!("Reading plan {} rebalanced into {} sessions", plan_id, sessions.len());
+        Ok(ReadingPlanPreview {
+            book_id: plan.book_id,
+            unit: plan.unit,
+            target_date,
+            sessions,
+        })
+    }
+
+    /// Rebalances every plan with a session left open past its day. Plans that
+    /// can't be rescheduled, e.g. because the target date has passed, are
+    /// skipped. Returns how many plans were rebalanced.
+    pub fn rebalance_overdue_plans_method(&mut self) -> Result<usize, ReadingPlanCommandError> {
+        let today = recurrence::format_date(today());
+        let plan_ids = ReadingPlanRepository::new(self.conn).get_behind_plan_ids(&today)?;
+
+        let mut rebalanced = 0;
+        for plan_id in plan_ids {
+            match self.rebalance_reading_plan_method(plan_id, None) {
+                Ok(_) => rebalanced += 1,
+                Err(err) => error!("Reading plan {} is behind but couldn't be rebalanced: {}", plan_id, err),
+            }
+        }
+        Ok(rebalanced)
+    }
+
+    pub fn list_reading_plans_method(&mut self, book_id: Option<i64>) -> Result<Vec<ReadingPlan>, ReadingPlanCommandError> {
+        info!("Listing reading plans");
+
+        match ReadingPlanRepository::new(self.conn).get_plans(book_id) {
+            Ok(plans) => Ok(plans),
+            Err(err) => {
+                error!("Failed to list reading plans: {}", err);
+                Err(ReadingPlanCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Deletes the plan together with its parent task and sessions.
+    pub fn delete_reading_plan_method(&mut self, plan_id: i64) -> Result<String, ReadingPlanCommandError> {
+        info!("Deleting reading plan {}", plan_id);
+
+        let plan = ReadingPlanRepository::new(self.conn)
+            .get_plan(plan_id)?
+            .ok_or_else(|| invalid_input(format!("Reading plan with ID {} not found", plan_id)))?;
+
+        match TaskRepository::new(self.conn).delete_task(plan.task_id) {
+            Ok(_) => {
+                let success_msg = format!("Reading plan {} deleted successfully", plan_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to delete reading plan {}: {}", plan_id, err);
+                Err(ReadingPlanCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn preview_reading_plan_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    book_id: i64,
+    target_date: String,
+    start_date: Option<String>,
+    unit: Option<String>,
+) -> Result<ReadingPlanPreview, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut plan_commands = ReadingPlanCommands::new(&mut conn);
+
+    match plan_commands.preview_reading_plan_method(user_id, book_id, target_date, start_date, unit) {
+        Ok(preview) => Ok(preview),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn create_reading_plan_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    book_id: i64,
+    target_date: String,
+    start_date: Option<String>,
+    unit: Option<String>,
+) -> Result<ReadingPlan, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut plan_commands = ReadingPlanCommands::new(&mut conn);
+
+    match plan_commands.create_reading_plan_method(user_id, book_id, target_date, start_date, unit) {
+        Ok(plan) => Ok(plan),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn rebalance_reading_plan_command(
+    app_state: tauri::State<'_, AppState>,
+    plan_id: i64,
+    target_date: Option<String>,
+) -> Result<ReadingPlanPreview, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut plan_commands = ReadingPlanCommands::new(&mut conn);
+
+    match plan_commands.rebalance_reading_plan_method(plan_id, target_date) {
+        Ok(preview) => Ok(preview),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_reading_plans_command(
+    app_state: tauri::State<'_, AppState>,
+    book_id: Option<i64>,
+) -> Result<Vec<ReadingPlan>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut plan_commands = ReadingPlanCommands::new(&mut conn);
+
+    match plan_commands.list_reading_plans_method(book_id) {
+        Ok(plans) => Ok(plans),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_reading_plan_command(
+    app_state: tauri::State<'_, AppState>,
+    plan_id: i64,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut plan_commands = ReadingPlanCommands::new(&mut conn);
+
+    match plan_commands.delete_reading_plan_method(plan_id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
@@ -0,0 +1,262 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use chrono::Utc;
+use crate::commands::document_commands::DocumentCommands;
+use crate::db::repositories::{
+    BookRepository, ChapterRepository, DocumentRepository, ReadingProgressRepository, UserRepository,
+};
+use crate::db::models::reading_progress::ReadingProgress;
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+const DEFAULT_RECENT_LIMIT: i64 = 20;
+
+#[derive(Debug, Error, Serialize)]
+pub enum ReadingProgressCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for ReadingProgressCommandError {
+    fn from(err: RusqliteError) -> Self {
+        ReadingProgressCommandError::DatabaseError(err.to_string())
+    }
+}
+
+/// Position reported by the reader when saving progress.
+#[derive(Debug, Default)]
+pub struct ReadingPosition {
+    pub current_page: Option<i32>,
+    pub location: Option<String>,
+    pub chapter_index: Option<i32>,
+    pub chapter_offset: Option<f64>,
+    pub percent_complete: Option<f64>,
+}
+
+pub struct ReadingProgressCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ReadingProgressCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn resolve_user_id(&mut self, user_id: Option<i32>) -> Result<i32, ReadingProgressCommandError> {
+        if let Some(user_id) = user_id {
+            return Ok(user_id);
+        }
+
+        UserRepository::new(self.conn).get_active_user_id()?.ok_or_else(|| {
+            let msg = "No active user found".to_string();
+            error!("{}", msg);
+            ReadingProgressCommandError::InvalidInput(msg)
+        })
+    }
+
+    pub fn save_reading_progress_method(
+        &mut self,
+        user_id: Option<i32>,
+        book_id: i64,
+        position: ReadingPosition,
+    ) -> Result<ReadingProgress, ReadingProgressCommandError> {
+        info!("Saving reading progress for book {}", book_id);
+
+        if position.current_page.is_none() && position.location.is_none() && position.chapter_index.is_none() {
+            let msg = "A page, location or chapter must be provided".to_string();
+            error!("{}", msg);
+            return Err(ReadingProgressCommandError::InvalidInput(msg));
+        }
+
+        if position.current_page.is_some_and(|page| page < 1) {
+            let msg = "Page numbers start at 1".to_string();
+            error!("{}", msg);
+            return Err(ReadingProgressCommandError::InvalidInput(msg));
+        }
+
+        if position.chapter_offset.is_some_and(|offset| !(0.0..=1.0).contains(&offset)) {
+            let msg = "Chapter offset must be between 0 and 1".to_string();
+            error!("{}", msg);
+            return Err(ReadingProgressCommandError::InvalidInput(msg));
+        }
+
+        let user_id = self.resolve_user_id(user_id)?;
+
+        let book = BookRepository::new(self.conn).get_book_by_id(book_id)?.ok_or_else(|| {
+            let msg = format!("Book with ID {} not found", book_id);
+            error!("{}", msg);
+            ReadingProgressCommandError::InvalidInput(msg)
+        })?;
+
+        let percent_complete = match position.percent_complete {
+            Some(percent) => percent,
+            None => self.estimate_percent(book.document_id, &position)?,
+        };
+
+        let mut progress = ReadingProgress {
+            id: None,
+            user_id,
+            book_id,
+            current_page: position.current_page,
+            location: position.location,
+            chapter_index: position.chapter_index,
+            chapter_offset: position.chapter_offset,
+            percent_complete: percent_complete.clamp(0.0, 100.0),
+            last_opened_at: Some(Utc::now()),
+        };
+
+        let id = match ReadingProgressRepository::new(self.conn).save_progress(&progress) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("Failed to save reading progress for book {}: {}", book_id, err);
+                return Err(ReadingProgressCommandError::DatabaseError(err.to_string()));
+            }
+        };
+        progress.id = Some(id);
+
+        if let Some(document_id) = book.document_id {
+            DocumentCommands::new(self.conn)
+                .mark_document_as_accessed(document_id as i32)
+                .map_err(ReadingProgressCommandError::DatabaseError)?;
+        }
+
+        info!("Saved progress for book {}: {:.1}%", book_id, progress.percent_complete);
+        Ok(progress)
+    }
+
+    /// Derives a completion percentage from the page count (PDF) or the
+    /// chapter list (EPUB) when the reader did not compute one itself.
+    fn estimate_percent(
+        &mut self,
+        document_id: Option<i64>,
+        position: &ReadingPosition,
+    ) -> Result<f64, ReadingProgressCommandError> {
+        let Some(document_id) = document_id else {
+            return Ok(0.0);
+        };
+
+        if let Some(chapter_index) = position.chapter_index {
+            let chapters = ChapterRepository::new(self.conn).get_chapters_by_document_id(document_id)?;
+            if !chapters.is_empty() {
+                let read = chapter_index as f64 + position.chapter_offset.unwrap_or(0.0);
+                return Ok(read / chapters.len() as f64 * 100.0);
+            }
+        }
+
+        if let Some(current_page) = position.current_page {
+            let document = DocumentRepository::new(self.conn)
+                .get_by_id(document_id as i32)
+                .map_err(ReadingProgressCommandError::DatabaseError)?;
+            if let Some(document) = document.filter(|d| d.page_count > 0) {
+                return Ok(current_page as f64 / document.page_count as f64 * 100.0);
+            }
+        }
+
+        Ok(0.0)
+    }
+
+    pub fn get_reading_progress_method(
+        &mut self,
+        user_id: Option<i32>,
+        book_id: i64,
+    ) -> Result<Option<ReadingProgress>, ReadingProgressCommandError> {
+        info!("Fetching reading progress for book {}", book_id);
+
+        let user_id = self.resolve_user_id(user_id)?;
+
+        match ReadingProgressRepository::new(self.conn).get_progress(user_id, book_id) {
+            Ok(progress) => Ok(progress),
+            Err(err) => {
+                error!("Failed to fetch reading progress for book {}: {}", book_id, err);
+                Err(ReadingProgressCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn get_recent_reading_method(
+        &mut self,
+        user_id: Option<i32>,
+        limit: Option<i64>,
+    ) -> Result<Vec<ReadingProgress>, ReadingProgressCommandError> {
+        info!("Fetching recently opened books");
+
+        let user_id = self.resolve_user_id(user_id)?;
+        let limit = limit.unwrap_or(DEFAULT_RECENT_LIMIT).max(1);
+
+        match ReadingProgressRepository::new(self.conn).get_recent_progress(user_id, limit) {
+            Ok(progress) => Ok(progress),
+            Err(err) => {
+                error!("Failed to fetch recent reading progress: {}", err);
+                Err(ReadingProgressCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn save_reading_progress_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    book_id: i64,
+    current_page: Option<i32>,
+    location: Option<String>,
+    chapter_index: Option<i32>,
+    chapter_offset: Option<f64>,
+    percent_complete: Option<f64>,
+) -> Result<ReadingProgress, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut progress_commands = ReadingProgressCommands::new(&mut conn);
+
+    let position = ReadingPosition {
+        current_page,
+        location,
+        chapter_index,
+        chapter_offset,
+        percent_complete,
+    };
+
+    match progress_commands.save_reading_progress_method(user_id, book_id, position) {
+        Ok(progress) => Ok(progress),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_reading_progress_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    book_id: i64,
+) -> Result<Option<ReadingProgress>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut progress_commands = ReadingProgressCommands::new(&mut conn);
+
+    match progress_commands.get_reading_progress_method(user_id, book_id) {
+        Ok(progress) => Ok(progress),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_recent_reading_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    limit: Option<i64>,
+) -> Result<Vec<ReadingProgress>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut progress_commands = ReadingProgressCommands::new(&mut conn);
+
+    match progress_commands.get_recent_reading_method(user_id, limit) {
+        Ok(progress) => Ok(progress),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
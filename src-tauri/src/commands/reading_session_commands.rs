@@ -0,0 +1,368 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use chrono::{Duration, Local, NaiveDate, Utc};
+use crate::db::repositories::{BookRepository, ReadingSessionRepository, UserRepository};
+use crate::db::models::reading_session::{ReadingSession, ReadingTime};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+/// Longest gap between heartbeats that still counts as reading. Anything
+/// beyond it (sleep, an idle window left open) is capped to this value.
+const MAX_HEARTBEAT_GAP_SECS: i64 = 120;
+const DEFAULT_RANGE_DAYS: i64 = 30;
+
+#[derive(Debug, Error, Serialize)]
+pub enum ReadingSessionCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for ReadingSessionCommandError {
+    fn from(err: RusqliteError) -> Self {
+        ReadingSessionCommandError::DatabaseError(err.to_string())
+    }
+}
+
+pub struct ReadingSessionCommands<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ReadingSessionCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn resolve_user_id(&mut self, user_id: Option<i32>) -> Result<i32, ReadingSessionCommandError> {
+        if let Some(user_id) = user_id {
+            return Ok(user_id);
+        }
+
+        UserRepository::new(self.conn).get_active_user_id()?.ok_or_else(|| {
+            let msg = "No active user found".to_string();
+            error!("{}", msg);
+            ReadingSessionCommandError::InvalidInput(msg)
+        })
+    }
+
+    fn validate_page(page: Option<i32>) -> Result<(), ReadingSessionCommandError> {
+        if page.is_some_and(|page| page < 1) {
+            let msg = "Page numbers start at 1".to_string();
+            error!("{}", msg);
+            return Err(ReadingSessionCommandError::InvalidInput(msg));
+        }
+        Ok(())
+    }
+
+    fn get_open_session(&mut self, session_id: i64) -> Result<ReadingSession, ReadingSessionCommandError> {
+        let session = ReadingSessionRepository::new(self.conn).get_session(session_id)?.ok_or_else(|| {
+            let msg = format!("Reading session with ID {} not found", session_id);
+            error!("{}", msg);
+            ReadingSessionCommandError::InvalidInput(msg)
+        })?;
+
+        if session.ended_at.is_some() {
+            let msg = format!("Reading session {} has already ended", session_id);
+            error!("{}", msg);
+            return Err(ReadingSessionCommandError::InvalidInput(msg));
+        }
+
+        Ok(session)
+    }
+
+    /// Starts a session for the user, first closing any session they left
+    /// open so a single user never has two clocks running.
+    pub fn start_reading_session_method(
+        &mut self,
+        user_id: Option<i32>,
+        book_id: i64,
+        current_page: Option<i32>,
+    ) -> Result<ReadingSession, ReadingSessionCommandError> {
+        info!("Starting reading session for book {}", book_id);
+
+        Self::validate_page(current_page)?;
+        let user_id = self.resolve_user_id(user_id)?;
+
+        if BookRepository::new(self.conn).get_book_by_id(book_id)?.is_none() {
+            let msg = format!("Book with ID {} not found", book_id);
+            error!("{}", msg);
+            return Err(ReadingSessionCommandError::InvalidInput(msg));
+        }
+
+        let mut repository = ReadingSessionRepository::new(self.conn);
+        let closed = repository.close_stale_sessions(Some(user_id))?;
+        if closed > 0 {
+            info!("Closed {} open reading session(s) for user {}", closed, user_id);
+        }
+
+        let session_id = match repository.start_session(user_id, book_id, current_page, Utc::now()) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("Failed to start reading session for book {}: {}", book_id, err);
+                return Err(ReadingSessionCommandError::DatabaseError(err.to_string()));
+            }
+        };
+
+        repository.get_session(session_id)?.ok_or_else(|| {
+            ReadingSessionCommandError::UnexpectedError(format!("Session {} vanished after insert", session_id))
+        })
+    }
+
+    /// Credits the time since the last heartbeat to the page that was on
+    /// screen, then records `current_page` as the page now being read.
+    pub fn heartbeat_reading_session_method(
+        &mut self,
+        session_id: i64,
+        current_page: Option<i32>,
+    ) -> Result<ReadingSession, ReadingSessionCommandError> {
+        Self::validate_page(current_page)?;
+
+        let session = self.get_open_session(session_id)?;
+        self.record_heartbeat(&session, current_page)?;
+
+        ReadingSessionRepository::new(self.conn).get_session(session_id)?.ok_or_else(|| {
+            ReadingSessionCommandError::UnexpectedError(format!("Session {} vanished after heartbeat", session_id))
+        })
+    }
+
+    fn record_heartbeat(
+        &mut self,
+        session: &ReadingSession,
+        current_page: Option<i32>,
+    ) -> Result<(), ReadingSessionCommandError> {
+        let now = Utc::now();
+        let dwell_seconds = (now - session.last_heartbeat_at)
+            .num_seconds()
+            .clamp(0, MAX_HEARTBEAT_GAP_SECS);
+
+        match ReadingSessionRepository::new(self.conn).record_heartbeat(session, current_page, dwell_seconds, now) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                error!("Failed to record heartbeat for session {:?}: {}", session.id, err);
+                Err(ReadingSessionCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn stop_reading_session_method(
+        &mut self,
+        session_id: i64,
+        current_page: Option<i32>,
+    ) -> Result<ReadingSession, ReadingSessionCommandError> {
+        info!("Stopping reading session {}", session_id);
+
+        Self::validate_page(current_page)?;
+
+        let session = self.get_open_session(session_id)?;
+        self.record_heartbeat(&session, current_page)?;
+
+        let mut repository = ReadingSessionRepository::new(self.conn);
+        repository.end_session(session_id, Utc::now(), "stopped")?;
+
+        repository.get_session(session_id)?.ok_or_else(|| {
+            ReadingSessionCommandError::UnexpectedError(format!("Session {} vanished after stop", session_id))
+        })
+    }
+
+    pub fn get_reading_session_method(
+        &mut self,
+        session_id: i64,
+    ) -> Result<Option<ReadingSession>, ReadingSessionCommandError> {
+        info!("Fetching reading session {}", session_id);
+
+        match ReadingSessionRepository::new(self.conn).get_session(session_id) {
+            Ok(session) => Ok(session),
+            Err(err) => {
+                error!("Failed to fetch reading session {}: {}", session_id, err);
+                Err(ReadingSessionCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Resolves an inclusive `YYYY-MM-DD` range in local time, defaulting to
+    /// the last thirty days.
+    fn resolve_range(
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<(String, String), ReadingSessionCommandError> {
+        let parse = |value: String| {
+            NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|_| {
+                let msg = format!("Invalid date '{}', expected YYYY-MM-DD", value);
+                error!("{}", msg);
+                ReadingSessionCommandError::InvalidInput(msg)
+            })
+        };
+
+        let to = match to {
+            Some(to) => parse(to)?,
+            None => Local::now().date_naive(),
+        };
+        let from = match from {
+            Some(from) => parse(from)?,
+            None => to - Duration::days(DEFAULT_RANGE_DAYS - 1),
+        };
+
+        if from > to {
+            let msg = "The start date must not be after the end date".to_string();
+            error!("{}", msg);
+            return Err(ReadingSessionCommandError::InvalidInput(msg));
+        }
+
+        Ok((from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string()))
+    }
+
+    pub fn get_reading_minutes_per_day_method(
+        &mut self,
+        user_id: Option<i32>,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<Vec<ReadingTime>, ReadingSessionCommandError> {
+        let (from, to) = Self::resolve_range(from, to)?;
+        info!("Fetching minutes read per day from {} to {}", from, to);
+
+        let user_id = self.resolve_user_id(user_id)?;
+        Ok(ReadingSessionRepository::new(self.conn).get_minutes_per_day(user_id, &from, &to)?)
+    }
+
+    pub fn get_reading_minutes_per_book_method(
+        &mut self,
+        user_id: Option<i32>,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<Vec<ReadingTime>, ReadingSessionCommandError> {
+        let (from, to) = Self::resolve_range(from, to)?;
+        info!("Fetching minutes read per book from {} to {}", from, to);
+
+        let user_id = self.resolve_user_id(user_id)?;
+        Ok(ReadingSessionRepository::new(self.conn).get_minutes_per_book(user_id, &from, &to)?)
+    }
+
+    pub fn get_reading_minutes_per_tag_method(
+        &mut self,
+        user_id: Option<i32>,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<Vec<ReadingTime>, ReadingSessionCommandError> {
+        let (from, to) = Self::resolve_range(from, to)?;
+        info!("Fetching minutes read per tag from {} to {}", from, to);
+
+        let user_id = self.resolve_user_id(user_id)?;
+        Ok(ReadingSessionRepository::new(self.conn).get_minutes_per_tag(user_id, &from, &to)?)
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn start_reading_session_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    book_id: i64,
+    current_page: Option<i32>,
+) -> Result<ReadingSession, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.start_reading_session_method(user_id, book_id, current_page) {
+        Ok(session) => Ok(session),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn heartbeat_reading_session_command(
+    app_state: tauri::State<'_, AppState>,
+    session_id: i64,
+    current_page: Option<i32>,
+) -> Result<ReadingSession, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.heartbeat_reading_session_method(session_id, current_page) {
+        Ok(session) => Ok(session),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn stop_reading_session_command(
+    app_state: tauri::State<'_, AppState>,
+    session_id: i64,
+    current_page: Option<i32>,
+) -> Result<ReadingSession, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.stop_reading_session_method(session_id, current_page) {
+        Ok(session) => Ok(session),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_reading_session_command(
+    app_state: tauri::State<'_, AppState>,
+    session_id: i64,
+) -> Result<Option<ReadingSession>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.get_reading_session_method(session_id) {
+        Ok(session) => Ok(session),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_reading_minutes_per_day_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<ReadingTime>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.get_reading_minutes_per_day_method(user_id, from, to) {
+        Ok(totals) => Ok(totals),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_reading_minutes_per_book_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<ReadingTime>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.get_reading_minutes_per_book_method(user_id, from, to) {
+        Ok(totals) => Ok(totals),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_reading_minutes_per_tag_command(
+    app_state: tauri::State<'_, AppState>,
+    user_id: Option<i32>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<ReadingTime>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut session_commands = ReadingSessionCommands::new(&mut conn);
+
+    match session_commands.get_reading_minutes_per_tag_method(user_id, from, to) {
+        Ok(totals) => Ok(totals),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
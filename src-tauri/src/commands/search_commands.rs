@@ -0,0 +1,92 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info};
+use crate::db::repositories::PageTextRepository;
+use crate::db::models::search_hit::SearchHit;
+use crate::services::build_match_query;
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+const MAX_SEARCH_LIMIT: i64 = 500;
+
+#[derive(Debug, Error, Serialize)]
+pub enum SearchCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for SearchCommandError {
+    fn from(err: RusqliteError) -> Self {
+        SearchCommandError::DatabaseError(err.to_string())
+    }
+}
+
+pub struct SearchCommands<'a> {
+    repository: PageTextRepository<'a>,
+}
+
+impl<'a> SearchCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        let repository = PageTextRepository::new(conn);
+        Self { repository }
+    }
+
+    pub fn search_library_method(
+        &self,
+        query: String,
+        tag_ids: Vec<i32>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<SearchHit>, SearchCommandError> {
+        info!("Searching the library for '{}'", query);
+
+        let match_query = match build_match_query(&query) {
+            Some(match_query) => match_query,
+            None => {
+                let msg = "You must provide a search term".to_string();
+                error!("{}", msg);
+                return Err(SearchCommandError::InvalidInput(msg));
+            }
+        };
+
+        let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+        let offset = offset.unwrap_or(0).max(0);
+
+        match self.repository.search(&match_query, &tag_ids, limit, offset) {
+            Ok(hits) => {
+                info!("Found {} hits for '{}'", hits.len(), query);
+                Ok(hits)
+            }
+            Err(err) => {
+                error!("Failed to search the library: {}", err);
+                Err(SearchCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn search_library_command(
+    app_state: tauri::State<'_, AppState>,
+    query: String,
+    tag_ids: Option<Vec<i32>>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<SearchHit>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let search_commands = SearchCommands::new(&mut conn);
+
+    match search_commands.search_library_method(query, tag_ids.unwrap_or_default(), limit, offset) {
+        Ok(hits) => Ok(hits),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
@@ -0,0 +1,988 @@
+use serde::Serialize;
+use rusqlite::{Connection, Error as RusqliteError};
+use thiserror::Error;
+use log::{error, info, warn};
+use std::collections::{HashMap, HashSet};
+use chrono::Utc;
+use crate::db::repositories::TaskRepository;
+use crate::db::models::{
+    tag::Tag,
+    task::{
+        RecurrenceFrequency, RecurrenceRule, Task, TaskFilter, TaskNode, TaskOccurrence, TaskProgress,
+        TaskStatus,
+    },
+    user_available_day::DayOfWeek,
+};
+use crate::services::recurrence::{self, Occurrences};
+use crate::AppState;
+use tauri::ipc::InvokeError;
+
+const MAX_UPCOMING_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Error, Serialize)]
+pub enum TaskCommandError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Invalid status transition: {0}")]
+    InvalidTransition(String),
+
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+impl From<RusqliteError> for TaskCommandError {
+    fn from(err: RusqliteError) -> Self {
+        TaskCommandError::DatabaseError(err.to_string())
+    }
+}
+
+fn parse_status(status: &str) -> Result<TaskStatus, TaskCommandError> {
+    TaskStatus::parse(status).ok_or_else(|| {
+        let msg = format!("Invalid status provided: {}", status);
+        error!("{}", msg);
+        TaskCommandError::InvalidInput(msg)
+    })
+}
+
+fn build_node(task: Task, children: &mut HashMap<i32, Vec<Task>>) -> TaskNode {
+    let mut subtasks = children.remove(&task.id).unwrap_or_default();
+    subtasks.sort_by_key(|subtask| (subtask.position, subtask.id));
+
+    let progress = if subtasks.is_empty() {
+        None
+    } else {
+        let counted = subtasks.iter().filter(|subtask| subtask.status != TaskStatus::Archived);
+        Some(TaskProgress {
+            done: counted.clone().filter(|subtask| subtask.status == TaskStatus::Done).count() as u32,
+            total: counted.count() as u32,
+        })
+    };
+
+    TaskNode {
+        task,
+        progress,
+        children: subtasks.into_iter().map(|subtask| build_node(subtask, children)).collect(),
+    }
+}
+
+pub struct TaskCommands<'a> {
+    repository: TaskRepository<'a>,
+}
+
+impl<'a> TaskCommands<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        let repository = TaskRepository::new(conn);
+        Self { repository }
+    }
+
+    fn get_existing(&self, id: i32) -> Result<Task, TaskCommandError> {
+        self.repository.get_task_by_id(id)?.ok_or_else(|| {
+            let msg = format!("Task with ID {} not found", id);
+            error!("{}", msg);
+            TaskCommandError::InvalidInput(msg)
+        })
+    }
+
+    /// Writes the task back. A recurring task that has just been completed
+    /// also gets its next occurrence created, whose ID is returned, and
+    /// archiving a task archives its subtasks too.
+    fn save_task(&mut self, task: &Task, previous_status: TaskStatus) -> Result<Option<i64>, TaskCommandError> {
+        let completed_now = previous_status != TaskStatus::Done && task.status == TaskStatus::Done;
+
+        let next_due_date = match (&task.recurrence, &task.due_date) {
+            (Some(rule), Some(due_date)) if completed_now => {
+                let due_date = recurrence::parse_date(due_date).map_err(TaskCommandError::InvalidInput)?;
+                recurrence::next_occurrence_after(rule, due_date)
+                    .map_err(TaskCommandError::UnexpectedError)?
+                    .map(|(_, date)| recurrence::format_date(date))
+            }
+            _ => None,
+        };
+
+        match next_due_date {
+            Some(next_due_date) => {
+                let next_id = self.repository.complete_recurring_task(task, &next_due_date)?;
+                info!("Created next occurrence of task {} with ID {}, due {}", task.id, next_id, next_due_date);
+                Ok(Some(next_id))
+            }
+            None if previous_status != TaskStatus::Archived && task.status == TaskStatus::Archived => {
+                let archived = self.repository.archive_task_tree(task)?;
+                if archived > 0 {
+                    info!("Archived {} subtasks of task {}", archived, task.id);
+                }
+                Ok(None)
+            }
+            None => {
+                self.repository.update_task(task)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn get_parent(&self, parent_id: i32) -> Result<Task, TaskCommandError> {
+        let parent = self.repository.get_task_by_id(parent_id)?.ok_or_else(|| {
+            let msg = format!("Parent task with ID {} not found", parent_id);
+            error!("{}", msg);
+            TaskCommandError::InvalidInput(msg)
+        })?;
+
+        if parent.status == TaskStatus::Archived {
+            let msg = format!("Parent task {} is archived", parent_id);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        Ok(parent)
+    }
+
+    fn validate_date(date: Option<&str>) -> Result<(), TaskCommandError> {
+        if let Some(date) = date {
+            if let Err(msg) = recurrence::parse_date(date) {
+                error!("{}", msg);
+                return Err(TaskCommandError::InvalidInput(msg));
+            }
+        }
+        Ok(())
+    }
+
+    /// A repeating task is always one occurrence of its series, so its due
+    /// date can only move to another occurrence and can't be cleared.
+    fn validate_recurring_due_date(task: &Task) -> Result<(), TaskCommandError> {
+        let Some(rule) = &task.recurrence else {
+            return Ok(());
+        };
+
+        let Some(due_date) = task.due_date.as_deref() else {
+            let msg = format!("Task {} repeats, so it needs a due date; clear its recurrence first", task.id);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        };
+
+        let due = recurrence::parse_date(due_date).map_err(TaskCommandError::InvalidInput)?;
+        if recurrence::occurrence_on(rule, due).map_err(TaskCommandError::InvalidInput)?.is_none() {
+            let msg = format!("The due date {} isn't an occurrence of task {}'s recurrence", due_date, task.id);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+        Ok(())
+    }
+
+    fn validate_document(&self, document_id: Option<i64>) -> Result<(), TaskCommandError> {
+        if let Some(document_id) = document_id {
+            if !self.repository.document_exists(document_id)? {
+                let msg = format!("Document with ID {} not found", document_id);
+                error!("{}", msg);
+                return Err(TaskCommandError::InvalidInput(msg));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn create_task_method(
+        &mut self,
+        title: String,
+        description: Option<String>,
+        due_date: Option<String>,
+        document_id: Option<i64>,
+        parent_id: Option<i32>,
+        tags: Vec<Tag>,
+    ) -> Result<String, TaskCommandError> {
+        info!("Starting the process of creating a new task");
+
+        if title.trim().is_empty() {
+            let msg = "You must provide a title".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        Self::validate_date(due_date.as_deref())?;
+        self.validate_document(document_id)?;
+
+        // Subtasks belong to their parent's document unless told otherwise.
+        let document_id = match parent_id {
+            Some(parent_id) => {
+                let parent = self.get_parent(parent_id)?;
+                document_id.or(parent.document_id)
+            }
+            None => document_id,
+        };
+        let position = self.repository.next_position(parent_id)?;
+
+        let task = Task {
+            id: 0,
+            title: title.trim().to_string(),
+            description,
+            status: TaskStatus::Todo,
+            created_at: Utc::now().to_rfc3339(),
+            due_date,
+            completed_at: None,
+            document_id,
+            parent_id,
+            position,
+            tags,
+            recurrence: None,
+        };
+
+        match self.repository.create_task(&task) {
+            Ok(task_id) => {
+                let success_msg = format!("Task created successfully with ID {}", task_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to create task: {}", err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Changes the given fields. `None` leaves a field as it is; the
+    /// `clear_*` flags empty the optional ones.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_task_method(
+        &mut self,
+        id: i32,
+        title: Option<String>,
+        description: Option<String>,
+        status: Option<String>,
+        due_date: Option<String>,
+        document_id: Option<i64>,
+        clear_description: bool,
+        clear_due_date: bool,
+        clear_document: bool,
+    ) -> Result<String, TaskCommandError> {
+        info!("Starting the process of updating task with ID {}", id);
+
+        let conflicting = (clear_description && description.is_some())
+            || (clear_due_date && due_date.is_some())
+            || (clear_document && document_id.is_some());
+        if conflicting {
+            let msg = "A field cannot be set and cleared at once".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        if title.as_ref().is_some_and(|t| t.trim().is_empty()) {
+            let msg = "Title cannot be empty".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        let status = status.as_deref().map(parse_status).transpose()?;
+
+        Self::validate_date(due_date.as_deref())?;
+        self.validate_document(document_id)?;
+
+        let existing_task = self.get_existing(id)?;
+        let previous_status = existing_task.status;
+        let previous_due_date = existing_task.due_date.clone();
+
+        let (status, completed_at) = match status {
+            Some(status) => Self::transition(&existing_task, status)?,
+            None => (existing_task.status, existing_task.completed_at),
+        };
+
+        let task = Task {
+            id,
+            title: title.map(|t| t.trim().to_string()).unwrap_or(existing_task.title),
+            description: if clear_description { None } else { description.or(existing_task.description) },
+            status,
+            created_at: existing_task.created_at,
+            due_date: if clear_due_date { None } else { due_date.or(existing_task.due_date) },
+            completed_at,
+            document_id: if clear_document { None } else { document_id.or(existing_task.document_id) },
+            parent_id: existing_task.parent_id,
+            position: existing_task.position,
+            tags: existing_task.tags,
+            recurrence: existing_task.recurrence,
+        };
+
+        if task.due_date != previous_due_date {
+            Self::validate_recurring_due_date(&task)?;
+        }
+
+        match self.save_task(&task, previous_status) {
+            Ok(next_id) => {
+                let mut success_msg = format!("Task with ID {} updated successfully", id);
+                if let Some(next_id) = next_id {
+                    success_msg.push_str(&format!("; next occurrence created with ID {}", next_id));
+                }
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to update task with ID {}: {}", id, err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Checks a status change against the transition rules and works out the
+    /// matching `completed_at`: stamped on entering `done`, cleared on leaving it.
+    fn transition(task: &Task, status: TaskStatus) -> Result<(TaskStatus, Option<String>), TaskCommandError> {
+        if !task.status.can_move_to(status) {
+            let msg = format!(
+                "Task {} cannot move from '{}' to '{}'",
+                task.id,
+                task.status.as_str(),
+                status.as_str()
+            );
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidTransition(msg));
+        }
+
+        let completed_at = match (task.status, status) {
+            (TaskStatus::Done, TaskStatus::Done) => task.completed_at.clone(),
+            (_, TaskStatus::Done) => Some(Utc::now().to_rfc3339()),
+            _ => None,
+        };
+
+        Ok((status, completed_at))
+    }
+
+    pub fn set_task_status_method(
+        &mut self,
+        id: i32,
+        status: String,
+    ) -> Result<String, TaskCommandError> {
+        info!("Changing status of task {} to '{}'", id, status);
+
+        let status = parse_status(&status)?;
+
+        let mut task = self.get_existing(id)?;
+        let previous_status = task.status;
+
+        let (status, completed_at) = Self::transition(&task, status)?;
+        task.status = status;
+        task.completed_at = completed_at;
+
+        match self.save_task(&task, previous_status) {
+            Ok(next_id) => {
+                let mut success_msg = format!("Task {} is now '{}'", id, status.as_str());
+                if let Some(next_id) = next_id {
+                    success_msg.push_str(&format!("; next occurrence created with ID {}", next_id));
+                }
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to change status of task {}: {}", id, err);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn set_task_recurrence_method(
+        &mut self,
+        task_id: i32,
+        frequency: String,
+        interval: Option<u32>,
+        weekdays: Vec<String>,
+        until: Option<String>,
+        count: Option<u32>,
+    ) -> Result<String, TaskCommandError> {
+        info!("Setting a {} recurrence on task {}", frequency, task_id);
+
+        let task = self.get_existing(task_id)?;
+
+        if matches!(task.status, TaskStatus::Done | TaskStatus::Archived) {
+            let msg = format!("Task {} is '{}' and can't start a series", task_id, task.status.as_str());
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        let Some(starts_on) = task.due_date.clone() else {
+            let msg = format!("Task {} needs a due date before it can repeat", task_id);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        };
+
+        let frequency = RecurrenceFrequency::parse(&frequency).ok_or_else(|| {
+            let msg = format!("Invalid frequency provided: {}", frequency);
+            error!("{}", msg);
+            TaskCommandError::InvalidInput(msg)
+        })?;
+
+        let interval = interval.unwrap_or(1);
+        if interval == 0 {
+            let msg = "The interval must be at least 1".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        let weekdays = weekdays
+            .iter()
+            .map(|day| {
+                DayOfWeek::from_str(day).ok_or_else(|| {
+                    let msg = format!("Invalid day of week provided: {}", day);
+                    error!("{}", msg);
+                    TaskCommandError::InvalidInput(msg)
+                })
+            })
+            .collect::<Result<Vec<DayOfWeek>, _>>()?;
+
+        if !weekdays.is_empty() && frequency != RecurrenceFrequency::Weekly {
+            let msg = "Days of the week only apply to weekly recurrences".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        if count == Some(0) {
+            let msg = "The count must be at least 1".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        let start = recurrence::parse_date(&starts_on).map_err(TaskCommandError::InvalidInput)?;
+        if let Some(until) = until.as_deref() {
+            if recurrence::parse_date(until).map_err(TaskCommandError::InvalidInput)? < start {
+                let msg = format!("The end date {} is before the due date {}", until, starts_on);
+                error!("{}", msg);
+                return Err(TaskCommandError::InvalidInput(msg));
+            }
+        }
+
+        let rule = RecurrenceRule {
+            frequency,
+            interval,
+            weekdays,
+            starts_on,
+            until,
+            count,
+        };
+
+        // The task itself is the first occurrence, so its due date has to be one.
+        let first = Occurrences::new(&rule).map_err(TaskCommandError::InvalidInput)?.next();
+        if first.map(|(_, date)| date) != Some(start) {
+            let msg = format!("The due date {} doesn't fall on one of the chosen days", rule.starts_on);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        match self.repository.set_recurrence(task_id, Some(&rule)) {
+            Ok(_) => {
+                let success_msg = format!("Task {} now repeats {}", task_id, rule.frequency.as_str());
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to set recurrence on task {}: {}", task_id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn clear_task_recurrence_method(&mut self, task_id: i32) -> Result<String, TaskCommandError> {
+        info!("Clearing the recurrence of task {}", task_id);
+
+        match self.repository.set_recurrence(task_id, None) {
+            Ok(0) => {
+                let msg = format!("Task {} doesn't repeat", task_id);
+                error!("{}", msg);
+                Err(TaskCommandError::InvalidInput(msg))
+            }
+            Ok(_) => {
+                let success_msg = format!("Task {} no longer repeats", task_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to clear recurrence of task {}: {}", task_id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Open tasks due within `from..=to`, together with the future occurrences
+    /// of recurring tasks in that range. Occurrences are computed from the rule
+    /// and only become tasks when the previous one is completed.
+    pub fn list_upcoming_occurrences_method(
+        &self,
+        from: String,
+        to: String,
+    ) -> Result<Vec<TaskOccurrence>, TaskCommandError> {
+        info!("Listing task occurrences from {} to {}", from, to);
+
+        let from_date = recurrence::parse_date(&from).map_err(TaskCommandError::InvalidInput)?;
+        let to_date = recurrence::parse_date(&to).map_err(TaskCommandError::InvalidInput)?;
+        if to_date < from_date {
+            let msg = format!("The range end {} is before its start {}", to, from);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+        if (to_date - from_date).num_days() > MAX_UPCOMING_RANGE_DAYS {
+            let msg = format!("The range can span at most {} days", MAX_UPCOMING_RANGE_DAYS);
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        let tasks = self.repository.get_tasks(&TaskFilter::default())?;
+
+        let mut occurrences = Vec::new();
+        for task in tasks {
+            if matches!(task.status, TaskStatus::Done | TaskStatus::Archived) {
+                continue;
+            }
+            let Some(due_date) = task.due_date.as_deref() else {
+                continue;
+            };
+            // One task with bad data shouldn't hide everyone else's.
+            let due = match recurrence::parse_date(due_date) {
+                Ok(due) => due,
+                Err(err) => {
+                    warn!("Skipping task {} with an unreadable due date: {}", task.id, err);
+                    continue;
+                }
+            };
+            let occurrence = match task.recurrence.as_ref().map(|rule| recurrence::occurrence_on(rule, due)) {
+                Some(Ok(occurrence)) => occurrence,
+                Some(Err(err)) => {
+                    warn!("Skipping task {} with an unreadable recurrence: {}", task.id, err);
+                    continue;
+                }
+                None => None,
+            };
+
+            if due >= from_date && due <= to_date {
+                occurrences.push(TaskOccurrence {
+                    task_id: task.id,
+                    title: task.title.clone(),
+                    due_date: due_date.to_string(),
+                    occurrence,
+                    materialized: true,
+                });
+            }
+
+            if let Some(rule) = &task.recurrence {
+                let upcoming = recurrence::occurrences_between(rule, from_date, to_date).unwrap_or_default();
+                for (index, date) in upcoming.into_iter().filter(|(_, date)| *date > due) {
+                    occurrences.push(TaskOccurrence {
+                        task_id: task.id,
+                        title: task.title.clone(),
+                        due_date: recurrence::format_date(date),
+                        occurrence: Some(index),
+                        materialized: false,
+                    });
+                }
+            }
+        }
+
+        occurrences.sort_by(|a, b| a.due_date.cmp(&b.due_date).then(a.task_id.cmp(&b.task_id)));
+        info!("Found {} task occurrences", occurrences.len());
+        Ok(occurrences)
+    }
+
+    /// Moves a task under another parent (or to the top level when `None`)
+    /// at the given position among its siblings, defaulting to the end.
+    pub fn move_task_method(
+        &mut self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: Option<usize>,
+    ) -> Result<String, TaskCommandError> {
+        info!("Moving task {} under {:?}", id, parent_id);
+
+        self.get_existing(id)?;
+
+        if let Some(parent_id) = parent_id {
+            self.get_parent(parent_id)?;
+            if parent_id == id || self.repository.get_descendant_ids(id)?.contains(&parent_id) {
+                let msg = format!("Task {} can't be moved under itself or one of its subtasks", id);
+                error!("{}", msg);
+                return Err(TaskCommandError::InvalidInput(msg));
+            }
+        }
+
+        match self.repository.move_task(id, parent_id, position.unwrap_or(usize::MAX)) {
+            Ok(_) => {
+                let success_msg = format!("Task {} moved successfully", id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to move task {}: {}", id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    /// Tasks of a document arranged by parent, with subtasks in order and
+    /// each parent's progress over its direct subtasks.
+    pub fn get_document_task_tree_method(&self, document_id: i64) -> Result<Vec<TaskNode>, TaskCommandError> {
+        info!("Building the task tree of document {}", document_id);
+
+        let filter = TaskFilter {
+            document_id: Some(document_id),
+            ..Default::default()
+        };
+        let tasks = match self.repository.get_tasks(&filter) {
+            Ok(tasks) => tasks,
+            Err(err) => {
+                error!("Failed to fetch tasks of document {}: {}", document_id, err);
+                return Err(TaskCommandError::DatabaseError(err.to_string()));
+            }
+        };
+
+        let ids: HashSet<i32> = tasks.iter().map(|task| task.id).collect();
+        let mut roots = Vec::new();
+        let mut children: HashMap<i32, Vec<Task>> = HashMap::new();
+        for task in tasks {
+            match task.parent_id.filter(|parent_id| ids.contains(parent_id)) {
+                Some(parent_id) => children.entry(parent_id).or_default().push(task),
+                None => roots.push(task),
+            }
+        }
+
+        Ok(roots.into_iter().map(|task| build_node(task, &mut children)).collect())
+    }
+
+    pub fn delete_task_method(
+        &mut self,
+        id: i32,
+    ) -> Result<String, TaskCommandError> {
+        info!("Starting the process of deleting task with ID {}", id);
+
+        match self.repository.delete_task(id) {
+            Ok(0) => {
+                let msg = format!("Task with ID {} not found", id);
+                error!("{}", msg);
+                Err(TaskCommandError::InvalidInput(msg))
+            }
+            Ok(1) => {
+                let success_msg = format!("Task with ID {} deleted successfully", id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Ok(deleted) => {
+                let success_msg = format!("Task with ID {} and {} subtasks deleted successfully", id, deleted - 1);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to delete task with ID {}: {}", id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn get_task_by_id_method(&self, id: i32) -> Result<Option<Task>, TaskCommandError> {
+        info!("Fetching task with ID {}", id);
+
+        match self.repository.get_task_by_id(id) {
+            Ok(task) => Ok(task),
+            Err(err) => {
+                error!("Failed to fetch task with ID {}: {}", id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn list_tasks_method(&self, filter: TaskFilter) -> Result<Vec<Task>, TaskCommandError> {
+        info!("Fetching tasks with {:?}", filter);
+
+        Self::validate_date(filter.due_from.as_deref())?;
+        Self::validate_date(filter.due_to.as_deref())?;
+
+        match self.repository.get_tasks(&filter) {
+            Ok(tasks) => {
+                info!("Successfully fetched {} tasks", tasks.len());
+                Ok(tasks)
+            }
+            Err(err) => {
+                error!("Failed to fetch tasks: {}", err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn add_tags_to_task_method(
+        &mut self,
+        task_id: i32,
+        tags: Vec<Tag>,
+    ) -> Result<String, TaskCommandError> {
+        info!("Starting process of adding tags to task {}", task_id);
+
+        if tags.is_empty() {
+            let msg = "No tags provided".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        match self.repository.add_tags_to_task(task_id, tags) {
+            Ok(_) => {
+                let success_msg = format!("Tags added successfully to task {}", task_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to add tags to task {}: {}", task_id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn remove_tags_from_task_method(
+        &mut self,
+        task_id: i32,
+        tags: Vec<Tag>,
+    ) -> Result<String, TaskCommandError> {
+        info!("Starting process of removing tags from task {}", task_id);
+
+        if tags.is_empty() {
+            let msg = "No tags provided".to_string();
+            error!("{}", msg);
+            return Err(TaskCommandError::InvalidInput(msg));
+        }
+
+        match self.repository.remove_tags_from_task(task_id, tags) {
+            Ok(_) => {
+                let success_msg = format!("Tags removed successfully from task {}", task_id);
+                info!("{}", success_msg);
+                Ok(success_msg)
+            }
+            Err(err) => {
+                error!("Failed to remove tags from task {}: {}", task_id, err);
+                Err(TaskCommandError::DatabaseError(err.to_string()))
+            }
+        }
+    }
+}
+
+// Tauri commands
+#[tauri::command]
+pub fn create_task_command(
+    app_state: tauri::State<'_, AppState>,
+    title: String,
+    description: Option<String>,
+    due_date: Option<String>,
+    document_id: Option<i64>,
+    parent_id: Option<i32>,
+    tags: Option<Vec<Tag>>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.create_task_method(title, description, due_date, document_id, parent_id, tags.unwrap_or_default()) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_task_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+    title: Option<String>,
+    description: Option<String>,
+    status: Option<String>,
+    due_date: Option<String>,
+    document_id: Option<i64>,
+    clear_description: Option<bool>,
+    clear_due_date: Option<bool>,
+    clear_document: Option<bool>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.update_task_method(
+        id,
+        title,
+        description,
+        status,
+        due_date,
+        document_id,
+        clear_description.unwrap_or(false),
+        clear_due_date.unwrap_or(false),
+        clear_document.unwrap_or(false),
+    ) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn set_task_status_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+    status: String,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.set_task_status_method(id, status) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn set_task_recurrence_command(
+    app_state: tauri::State<'_, AppState>,
+    task_id: i32,
+    frequency: String,
+    interval: Option<u32>,
+    weekdays: Option<Vec<String>>,
+    until: Option<String>,
+    count: Option<u32>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.set_task_recurrence_method(task_id, frequency, interval, weekdays.unwrap_or_default(), until, count) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn clear_task_recurrence_command(
+    app_state: tauri::State<'_, AppState>,
+    task_id: i32,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.clear_task_recurrence_method(task_id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_upcoming_task_occurrences_command(
+    app_state: tauri::State<'_, AppState>,
+    from: String,
+    to: String,
+) -> Result<Vec<TaskOccurrence>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.list_upcoming_occurrences_method(from, to) {
+        Ok(occurrences) => Ok(occurrences),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn move_task_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+    parent_id: Option<i32>,
+    position: Option<usize>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.move_task_method(id, parent_id, position) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_document_task_tree_command(
+    app_state: tauri::State<'_, AppState>,
+    document_id: i64,
+) -> Result<Vec<TaskNode>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.get_document_task_tree_method(document_id) {
+        Ok(tree) => Ok(tree),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn delete_task_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.delete_task_method(id) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn get_task_by_id_command(
+    app_state: tauri::State<'_, AppState>,
+    id: i32,
+) -> Result<Option<Task>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.get_task_by_id_method(id) {
+        Ok(task) => Ok(task),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn list_tasks_command(
+    app_state: tauri::State<'_, AppState>,
+    status: Option<String>,
+    due_from: Option<String>,
+    due_to: Option<String>,
+    document_id: Option<i64>,
+    tag_id: Option<i32>,
+) -> Result<Vec<Task>, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let task_commands = TaskCommands::new(&mut conn);
+
+    let status = match status.as_deref().map(parse_status).transpose() {
+        Ok(status) => status,
+        Err(err) => return Err(InvokeError::from(err)),
+    };
+
+    let filter = TaskFilter {
+        status,
+        due_from,
+        due_to,
+        document_id,
+        tag_id,
+    };
+
+    match task_commands.list_tasks_method(filter) {
+        Ok(tasks) => Ok(tasks),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn add_tags_to_task_command(
+    app_state: tauri::State<'_, AppState>,
+    task_id: i32,
+    tags: Vec<Tag>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.add_tags_to_task_method(task_id, tags) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
+
+#[tauri::command]
+pub fn remove_tags_from_task_command(
+    app_state: tauri::State<'_, AppState>,
+    task_id: i32,
+    tags: Vec<Tag>,
+) -> Result<String, InvokeError> {
+    let mut conn = app_state.db_conn.lock().unwrap();
+    let mut task_commands = TaskCommands::new(&mut conn);
+
+    match task_commands.remove_tags_from_task_method(task_id, tags) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(InvokeError::from(err)),
+    }
+}
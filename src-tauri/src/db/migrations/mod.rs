@@ -1,3 +1,139 @@
 pub mod v1_initial_schema;
+pub mod v2_documents;
+pub mod v3_book_documents;
+pub mod v4_unique_document_hash;
+pub mod v5_document_metadata;
+pub mod v6_epub_chapters;
+pub mod v7_page_search;
+pub mod v8_reading_progress;
+pub mod v9_reading_sessions;
+pub mod v10_annotations;
+pub mod v11_bookmarks;
+pub mod v12_tasks;
+pub mod v13_task_status;
+pub mod v14_task_recurrence;
+pub mod v15_subtasks;
+pub mod v16_reading_plans;
+pub mod v17_flashcards;
+pub mod v18_cloze_cards;
+pub mod v19_anki_notes;
+pub mod v20_quizzes;
+pub mod v21_pomodoros;
+pub mod v22_pdf_annotation_refs;
 
-pub use v1_initial_schema::*;
\ No newline at end of file
+use rusqlite::{params, Connection, Transaction};
+use thiserror::Error;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+// Append new migrations at the end; never edit or reorder one that has shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial_schema", up: v1_initial_schema::up },
+    Migration { version: 2, name: "documents", up: v2_documents::up },
+    Migration { version: 3, name: "book_documents", up: v3_book_documents::up },
+    Migration { version: 4, name: "unique_document_hash", up: v4_unique_document_hash::up },
+    Migration { version: 5, name: "document_metadata", up: v5_document_metadata::up },
+    Migration { version: 6, name: "epub_chapters", up: v6_epub_chapters::up },
+    Migration { version: 7, name: "page_search", up: v7_page_search::up },
+    Migration { version: 8, name: "reading_progress", up: v8_reading_progress::up },
+    Migration { version: 9, name: "reading_sessions", up: v9_reading_sessions::up },
+    Migration { version: 10, name: "annotations", up: v10_annotations::up },
+    Migration { version: 11, name: "bookmarks", up: v11_bookmarks::up },
+    Migration { version: 12, name: "tasks", up: v12_tasks::up },
+    Migration { version: 13, name: "task_status", up: v13_task_status::up },
+    Migration { version: 14, name: "task_recurrence", up: v14_task_recurrence::up },
+    Migration { version: 15, name: "subtasks", up: v15_subtasks::up },
+    Migration { version: 16, name: "reading_plans", up: v16_reading_plans::up },
+    Migration { version: 17, name: "flashcards", up: v17_flashcards::up },
+    Migration { version: 18, name: "cloze_cards", up: v18_cloze_cards::up },
+    Migration { version: 19, name: "anki_notes", up: v19_anki_notes::up },
+    Migration { version: 20, name: "quizzes", up: v20_quizzes::up },
+    Migration { version: 21, name: "pomodoros", up: v21_pomodoros::up },
+    Migration { version: 22, name: "pdf_annotation_refs", up: v22_pdf_annotation_refs::up },
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: &'static str,
+}
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+
+    #[error("Database schema version {found} is newer than this build supports ({supported})")]
+    DatabaseTooNew { found: i64, supported: i64 },
+
+    #[error("Unknown migration version: {0}")]
+    UnknownVersion(i64),
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn ensure_migrations_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        "#
+    )
+}
+
+pub fn current_version(conn: &Connection) -> Result<i64, MigrationError> {
+    ensure_migrations_table(conn)?;
+    let version = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+/// Brings the database up to the latest schema this build knows about.
+pub fn run_migrations(conn: &mut Connection) -> Result<Vec<AppliedMigration>, MigrationError> {
+    migrate_to(conn, latest_version())
+}
+
+/// Applies every pending migration up to and including `target`, each one in
+/// its own transaction. Returns the migrations that were actually applied.
+pub fn migrate_to(conn: &mut Connection, target: i64) -> Result<Vec<AppliedMigration>, MigrationError> {
+    let supported = latest_version();
+    if target > supported || (target > 0 && !MIGRATIONS.iter().any(|m| m.version == target)) {
+        return Err(MigrationError::UnknownVersion(target));
+    }
+
+    let current = current_version(conn)?;
+    if current > supported {
+        return Err(MigrationError::DatabaseTooNew { found: current, supported });
+    }
+
+    let mut applied = Vec::new();
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current && m.version <= target) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?, ?)",
+            params![migration.version, migration.name],
+        )?;
+        tx.commit()?;
+
+        applied.push(AppliedMigration {
+            version: migration.version,
+            name: migration.name,
+        });
+    }
+
+    Ok(applied)
+}
@@ -0,0 +1,37 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS annotations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            book_id INTEGER NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'highlight' CHECK(kind IN ('highlight', 'underline', 'note')),
+            page_number INTEGER,
+            chapter_index INTEGER,
+            location TEXT,
+            rects TEXT,
+            start_offset INTEGER,
+            end_offset INTEGER,
+            selected_text TEXT,
+            color TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_annotations_book_page
+            ON annotations(book_id, page_number);
+
+        CREATE TABLE IF NOT EXISTS annotation_tags (
+            annotation_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (annotation_id, tag_id),
+            FOREIGN KEY (annotation_id) REFERENCES annotations(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS bookmarks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            book_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            page_number INTEGER,
+            chapter_index INTEGER,
+            location TEXT,
+            note TEXT,
+            color TEXT,
+            created_at TEXT NOT NULL,
+            last_opened_at TEXT,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_bookmarks_book ON bookmarks(book_id);
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    // SQLite can't add a CHECK constraint to an existing column, so the table
+    // is rebuilt. Statuses outside the known set fall back to 'todo'. Dropping
+    // the old table deletes its rows first, which would take task_tags with it
+    // through the foreign key, so the links are kept aside and restored.
+    tx.execute_batch(
+        r#"
+        CREATE TEMP TABLE task_tags_backup AS SELECT task_id, tag_id FROM task_tags;
+
+        CREATE TABLE tasks_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            description TEXT,
+            status TEXT NOT NULL DEFAULT 'todo'
+                CHECK (status IN ('todo', 'in_progress', 'blocked', 'done', 'archived')),
+            created_at TEXT NOT NULL,
+            due_date TEXT,
+            completed_at TEXT,
+            document_id INTEGER,
+            FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE SET NULL
+        );
+
+        INSERT INTO tasks_new (id, title, description, status, created_at, due_date, completed_at, document_id)
+        SELECT id, title, description,
+               CASE WHEN status IN ('todo', 'in_progress', 'blocked', 'done', 'archived')
+                    THEN status ELSE 'todo' END,
+               created_at, due_date, completed_at, document_id
+        FROM tasks;
+
+        DROP TABLE tasks;
+        ALTER TABLE tasks_new RENAME TO tasks;
+
+        INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+        SELECT task_id, tag_id FROM task_tags_backup;
+        DROP TABLE task_tags_backup;
+
+        CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
+        CREATE INDEX IF NOT EXISTS idx_tasks_due_date ON tasks(due_date);
+        CREATE INDEX IF NOT EXISTS idx_tasks_document ON tasks(document_id);
+        "#
+    )?;
+    Ok(())
+}
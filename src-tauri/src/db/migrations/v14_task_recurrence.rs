@@ -0,0 +1,19 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_recurrences (
+            task_id INTEGER PRIMARY KEY,
+            frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly', 'monthly')),
+            interval INTEGER NOT NULL DEFAULT 1 CHECK (interval >= 1),
+            weekdays TEXT,
+            starts_on TEXT NOT NULL,
+            until TEXT,
+            count INTEGER CHECK (count IS NULL OR count >= 1),
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
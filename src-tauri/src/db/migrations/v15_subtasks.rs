@@ -0,0 +1,13 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id) ON DELETE CASCADE;
+        ALTER TABLE tasks ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+        CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id, position);
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS reading_plans (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            book_id INTEGER NOT NULL,
+            task_id INTEGER NOT NULL UNIQUE,
+            unit TEXT NOT NULL CHECK (unit IN ('pages', 'chapters')),
+            target_date TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reading_plans_book ON reading_plans(book_id);
+
+        CREATE TABLE IF NOT EXISTS reading_plan_items (
+            task_id INTEGER PRIMARY KEY,
+            plan_id INTEGER NOT NULL,
+            start_unit INTEGER NOT NULL,
+            end_unit INTEGER NOT NULL CHECK (end_unit >= start_unit),
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+            FOREIGN KEY (plan_id) REFERENCES reading_plans(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reading_plan_items_plan ON reading_plan_items(plan_id);
+        "#
+    )?;
+    Ok(())
+}
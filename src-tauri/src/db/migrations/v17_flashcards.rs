@@ -0,0 +1,52 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS decks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT,
+            scheduler TEXT NOT NULL DEFAULT 'sm2' CHECK (scheduler IN ('sm2', 'fsrs')),
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS cards (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            deck_id INTEGER NOT NULL,
+            front TEXT NOT NULL,
+            back TEXT NOT NULL,
+            book_id INTEGER,
+            page_number INTEGER,
+            due_date TEXT NOT NULL,
+            repetitions INTEGER NOT NULL DEFAULT 0,
+            lapses INTEGER NOT NULL DEFAULT 0,
+            interval_days INTEGER NOT NULL DEFAULT 0,
+            ease_factor REAL NOT NULL DEFAULT 2.5,
+            stability REAL NOT NULL DEFAULT 0,
+            difficulty REAL NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            last_reviewed_at TEXT,
+            FOREIGN KEY (deck_id) REFERENCES decks(id) ON DELETE CASCADE,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_cards_deck_due ON cards(deck_id, due_date);
+        CREATE INDEX IF NOT EXISTS idx_cards_book ON cards(book_id);
+
+        CREATE TABLE IF NOT EXISTS card_reviews (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id INTEGER NOT NULL,
+            grade TEXT NOT NULL CHECK (grade IN ('again', 'hard', 'good', 'easy')),
+            scheduler TEXT NOT NULL,
+            elapsed_days INTEGER NOT NULL,
+            interval_days INTEGER NOT NULL,
+            reviewed_at TEXT NOT NULL,
+            FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_card_reviews_card ON card_reviews(card_id, reviewed_at);
+        "#
+    )?;
+    Ok(())
+}
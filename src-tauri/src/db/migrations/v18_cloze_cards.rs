@@ -0,0 +1,21 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE cards ADD COLUMN annotation_id INTEGER REFERENCES annotations(id) ON DELETE SET NULL;
+        ALTER TABLE cards ADD COLUMN cloze_text TEXT;
+
+        CREATE INDEX IF NOT EXISTS idx_cards_annotation ON cards(annotation_id);
+
+        CREATE TABLE IF NOT EXISTS card_tags (
+            card_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (card_id, tag_id),
+            FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE cards ADD COLUMN anki_guid TEXT;
+        ALTER TABLE cards ADD COLUMN anki_ord INTEGER;
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_cards_anki_note
+            ON cards(anki_guid, anki_ord) WHERE anki_guid IS NOT NULL;
+        "#
+    )?;
+    Ok(())
+}
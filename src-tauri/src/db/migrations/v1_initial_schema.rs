@@ -1,7 +1,7 @@
-use rusqlite::{Connection, Result};
+use rusqlite::{Result, Transaction};
 
-pub fn run_migrations(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
         r#"
         CREATE TABLE IF NOT EXISTS users (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
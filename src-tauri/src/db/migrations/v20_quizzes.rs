@@ -0,0 +1,71 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS quizzes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            deck_id INTEGER,
+            tag_id INTEGER,
+            book_id INTEGER,
+            chapter_index INTEGER,
+            time_limit_seconds INTEGER CHECK (time_limit_seconds > 0),
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (deck_id) REFERENCES decks(id) ON DELETE SET NULL,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE SET NULL,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS quiz_questions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            quiz_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            card_id INTEGER,
+            kind TEXT NOT NULL CHECK (kind IN ('multiple_choice', 'true_false', 'typed')),
+            prompt TEXT NOT NULL,
+            statement TEXT,
+            choices TEXT,
+            answer TEXT NOT NULL,
+            UNIQUE (quiz_id, position),
+            FOREIGN KEY (quiz_id) REFERENCES quizzes(id) ON DELETE CASCADE,
+            FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_quiz_questions_card ON quiz_questions(card_id);
+
+        CREATE TABLE IF NOT EXISTS quiz_attempts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            quiz_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            expires_at TEXT,
+            finished_at TEXT,
+            correct_count INTEGER NOT NULL DEFAULT 0,
+            question_count INTEGER NOT NULL,
+            score REAL,
+            FOREIGN KEY (quiz_id) REFERENCES quizzes(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_quiz_attempts_user ON quiz_attempts(user_id, started_at);
+        CREATE INDEX IF NOT EXISTS idx_quiz_attempts_quiz ON quiz_attempts(quiz_id);
+
+        CREATE TABLE IF NOT EXISTS quiz_answers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            attempt_id INTEGER NOT NULL,
+            question_id INTEGER NOT NULL,
+            response TEXT NOT NULL,
+            is_correct INTEGER NOT NULL,
+            similarity REAL,
+            answered_at TEXT NOT NULL,
+            UNIQUE (attempt_id, question_id),
+            FOREIGN KEY (attempt_id) REFERENCES quiz_attempts(id) ON DELETE CASCADE,
+            FOREIGN KEY (question_id) REFERENCES quiz_questions(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_quiz_answers_question ON quiz_answers(question_id);
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS pomodoros (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            task_id INTEGER,
+            book_id INTEGER,
+            started_at TEXT NOT NULL,
+            ended_at TEXT NOT NULL,
+            focus_seconds INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE SET NULL,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_pomodoros_user ON pomodoros(user_id, ended_at);
+        CREATE INDEX IF NOT EXISTS idx_pomodoros_task ON pomodoros(task_id);
+        CREATE INDEX IF NOT EXISTS idx_pomodoros_book ON pomodoros(book_id);
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE annotations ADD COLUMN pdf_ref TEXT;
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS documents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            original_filename TEXT NOT NULL,
+            stored_filename TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            page_count INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT,
+            last_accessed TEXT,
+            thumbnail_path TEXT,
+            tags TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_documents_title ON documents(title);
+        "#
+    )?;
+    Ok(())
+}
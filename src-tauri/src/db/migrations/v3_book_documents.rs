@@ -0,0 +1,12 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE books ADD COLUMN document_id INTEGER REFERENCES documents(id) ON DELETE SET NULL;
+
+        CREATE INDEX IF NOT EXISTS idx_books_document_id ON books(document_id);
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    // Imports before this migration could register the same file twice;
+    // fold those rows onto the oldest document before enforcing uniqueness.
+    tx.execute_batch(
+        r#"
+        UPDATE books
+        SET document_id = COALESCE(
+            (SELECT MIN(d2.id)
+             FROM documents d1
+             JOIN documents d2 ON d2.hash = d1.hash
+             WHERE d1.id = books.document_id AND d1.hash <> ''),
+            document_id
+        )
+        WHERE document_id IS NOT NULL;
+
+        DELETE FROM documents
+        WHERE hash <> ''
+          AND id NOT IN (SELECT MIN(id) FROM documents WHERE hash <> '' GROUP BY hash);
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_documents_hash ON documents(hash) WHERE hash <> '';
+        "#
+    )?;
+    Ok(())
+}
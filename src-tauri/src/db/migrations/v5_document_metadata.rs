@@ -0,0 +1,11 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE documents ADD COLUMN subject TEXT;
+        ALTER TABLE documents ADD COLUMN keywords TEXT;
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS reading_progress (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            book_id INTEGER NOT NULL,
+            current_page INTEGER,
+            location TEXT,
+            chapter_index INTEGER,
+            chapter_offset REAL,
+            percent_complete REAL NOT NULL DEFAULT 0 CHECK(percent_complete BETWEEN 0 AND 100),
+            last_opened_at TEXT,
+            UNIQUE (user_id, book_id),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reading_progress_last_opened
+            ON reading_progress(user_id, last_opened_at);
+        "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use rusqlite::{Result, Transaction};
+
+pub fn up(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS reading_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            book_id INTEGER NOT NULL,
+            current_page INTEGER,
+            started_at TEXT NOT NULL,
+            last_heartbeat_at TEXT NOT NULL,
+            ended_at TEXT,
+            end_reason TEXT CHECK(end_reason IN ('stopped', 'stale')),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reading_sessions_user_started
+            ON reading_sessions(user_id, started_at);
+        CREATE INDEX IF NOT EXISTS idx_reading_sessions_open
+            ON reading_sessions(ended_at) WHERE ended_at IS NULL;
+
+        CREATE TABLE IF NOT EXISTS reading_session_pages (
+            session_id INTEGER NOT NULL,
+            page_number INTEGER NOT NULL,
+            dwell_seconds INTEGER NOT NULL DEFAULT 0,
+            visits INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (session_id, page_number),
+            FOREIGN KEY (session_id) REFERENCES reading_sessions(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
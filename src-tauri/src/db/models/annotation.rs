@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::db::models::Tag;
+
+pub const DEFAULT_ANNOTATION_COLOR: &str = "#ffeb3b";
+
+/// A highlight, underline or note anchored to a page (PDF) or a chapter
+/// and location (EPUB).
+///
+/// `rects` are in PDF user space: points, origin at the bottom-left of the
+/// page. `start_offset`/`end_offset` are character offsets into the text
+/// extracted for that page or chapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: Option<i64>,
+    pub book_id: i64,
+    pub kind: AnnotationKind,
+    pub page_number: Option<i32>,
+    pub chapter_index: Option<i32>,
+    pub location: Option<String>,
+    pub rects: Option<Vec<AnnotationRect>>,
+    pub start_offset: Option<i32>,
+    pub end_offset: Option<i32>,
+    pub selected_text: Option<String>,
+    pub color: String,
+    pub note: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Position (`page/index` into the page's `/Annots`) of an annotation
+    /// imported from the book's own PDF. The stored file never changes, so
+    /// this keeps pointing at it, and export leaves it to the copy it has.
+    pub pdf_ref: Option<String>,
+    pub tags: Option<Vec<Tag>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AnnotationRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AnnotationKind {
+    Highlight,
+    Underline,
+    Note,
+}
+
+impl AnnotationKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "highlight" => Some(Self::Highlight),
+            "underline" => Some(Self::Underline),
+            "note" => Some(Self::Note),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Highlight => "highlight",
+            Self::Underline => "underline",
+            Self::Note => "note",
+        }
+    }
+}
@@ -7,5 +7,6 @@ pub struct Book {
     pub title: String,
     pub author: Option<String>,
     pub file_path: Option<String>,
+    pub document_id: Option<i64>,
     pub tags: Option<Vec<Tag>>
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A named position in a book: `page_number` for PDFs, `location` (a CFI)
+/// or `chapter_index` for EPUBs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: Option<i64>,
+    pub book_id: i64,
+    pub book_title: Option<String>,
+    pub name: String,
+    pub page_number: Option<i32>,
+    pub chapter_index: Option<i32>,
+    pub location: Option<String>,
+    pub note: Option<String>,
+    pub color: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
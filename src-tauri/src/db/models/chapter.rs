@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+    pub id: Option<i64>,
+    pub document_id: i64,
+    pub chapter_index: i32,
+    pub title: Option<String>,
+    pub href: String,
+}
@@ -16,4 +16,8 @@ pub struct Document {
     pub last_accessed: Option<DateTime<Utc>>,
     pub thumbnail_path: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub subject: Option<String>,
+    pub keywords: Option<Vec<String>>,
+    pub language: Option<String>,
+    pub identifiers: Option<Vec<String>>,
 }
\ No newline at end of file
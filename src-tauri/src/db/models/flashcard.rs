@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::db::models::Tag;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deck {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub scheduler: SchedulerKind,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Card {
+    pub id: Option<i64>,
+    pub deck_id: i64,
+    pub front: String,
+    pub back: String,
+    /// Book and page the card was written from, if any.
+    pub book_id: Option<i64>,
+    pub page_number: Option<i32>,
+    /// `YYYY-MM-DD`; new cards are due the day they are created.
+    pub due_date: String,
+    pub state: CardState,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_reviewed_at: Option<DateTime<Utc>>,
+    /// Highlight the card was made from, so review can jump back to it.
+    pub annotation_id: Option<i64>,
+    /// Source of a cloze card in `{{c1::answer}}` markup; `front` and `back`
+    /// are rendered from it.
+    pub cloze_text: Option<String>,
+    /// Anki note and template the card came from or was exported as, so
+    /// packages can be imported again without duplicating cards.
+    pub anki_guid: Option<String>,
+    pub anki_ord: Option<u32>,
+    pub tags: Option<Vec<Tag>>,
+}
+
+/// Characters `start..end` of a highlight's text to blank out on a cloze card.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ClozeSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Scheduling memory of a card. SM-2 uses `repetitions`, `ease_factor` and
+/// `interval_days`; FSRS uses `stability` and `difficulty`. Both are kept so
+/// a deck can switch schedulers; FSRS seeds its own fields from the SM-2
+/// interval the first time it reviews a card SM-2 has scheduled.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CardState {
+    pub repetitions: u32,
+    pub lapses: u32,
+    pub interval_days: u32,
+    pub ease_factor: f64,
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+impl Default for CardState {
+    fn default() -> Self {
+        Self {
+            repetitions: 0,
+            lapses: 0,
+            interval_days: 0,
+            ease_factor: 2.5,
+            stability: 0.0,
+            difficulty: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SchedulerKind {
+    Sm2,
+    Fsrs,
+}
+
+impl SchedulerKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "sm2" => Some(Self::Sm2),
+            "fsrs" => Some(Self::Fsrs),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Sm2 => "sm2",
+            Self::Fsrs => "fsrs",
+        }
+    }
+}
+
+/// How well a card was recalled, on the four-button scale shared by SM-2
+/// front ends and FSRS.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ReviewGrade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl ReviewGrade {
+    pub fn parse(grade: &str) -> Option<Self> {
+        match grade {
+            "again" => Some(Self::Again),
+            "hard" => Some(Self::Hard),
+            "good" => Some(Self::Good),
+            "easy" => Some(Self::Easy),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Again => "again",
+            Self::Hard => "hard",
+            Self::Good => "good",
+            Self::Easy => "easy",
+        }
+    }
+
+    /// 1 (again) to 4 (easy), as used by FSRS.
+    pub fn rating(&self) -> u32 {
+        match self {
+            Self::Again => 1,
+            Self::Hard => 2,
+            Self::Good => 3,
+            Self::Easy => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardReview {
+    pub id: Option<i64>,
+    pub card_id: i64,
+    pub grade: ReviewGrade,
+    pub scheduler: SchedulerKind,
+    /// Days since the previous review, 0 for the first one.
+    pub elapsed_days: u32,
+    pub interval_days: u32,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckStatistics {
+    pub deck_id: i64,
+    pub total_cards: u32,
+    pub new_cards: u32,
+    pub due_cards: u32,
+    /// Cards with an interval of 21 days or more.
+    pub mature_cards: u32,
+    pub reviews_today: u32,
+    /// Share of reviews in the last 30 days not graded `again`, if any.
+    pub retention: Option<f64>,
+}
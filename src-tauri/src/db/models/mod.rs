@@ -5,6 +5,16 @@ pub mod task;
 pub mod user_available_day;
 pub mod user_interesting;
 pub mod book;
+pub mod bookmark;
+pub mod chapter;
+pub mod search_hit;
+pub mod reading_progress;
+pub mod reading_session;
+pub mod annotation;
+pub mod reading_plan;
+pub mod flashcard;
+pub mod quiz;
+pub mod pomodoro;
 
 pub use user::*;
 pub use document::*;
@@ -12,4 +22,14 @@ pub use tag::*;
 pub use task::*;
 pub use user_available_day::*;
 pub use user_interesting::*;
-pub use book::*;
\ No newline at end of file
+pub use book::*;
+pub use bookmark::*;
+pub use chapter::*;
+pub use search_hit::*;
+pub use reading_progress::*;
+pub use reading_session::*;
+pub use annotation::*;
+pub use reading_plan::*;
+pub use flashcard::*;
+pub use quiz::*;
+pub use pomodoro::*;
\ No newline at end of file
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A work phase of the focus timer that ran to the end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pomodoro {
+    pub id: Option<i64>,
+    pub user_id: i32,
+    pub task_id: Option<i32>,
+    pub book_id: Option<i64>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Time spent working, not counting pauses.
+    pub focus_seconds: u32,
+}
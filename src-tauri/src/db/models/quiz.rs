@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::db::models::Tag;
+
+/// A fixed set of questions drawn from a deck, a tag or a book. Only one of
+/// `deck_id`, `tag_id` and `book_id` is set; `chapter_index` narrows a book
+/// to the cards made from highlights in that chapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quiz {
+    pub id: Option<i64>,
+    pub title: String,
+    pub deck_id: Option<i64>,
+    pub tag_id: Option<i64>,
+    pub book_id: Option<i64>,
+    pub chapter_index: Option<i32>,
+    pub time_limit_seconds: Option<u32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub questions: Option<Vec<QuizQuestion>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum QuestionKind {
+    MultipleChoice,
+    TrueFalse,
+    Typed,
+}
+
+impl QuestionKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "multiple_choice" => Some(Self::MultipleChoice),
+            "true_false" => Some(Self::TrueFalse),
+            "typed" => Some(Self::Typed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::MultipleChoice => "multiple_choice",
+            Self::TrueFalse => "true_false",
+            Self::Typed => "typed",
+        }
+    }
+}
+
+/// One question, asked from a card's front. Multiple choice questions list
+/// their options in `choices`; true/false questions ask whether `statement`
+/// answers the prompt, and `answer` is then `"true"` or `"false"`. The answer
+/// is not sent to the webview; finished attempts list it in `solutions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizQuestion {
+    pub id: Option<i64>,
+    pub quiz_id: i64,
+    pub position: u32,
+    pub card_id: Option<i64>,
+    pub kind: QuestionKind,
+    pub prompt: String,
+    pub statement: Option<String>,
+    pub choices: Option<Vec<String>>,
+    #[serde(skip_serializing)]
+    pub answer: String,
+}
+
+/// A user taking a quiz. `expires_at` is set for timed quizzes; answers after
+/// it are refused and the attempt is closed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAttempt {
+    pub id: Option<i64>,
+    pub quiz_id: i64,
+    pub user_id: i32,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub correct_count: u32,
+    pub question_count: u32,
+    /// Share of all questions answered correctly, set when the attempt ends.
+    pub score: Option<f64>,
+    pub answers: Option<Vec<QuizAnswer>>,
+    /// The expected answer to every question, once the attempt has ended.
+    pub solutions: Option<Vec<QuizSolution>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAnswer {
+    pub id: Option<i64>,
+    pub attempt_id: i64,
+    pub question_id: i64,
+    pub response: String,
+    pub is_correct: bool,
+    /// How close a typed answer was to the expected one, 0 to 1.
+    pub similarity: Option<f64>,
+    pub answered_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizSolution {
+    pub question_id: i64,
+    pub answer: String,
+}
+
+/// How a user has done on questions about one tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicScore {
+    pub tag: Tag,
+    pub answered: u32,
+    pub correct: u32,
+    pub accuracy: f64,
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::db::models::task::TaskStatus;
+
+/// A schedule for finishing a book by a target date. The plan owns a parent
+/// task whose subtasks are the dated reading sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingPlan {
+    pub id: Option<i64>,
+    pub user_id: i32,
+    pub book_id: i64,
+    pub task_id: i32,
+    pub unit: PlanUnit,
+    pub target_date: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// What a plan splits the book by. Pages are 1-based; chapters use the
+/// 0-based `chapter_index` of the chapters table.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PlanUnit {
+    Pages,
+    Chapters,
+}
+
+impl PlanUnit {
+    pub fn parse(unit: &str) -> Option<Self> {
+        match unit {
+            "pages" => Some(Self::Pages),
+            "chapters" => Some(Self::Chapters),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Pages => "pages",
+            Self::Chapters => "chapters",
+        }
+    }
+}
+
+/// One day of reading, covering `start_unit..=end_unit`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlannedSession {
+    pub date: String,
+    pub start_unit: i32,
+    pub end_unit: i32,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingPlanPreview {
+    pub book_id: i64,
+    pub unit: PlanUnit,
+    pub target_date: String,
+    pub sessions: Vec<PlannedSession>,
+}
+
+/// A reading session already scheduled as a task of a plan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingPlanItem {
+    pub task_id: i32,
+    pub start_unit: i32,
+    pub end_unit: i32,
+    pub status: TaskStatus,
+    pub due_date: Option<String>,
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where a user stopped in a book. PDFs use `current_page`; EPUBs use
+/// `location` (a CFI) or `chapter_index` plus `chapter_offset` (0.0 to 1.0).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingProgress {
+    pub id: Option<i64>,
+    pub user_id: i32,
+    pub book_id: i64,
+    pub current_page: Option<i32>,
+    pub location: Option<String>,
+    pub chapter_index: Option<i32>,
+    pub chapter_offset: Option<f64>,
+    pub percent_complete: f64,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingSession {
+    pub id: Option<i64>,
+    pub user_id: i32,
+    pub book_id: i64,
+    pub current_page: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub last_heartbeat_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub end_reason: Option<String>,
+    pub pages: Option<Vec<PageDwell>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageDwell {
+    pub page_number: i32,
+    pub dwell_seconds: i64,
+    pub visits: i64,
+}
+
+/// Minutes read, grouped by day, book or tag depending on the query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingTime {
+    pub key: String,
+    pub id: Option<i64>,
+    pub label: String,
+    pub minutes: f64,
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub book_id: i64,
+    pub document_id: i64,
+    pub title: String,
+    pub page_number: i32,
+    pub snippet: String,
+    pub rank: f64,
+}
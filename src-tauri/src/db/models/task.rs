@@ -1,15 +1,152 @@
 use serde::{Deserialize, Serialize};
-use crate::db::models::Tag;
+use crate::db::models::{user_available_day::DayOfWeek, Tag};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: i32,
     pub title: String,
     pub description: Option<String>,
-    pub status: String,
+    pub status: TaskStatus,
     pub created_at: String,
     pub due_date: Option<String>,
     pub completed_at: Option<String>,
     pub document_id: Option<i64>,
+    /// The task this one is a step of, if any.
+    pub parent_id: Option<i32>,
+    /// Order among the subtasks of the same parent.
+    pub position: i32,
     pub tags: Vec<Tag>,
+    /// Set on the open task of a recurring series; completing it moves the
+    /// rule to the next occurrence.
+    pub recurrence: Option<RecurrenceRule>,
+}
+
+/// Criteria for listing tasks; unset fields don't filter. Due dates are
+/// `YYYY-MM-DD` and both ends of the range are inclusive.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub status: Option<TaskStatus>,
+    pub due_from: Option<String>,
+    pub due_to: Option<String>,
+    pub document_id: Option<i64>,
+    pub tag_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+    Archived,
+}
+
+impl TaskStatus {
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "todo" => Some(Self::Todo),
+            "in_progress" => Some(Self::InProgress),
+            "blocked" => Some(Self::Blocked),
+            "done" => Some(Self::Done),
+            "archived" => Some(Self::Archived),
+            _ => None,
+        }
+    }
+
+    /// Whether a task may move from this status to `to`. Blocked tasks have
+    /// to be unblocked before they can be completed, and archived tasks only
+    /// come back as `todo`.
+    pub fn can_move_to(self, to: TaskStatus) -> bool {
+        use TaskStatus::*;
+
+        match (self, to) {
+            (from, to) if from == to => true,
+            (Todo, InProgress | Blocked | Done | Archived) => true,
+            (InProgress, Todo | Blocked | Done | Archived) => true,
+            (Blocked, Todo | InProgress | Archived) => true,
+            (Done, Todo | InProgress | Archived) => true,
+            (Archived, Todo) => true,
+            _ => false,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Todo => "todo",
+            Self::InProgress => "in_progress",
+            Self::Blocked => "blocked",
+            Self::Done => "done",
+            Self::Archived => "archived",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl RecurrenceFrequency {
+    pub fn parse(frequency: &str) -> Option<Self> {
+        match frequency {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+}
+
+/// When a task repeats, in the spirit of an iCalendar RRULE. Dates are
+/// `YYYY-MM-DD`; `starts_on` is the first occurrence and anchors the series.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    /// Repeat every `interval` days, weeks or months.
+    pub interval: u32,
+    /// Days a weekly rule falls on; empty means the weekday of `starts_on`.
+    pub weekdays: Vec<DayOfWeek>,
+    pub starts_on: String,
+    /// Last date an occurrence may fall on, inclusive.
+    pub until: Option<String>,
+    /// Total number of occurrences in the series, counting the first one.
+    pub count: Option<u32>,
+}
+
+/// One entry of the upcoming list: either a stored task or a future
+/// occurrence of a recurring one that hasn't been created yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOccurrence {
+    pub task_id: i32,
+    pub title: String,
+    pub due_date: String,
+    /// 1-based position in the series, for recurring tasks.
+    pub occurrence: Option<u32>,
+    pub materialized: bool,
+}
+
+/// How many of a task's subtasks are done. Archived subtasks don't count.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TaskProgress {
+    pub done: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskNode {
+    pub task: Task,
+    /// `None` for tasks without subtasks.
+    pub progress: Option<TaskProgress>,
+    pub children: Vec<TaskNode>,
 }
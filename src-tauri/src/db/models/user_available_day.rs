@@ -1,3 +1,4 @@
+use chrono::Weekday;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -7,7 +8,7 @@ pub struct UserAvailableDay {
     pub day_of_week: DayOfWeek,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum DayOfWeek {
     Monday,
     Tuesday,
@@ -43,4 +44,16 @@ impl DayOfWeek {
             Self::Sunday => "sunday",
         }
     }
+
+    pub fn weekday(&self) -> Weekday {
+        match self {
+            Self::Monday => Weekday::Mon,
+            Self::Tuesday => Weekday::Tue,
+            Self::Wednesday => Weekday::Wed,
+            Self::Thursday => Weekday::Thu,
+            Self::Friday => Weekday::Fri,
+            Self::Saturday => Weekday::Sat,
+            Self::Sunday => Weekday::Sun,
+        }
+    }
 }
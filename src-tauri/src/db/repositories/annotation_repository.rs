@@ -0,0 +1,226 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::annotation::{Annotation, AnnotationKind};
+use crate::db::models::tag::Tag;
+
+const ANNOTATION_COLUMNS: &str = "
+    id, book_id, kind, page_number, chapter_index, location, rects,
+    start_offset, end_offset, selected_text, color, note, created_at, updated_at, pdf_ref";
+
+pub struct AnnotationRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> AnnotationRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<Annotation> {
+        let kind: String = row.get(2)?;
+        Ok(Annotation {
+            id: row.get(0)?,
+            book_id: row.get(1)?,
+            kind: AnnotationKind::parse(&kind).unwrap_or(AnnotationKind::Highlight),
+            page_number: row.get(3)?,
+            chapter_index: row.get(4)?,
+            location: row.get(5)?,
+            rects: row.get::<_, Option<String>>(6)?
+                .map(|rects_str| serde_json::from_str(&rects_str).unwrap_or_default()),
+            start_offset: row.get(7)?,
+            end_offset: row.get(8)?,
+            selected_text: row.get(9)?,
+            color: row.get(10)?,
+            note: row.get(11)?,
+            created_at: row.get::<_, Option<String>>(12)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+            updated_at: row.get::<_, Option<String>>(13)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+            pdf_ref: row.get(14)?,
+            tags: None,
+        })
+    }
+
+    pub fn create_annotation(&mut self, annotation: &Annotation) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        let annotation_id = Self::insert_annotation(&tx, annotation)?;
+        tx.commit()?;
+        Ok(annotation_id)
+    }
+
+    /// Inserts an annotation and links its tags, stamping unset timestamps
+    /// with the current time. Book imports add a PDF's existing annotations
+    /// through it in the same transaction as the book.
+    pub fn insert_annotation(conn: &Connection, annotation: &Annotation) -> Result<i64> {
+        let now = Utc::now();
+        conn.execute(
+            "INSERT INTO annotations (
+                book_id, kind, page_number, chapter_index, location, rects,
+                start_offset, end_offset, selected_text, color, note, created_at, updated_at, pdf_ref
+             )
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                annotation.book_id,
+                annotation.kind.as_str(),
+                annotation.page_number,
+                annotation.chapter_index,
+                annotation.location,
+                annotation.rects.as_ref().map(|r| serde_json::to_string(r).unwrap_or_default()),
+                annotation.start_offset,
+                annotation.end_offset,
+                annotation.selected_text,
+                annotation.color,
+                annotation.note,
+                annotation.created_at.unwrap_or(now).to_rfc3339(),
+                annotation.updated_at.unwrap_or(now).to_rfc3339(),
+                annotation.pdf_ref,
+            ],
+        )?;
+        let annotation_id = conn.last_insert_rowid();
+
+        if let Some(tags) = &annotation.tags {
+            for tag in tags {
+                if let Some(tag_id) = tag.id {
+                    conn.execute(
+                        "INSERT OR IGNORE INTO annotation_tags (annotation_id, tag_id) VALUES (?, ?)",
+                        params![annotation_id, tag_id],
+                    )?;
+                }
+            }
+        }
+
+        Ok(annotation_id)
+    }
+
+    pub fn update_annotation(&mut self, annotation: &Annotation) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE annotations
+             SET kind = ?, page_number = ?, chapter_index = ?, location = ?, rects = ?,
+                 start_offset = ?, end_offset = ?, selected_text = ?, color = ?, note = ?,
+                 updated_at = ?
+             WHERE id = ?",
+            params![
+                annotation.kind.as_str(),
+                annotation.page_number,
+                annotation.chapter_index,
+                annotation.location,
+                annotation.rects.as_ref().map(|r| serde_json::to_string(r).unwrap_or_default()),
+                annotation.start_offset,
+                annotation.end_offset,
+                annotation.selected_text,
+                annotation.color,
+                annotation.note,
+                Utc::now().to_rfc3339(),
+                annotation.id,
+            ],
+        )
+    }
+
+    pub fn delete_annotation(&mut self, id: i64) -> Result<usize> {
+        self.conn.execute("DELETE FROM annotations WHERE id = ?", params![id])
+    }
+
+    pub fn get_annotation_by_id(&self, id: i64) -> Result<Option<Annotation>> {
+        let annotation = self.conn.query_row(
+            &format!("SELECT {} FROM annotations WHERE id = ?", ANNOTATION_COLUMNS),
+            params![id],
+            Self::map_row,
+        ).optional()?;
+
+        match annotation {
+            Some(mut annotation) => {
+                annotation.tags = Some(self.get_tags_by_annotation_id(id)?);
+                Ok(Some(annotation))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Lists a book's annotations in reading order, optionally limited to one page.
+    pub fn get_annotations_by_book_id(&self, book_id: i64, page_number: Option<i32>) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM annotations
+             WHERE book_id = ?1 AND (?2 IS NULL OR page_number = ?2)
+             ORDER BY page_number, chapter_index, start_offset, id",
+            ANNOTATION_COLUMNS
+        ))?;
+
+        let annotations = stmt
+            .query_map(params![book_id, page_number], Self::map_row)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(annotations)
+    }
+
+    /// Annotations carrying the tag, plus every annotation of books carrying it.
+    pub fn get_annotations_by_tag_id(&self, tag_id: i32) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM annotations
+             WHERE id IN (SELECT annotation_id FROM annotation_tags WHERE tag_id = ?1)
+                OR book_id IN (SELECT book_id FROM book_tags WHERE tag_id = ?1)
+             ORDER BY book_id, page_number, chapter_index, start_offset, id",
+            ANNOTATION_COLUMNS
+        ))?;
+
+        let annotations = stmt
+            .query_map(params![tag_id], Self::map_row)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(annotations)
+    }
+
+    fn with_tags(&self, mut annotations: Vec<Annotation>) -> Result<Vec<Annotation>> {
+        for annotation in &mut annotations {
+            if let Some(id) = annotation.id {
+                annotation.tags = Some(self.get_tags_by_annotation_id(id)?);
+            }
+        }
+        Ok(annotations)
+    }
+
+    pub fn get_tags_by_annotation_id(&self, annotation_id: i64) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.title, t.color, t.icon
+             FROM tags t
+             JOIN annotation_tags at ON at.tag_id = t.id
+             WHERE at.annotation_id = ?"
+        )?;
+
+        let tags = stmt.query_map(params![annotation_id], |row| {
+            Ok(Tag {
+                id: Some(row.get(0)?),
+                title: row.get(1)?,
+                color: row.get(2)?,
+                icon: row.get(3)?,
+            })
+        })?;
+
+        tags.collect()
+    }
+
+    pub fn add_tags_to_annotation(&mut self, annotation_id: i64, tags: Vec<Tag>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for tag in tags {
+            if let Some(tag_id) = tag.id {
+                tx.execute(
+                    "INSERT OR IGNORE INTO annotation_tags (annotation_id, tag_id) VALUES (?, ?)",
+                    params![annotation_id, tag_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_tags_from_annotation(&mut self, annotation_id: i64, tags: Vec<Tag>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for tag in tags {
+            if let Some(tag_id) = tag.id {
+                tx.execute(
+                    "DELETE FROM annotation_tags WHERE annotation_id = ? AND tag_id = ?",
+                    params![annotation_id, tag_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use crate::db::models::book::Book;
 use crate::db::models::tag::Tag;
 
@@ -13,37 +13,45 @@ impl<'a> BookRepository<'a> {
 
     pub fn create_book(&mut self, book: &Book) -> Result<i64> {
         let tx = self.conn.transaction()?;
+        let book_id = Self::insert_book(&tx, book)?;
+        tx.commit()?;
+        Ok(book_id)
+    }
+
+    /// Inserts the book and links its tags by ID. Imports call it within the
+    /// transaction that writes the book's document.
+    pub fn insert_book(conn: &Connection, book: &Book) -> Result<i64> {
         {
-            let mut stmt = tx.prepare(
-                "INSERT INTO books (title, author, file_path) 
-                 VALUES(?, ?, ?)"
+            let mut stmt = conn.prepare(
+                "INSERT INTO books (title, author, file_path, document_id) 
+                 VALUES(?, ?, ?, ?)"
             )?;
             stmt.execute(params![
                 book.title,
                 book.author,
-                book.file_path
+                book.file_path,
+                book.document_id
             ])?;
         }
-        let book_id = tx.last_insert_rowid();
+        let book_id = conn.last_insert_rowid();
         
         if let Some(tags) = &book.tags {
             for tag in tags {
-                let mut stmt = tx.prepare(
+                let mut stmt = conn.prepare(
                     "INSERT INTO book_tags (book_id, tag_id) 
                      VALUES(?, ?)"
                 )?;
                 stmt.execute(params![book_id, tag.id])?;
             }
         }
-        
-        tx.commit()?;
+
         Ok(book_id)
     }
 
     pub fn update_book(&mut self, book: &Book) -> Result<usize> {
         self.conn.execute(
-            "UPDATE books SET title = ?, author = ?, file_path = ? WHERE id = ?",
-            params![book.title, book.author, book.file_path, book.id],
+            "UPDATE books SET title = ?, author = ?, file_path = ?, document_id = ? WHERE id = ?",
+            params![book.title, book.author, book.file_path, book.document_id, book.id],
         )
     }
 
@@ -59,7 +67,7 @@ impl<'a> BookRepository<'a> {
 
     pub fn get_book_by_id(&self, id: i64) -> Result<Option<Book>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, title, author, file_path FROM books WHERE id = ?"
+            "SELECT id, title, author, file_path, document_id FROM books WHERE id = ?"
         )?;
         
         let mut rows = stmt.query(params![id])?;
@@ -70,6 +78,7 @@ impl<'a> BookRepository<'a> {
                 title: row.get(1)?,
                 author: row.get(2)?,
                 file_path: row.get(3)?,
+                document_id: row.get(4)?,
                 tags: Some(self.get_tags_by_book_id(row.get(0)?)?),
             };
             Ok(Some(book))
@@ -78,9 +87,45 @@ impl<'a> BookRepository<'a> {
         }
     }
 
+    pub fn get_book_by_document_id(&self, document_id: i64) -> Result<Option<Book>> {
+        let book_id: Option<i64> = self.conn.query_row(
+            "SELECT id FROM books WHERE document_id = ? ORDER BY id LIMIT 1",
+            params![document_id],
+            |row| row.get(0),
+        ).optional()?;
+
+        match book_id {
+            Some(id) => self.get_book_by_id(id),
+            None => Ok(None),
+        }
+    }
+
+    /// Fills in a new title/author and adds any tags the book does not have yet.
+    pub fn merge_into_book(
+        conn: &Connection,
+        book_id: i64,
+        title: Option<&str>,
+        author: Option<&str>,
+        tags: &[Tag],
+    ) -> Result<()> {
+        conn.execute(
+            "UPDATE books SET title = COALESCE(?, title), author = COALESCE(?, author) WHERE id = ?",
+            params![title, author, book_id],
+        )?;
+        for tag in tags {
+            if let Some(tag_id) = tag.id {
+                conn.execute(
+                    "INSERT OR IGNORE INTO book_tags (book_id, tag_id) VALUES(?, ?)",
+                    params![book_id, tag_id],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_all_books(&self) -> Result<Vec<Book>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, title, author, file_path FROM books"
+            "SELECT id, title, author, file_path, document_id FROM books"
         )?;
         
         let book_iter = stmt.query_map([], |row| {
@@ -89,6 +134,7 @@ impl<'a> BookRepository<'a> {
                 title: row.get(1)?,
                 author: row.get(2)?,
                 file_path: row.get(3)?,
+                document_id: row.get(4)?,
                 tags: Some(self.get_tags_by_book_id(row.get(0)?)?),
             })
         })?;
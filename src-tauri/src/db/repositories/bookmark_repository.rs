@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::bookmark::Bookmark;
+
+const BOOKMARK_COLUMNS: &str = "
+    bm.id, bm.book_id, b.title, bm.name, bm.page_number, bm.chapter_index,
+    bm.location, bm.note, bm.color, bm.created_at, bm.last_opened_at";
+
+pub struct BookmarkRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> BookmarkRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<Bookmark> {
+        Ok(Bookmark {
+            id: row.get(0)?,
+            book_id: row.get(1)?,
+            book_title: row.get(2)?,
+            name: row.get(3)?,
+            page_number: row.get(4)?,
+            chapter_index: row.get(5)?,
+            location: row.get(6)?,
+            note: row.get(7)?,
+            color: row.get(8)?,
+            created_at: row.get::<_, Option<String>>(9)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+            last_opened_at: row.get::<_, Option<String>>(10)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+        })
+    }
+
+    pub fn create_bookmark(&mut self, bookmark: &Bookmark) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO bookmarks (
+                book_id, name, page_number, chapter_index, location, note, color, created_at
+             )
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                bookmark.book_id,
+                bookmark.name,
+                bookmark.page_number,
+                bookmark.chapter_index,
+                bookmark.location,
+                bookmark.note,
+                bookmark.color,
+                bookmark.created_at.unwrap_or_else(Utc::now).to_rfc3339(),
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_bookmark(&mut self, bookmark: &Bookmark) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE bookmarks
+             SET name = ?, page_number = ?, chapter_index = ?, location = ?, note = ?, color = ?
+             WHERE id = ?",
+            params![
+                bookmark.name,
+                bookmark.page_number,
+                bookmark.chapter_index,
+                bookmark.location,
+                bookmark.note,
+                bookmark.color,
+                bookmark.id,
+            ],
+        )
+    }
+
+    pub fn mark_opened(&mut self, id: i64) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE bookmarks SET last_opened_at = ? WHERE id = ?",
+            params![Utc::now().to_rfc3339(), id],
+        )
+    }
+
+    pub fn delete_bookmark(&mut self, id: i64) -> Result<usize> {
+        self.conn.execute("DELETE FROM bookmarks WHERE id = ?", params![id])
+    }
+
+    pub fn get_bookmark_by_id(&self, id: i64) -> Result<Option<Bookmark>> {
+        self.conn.query_row(
+            &format!(
+                "SELECT {} FROM bookmarks bm LEFT JOIN books b ON b.id = bm.book_id WHERE bm.id = ?",
+                BOOKMARK_COLUMNS
+            ),
+            params![id],
+            Self::map_row,
+        ).optional()
+    }
+
+    pub fn get_bookmarks_by_book_id(&self, book_id: i64) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM bookmarks bm
+             LEFT JOIN books b ON b.id = bm.book_id
+             WHERE bm.book_id = ?
+             ORDER BY bm.page_number, bm.chapter_index, bm.created_at",
+            BOOKMARK_COLUMNS
+        ))?;
+
+        let bookmarks = stmt.query_map(params![book_id], Self::map_row)?;
+        bookmarks.collect()
+    }
+
+    /// Every bookmark in the library, most recently opened or created first.
+    pub fn get_recent_bookmarks(&self, limit: i64) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM bookmarks bm
+             LEFT JOIN books b ON b.id = bm.book_id
+             ORDER BY COALESCE(bm.last_opened_at, bm.created_at) DESC, bm.id DESC
+             LIMIT ?",
+            BOOKMARK_COLUMNS
+        ))?;
+
+        let bookmarks = stmt.query_map(params![limit], Self::map_row)?;
+        bookmarks.collect()
+    }
+}
@@ -0,0 +1,56 @@
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::chapter::Chapter;
+
+pub struct ChapterRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> ChapterRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<Chapter> {
+        Ok(Chapter {
+            id: row.get(0)?,
+            document_id: row.get(1)?,
+            chapter_index: row.get(2)?,
+            title: row.get(3)?,
+            href: row.get(4)?,
+        })
+    }
+
+    pub fn insert_chapters(&self, document_id: i64, chapters: &[Chapter]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO chapters (document_id, chapter_index, title, href)
+             VALUES (?, ?, ?, ?)"
+        )?;
+
+        for chapter in chapters {
+            stmt.execute(params![document_id, chapter.chapter_index, chapter.title, chapter.href])?;
+        }
+        Ok(())
+    }
+
+    pub fn get_chapters_by_document_id(&self, document_id: i64) -> Result<Vec<Chapter>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, document_id, chapter_index, title, href
+             FROM chapters
+             WHERE document_id = ?
+             ORDER BY chapter_index"
+        )?;
+
+        let chapters = stmt.query_map(params![document_id], Self::map_row)?;
+        chapters.collect()
+    }
+
+    pub fn get_chapter(&self, document_id: i64, chapter_index: i32) -> Result<Option<Chapter>> {
+        self.conn.query_row(
+            "SELECT id, document_id, chapter_index, title, href
+             FROM chapters
+             WHERE document_id = ? AND chapter_index = ?",
+            params![document_id, chapter_index],
+            Self::map_row,
+        ).optional()
+    }
+}
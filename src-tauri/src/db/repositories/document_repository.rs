@@ -1,9 +1,15 @@
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Connection, Result, Row, params};
 use rusqlite::OptionalExtension;
 use crate::db::models::document::Document;
 use serde_json;
 use chrono::{DateTime, Utc};
 
+const DOCUMENT_COLUMNS: &str = "
+    id, title, original_filename, stored_filename, file_path,
+    file_size, mime_type, hash, page_count, created_at,
+    last_accessed, thumbnail_path, tags, subject, keywords,
+    language, identifiers";
+
 pub struct DocumentRepository<'a> {
     conn: &'a Connection,
 }
@@ -13,27 +19,58 @@ impl<'a> DocumentRepository<'a> {
         Self { conn }
     }
 
-    pub fn create(&self, document: &Document) -> Result<(), String> {
+    fn map_row(row: &Row) -> Result<Document> {
+        Ok(Document {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            original_filename: row.get(2)?,
+            stored_filename: row.get(3)?,
+            file_path: row.get(4)?,
+            file_size: row.get(5)?,
+            mime_type: row.get(6)?,
+            hash: row.get(7)?,
+            page_count: row.get(8)?,
+            created_at: row.get::<_, Option<String>>(9)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+            last_accessed: row.get::<_, Option<String>>(10)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+            thumbnail_path: row.get(11)?,
+            tags: row.get::<_, Option<String>>(12)?
+                .map(|tags_str| serde_json::from_str(&tags_str).unwrap_or_default()),
+            subject: row.get(13)?,
+            keywords: row.get::<_, Option<String>>(14)?
+                .map(|keywords_str| serde_json::from_str(&keywords_str).unwrap_or_default()),
+            language: row.get(15)?,
+            identifiers: row.get::<_, Option<String>>(16)?
+                .map(|identifiers_str| serde_json::from_str(&identifiers_str).unwrap_or_default()),
+        })
+    }
+
+    pub fn create(&self, document: &Document) -> Result<i64, String> {
         let query = "
             INSERT INTO documents (
-                title, 
-                original_filename, 
-                stored_filename, 
-                file_path, 
-                file_size, 
-                mime_type, 
-                hash, 
-                page_count, 
-                created_at, 
-                last_accessed, 
-                thumbnail_path, 
-                tags
-            ) 
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
-    
+                title,
+                original_filename,
+                stored_filename,
+                file_path,
+                file_size,
+                mime_type,
+                hash,
+                page_count,
+                created_at,
+                last_accessed,
+                thumbnail_path,
+                tags,
+                subject,
+                keywords,
+                language,
+                identifiers
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)";
+
         self.conn
             .execute(
-                query, 
+                query,
                 params![
                     document.title,
                     document.original_filename,
@@ -47,72 +84,80 @@ impl<'a> DocumentRepository<'a> {
                     document.last_accessed.map(|dt| dt.to_rfc3339()),
                     document.thumbnail_path,
                     document.tags.as_ref().map(|t| serde_json::to_string(t).unwrap_or_default()),
+                    document.subject,
+                    document.keywords.as_ref().map(|k| serde_json::to_string(k).unwrap_or_default()),
+                    document.language,
+                    document.identifiers.as_ref().map(|i| serde_json::to_string(i).unwrap_or_default()),
                 ]
             )
             .map_err(|e| e.to_string())?;
-        Ok(())
+        Ok(self.conn.last_insert_rowid())
     }
 
     pub fn get_by_id(&self, id: i32) -> Result<Option<Document>, String> {
-        let query = "
-            SELECT 
-                id, title, original_filename, stored_filename, file_path, 
-                file_size, mime_type, hash, page_count, created_at, 
-                last_accessed, thumbnail_path, tags
-            FROM documents 
-            WHERE id = ?1";
-
-        let result = self.conn.query_row(
-            query, 
-            params![id], 
-            |row| {
-                Ok(Document {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    original_filename: row.get(2)?,
-                    stored_filename: row.get(3)?,
-                    file_path: row.get(4)?,
-                    file_size: row.get(5)?,
-                    mime_type: row.get(6)?,
-                    hash: row.get(7)?,
-                    page_count: row.get(8)?,
-                    created_at: row.get::<_, Option<String>>(9)?
-                        .map(|dt_str| dt_str.parse::<DateTime<Utc>>().unwrap()),
-                    last_accessed: row.get::<_, Option<String>>(9)?
-                    .map(|dt_str| dt_str.parse::<DateTime<Utc>>().unwrap()),
-                    thumbnail_path: row.get(11)?,
-                    tags: row.get::<_, Option<String>>(12)?
-                        .map(|tags_str| serde_json::from_str(&tags_str).unwrap_or_default()),
-                })
-            }
-        )
-        .optional()
-        .map_err(|e| e.to_string())?;
+        let query = format!("SELECT {} FROM documents WHERE id = ?1", DOCUMENT_COLUMNS);
+
+        let result = self.conn
+            .query_row(&query, params![id], Self::map_row)
+            .optional()
+            .map_err(|e| e.to_string())?;
 
         Ok(result)
     }
 
+    pub fn find_by_hash(&self, hash: &str) -> Result<Option<Document>, String> {
+        let query = format!("SELECT {} FROM documents WHERE hash = ?1", DOCUMENT_COLUMNS);
+
+        self.conn
+            .query_row(&query, params![hash], Self::map_row)
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn search_by_title(&self, title: &str) -> Result<Vec<Document>, String> {
+        let query = format!(
+            "SELECT {} FROM documents WHERE title LIKE ?1 ESCAPE '\\' ORDER BY title",
+            DOCUMENT_COLUMNS
+        );
+        let pattern = format!(
+            "%{}%",
+            title.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+
+        let mut stmt = self.conn.prepare(&query).map_err(|e| e.to_string())?;
+
+        let document_iter = stmt
+            .query_map(params![pattern], Self::map_row)
+            .map_err(|e| e.to_string())?;
+
+        document_iter.collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())
+    }
+
     pub fn update(&self, document: &Document) -> Result<(), String> {
-        let query = "pub use user_available_day_repository::*;
-
-            UPDATE documents 
-            SET title = ?1, 
-                original_filename = ?2, 
-                stored_filename = ?3, 
-                file_path = ?4, 
-                file_size = ?5, 
-                mime_type = ?6, 
-                hash = ?7, 
-                page_count = ?8, 
-                created_at = ?9, 
-                last_accessed = ?10, 
-                thumbnail_path = ?11, 
-                tags = ?12 
-            WHERE id = ?13";
+        let query = "
+            UPDATE documents
+            SET title = ?1,
+                original_filename = ?2,
+                stored_filename = ?3,
+                file_path = ?4,
+                file_size = ?5,
+                mime_type = ?6,
+                hash = ?7,
+                page_count = ?8,
+                created_at = ?9,
+                last_accessed = ?10,
+                thumbnail_path = ?11,
+                tags = ?12,
+                subject = ?13,
+                keywords = ?14,
+                language = ?15,
+                identifiers = ?16
+            WHERE id = ?17";
 
         self.conn
             .execute(
-                query, 
+                query,
                 params![
                     document.title,
                     document.original_filename,
@@ -126,6 +171,10 @@ impl<'a> DocumentRepository<'a> {
                     document.last_accessed.map(|dt| dt.to_rfc3339()),
                     document.thumbnail_path,
                     document.tags.as_ref().map(|t| serde_json::to_string(t).unwrap_or_default()),
+                    document.subject,
+                    document.keywords.as_ref().map(|k| serde_json::to_string(k).unwrap_or_default()),
+                    document.language,
+                    document.identifiers.as_ref().map(|i| serde_json::to_string(i).unwrap_or_default()),
                     document.id
                 ]
             )
@@ -133,6 +182,16 @@ impl<'a> DocumentRepository<'a> {
         Ok(())
     }
 
+    pub fn set_thumbnail_path(&self, id: i32, thumbnail_path: Option<&str>) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE documents SET thumbnail_path = ?1 WHERE id = ?2",
+                params![thumbnail_path, id],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
     pub fn delete(&self, id: i32) -> Result<(), String> {
         let query = "DELETE FROM documents WHERE id = ?1";
         self.conn
@@ -142,38 +201,15 @@ impl<'a> DocumentRepository<'a> {
     }
 
     pub fn get_all(&self) -> Result<Vec<Document>, String> {
-        let query = "
-            SELECT 
-                id, title, original_filename, stored_filename, file_path, 
-                file_size, mime_type, hash, page_count, created_at, 
-                last_accessed, thumbnail_path, tags 
-            FROM documents";
-
-        let mut stmt = self.conn.prepare(query).map_err(|e| e.to_string())?;
-
-        let document_iter = stmt.query_map([], |row| {
-            Ok(Document {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                original_filename: row.get(2)?,
-                stored_filename: row.get(3)?,
-                file_path: row.get(4)?,
-                file_size: row.get(5)?,
-                mime_type: row.get(6)?,
-                hash: row.get(7)?,
-                page_count: row.get(8)?,
-                created_at: row.get::<_, Option<String>>(9)?
-                    .map(|dt_str| dt_str.parse::<DateTime<Utc>>().unwrap()),
-                last_accessed: row.get::<_, Option<String>>(9)?
-                    .map(|dt_str| dt_str.parse::<DateTime<Utc>>().unwrap()),
-                thumbnail_path: row.get(11)?,
-                tags: row.get::<_, Option<String>>(12)?
-                    .map(|tags_str| serde_json::from_str(&tags_str).unwrap_or_default()),
-            })
-        })
-        .map_err(|e| e.to_string())?;
+        let query = format!("SELECT {} FROM documents", DOCUMENT_COLUMNS);
+
+        let mut stmt = self.conn.prepare(&query).map_err(|e| e.to_string())?;
+
+        let document_iter = stmt
+            .query_map([], Self::map_row)
+            .map_err(|e| e.to_string())?;
 
         document_iter.collect::<Result<_, _>>()
             .map_err(|e| e.to_string())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,425 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::flashcard::{
+    Card, CardReview, CardState, Deck, DeckStatistics, ReviewGrade, SchedulerKind,
+};
+use crate::db::models::tag::Tag;
+
+const DECK_COLUMNS: &str = "id, name, description, scheduler, created_at";
+
+const CARD_COLUMNS: &str = "
+    id, deck_id, front, back, book_id, page_number, due_date, repetitions, lapses,
+    interval_days, ease_factor, stability, difficulty, created_at, last_reviewed_at,
+    annotation_id, cloze_text, anki_guid, anki_ord";
+
+fn parse_datetime(value: Option<String>) -> Option<DateTime<Utc>> {
+    value.and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok())
+}
+
+pub struct FlashcardRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> FlashcardRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_deck(row: &Row) -> Result<Deck> {
+        let scheduler: String = row.get(3)?;
+        Ok(Deck {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            scheduler: SchedulerKind::parse(&scheduler).unwrap_or(SchedulerKind::Sm2),
+            created_at: parse_datetime(row.get(4)?),
+        })
+    }
+
+    fn map_card(row: &Row) -> Result<Card> {
+        Ok(Card {
+            id: row.get(0)?,
+            deck_id: row.get(1)?,
+            front: row.get(2)?,
+            back: row.get(3)?,
+            book_id: row.get(4)?,
+            page_number: row.get(5)?,
+            due_date: row.get(6)?,
+            state: CardState {
+                repetitions: row.get(7)?,
+                lapses: row.get(8)?,
+                interval_days: row.get(9)?,
+                ease_factor: row.get(10)?,
+                stability: row.get(11)?,
+                difficulty: row.get(12)?,
+            },
+            created_at: parse_datetime(row.get(13)?),
+            last_reviewed_at: parse_datetime(row.get(14)?),
+            annotation_id: row.get(15)?,
+            cloze_text: row.get(16)?,
+            anki_guid: row.get(17)?,
+            anki_ord: row.get(18)?,
+            tags: None,
+        })
+    }
+
+    pub fn create_deck(&mut self, deck: &Deck) -> Result<i64> {
+        Self::insert_deck(self.conn, deck)
+    }
+
+    pub fn insert_deck(conn: &Connection, deck: &Deck) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO decks (name, description, scheduler, created_at) VALUES (?, ?, ?, ?)",
+            params![
+                deck.name,
+                deck.description,
+                deck.scheduler.as_str(),
+                deck.created_at.unwrap_or_else(Utc::now).to_rfc3339(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn update_deck(&mut self, deck: &Deck) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE decks SET name = ?, description = ?, scheduler = ? WHERE id = ?",
+            params![deck.name, deck.description, deck.scheduler.as_str(), deck.id],
+        )
+    }
+
+    pub fn delete_deck(&mut self, id: i64) -> Result<usize> {
+        self.conn.execute("DELETE FROM decks WHERE id = ?", params![id])
+    }
+
+    pub fn get_deck_by_id(&self, id: i64) -> Result<Option<Deck>> {
+        self.conn.query_row(
+            &format!("SELECT {} FROM decks WHERE id = ?", DECK_COLUMNS),
+            params![id],
+            Self::map_deck,
+        ).optional()
+    }
+
+    pub fn get_deck_by_name(&self, name: &str) -> Result<Option<Deck>> {
+        self.conn.query_row(
+            &format!("SELECT {} FROM decks WHERE name = ?", DECK_COLUMNS),
+            params![name],
+            Self::map_deck,
+        ).optional()
+    }
+
+    pub fn get_decks(&self) -> Result<Vec<Deck>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM decks ORDER BY name", DECK_COLUMNS))?;
+        let decks = stmt.query_map([], Self::map_deck)?;
+        decks.collect()
+    }
+
+    pub fn create_card(&mut self, card: &Card) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        let card_id = Self::insert_card(&tx, card)?;
+        tx.commit()?;
+        Ok(card_id)
+    }
+
+    /// Inserts a card with its scheduling state and links its tags. Anki
+    /// imports add a whole package's cards through it in one transaction.
+    pub fn insert_card(conn: &Connection, card: &Card) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO cards (
+                deck_id, front, back, book_id, page_number, due_date, repetitions, lapses,
+                interval_days, ease_factor, stability, difficulty, created_at, last_reviewed_at,
+                annotation_id, cloze_text, anki_guid, anki_ord
+             )
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                card.deck_id,
+                card.front,
+                card.back,
+                card.book_id,
+                card.page_number,
+                card.due_date,
+                card.state.repetitions,
+                card.state.lapses,
+                card.state.interval_days,
+                card.state.ease_factor,
+                card.state.stability,
+                card.state.difficulty,
+                card.created_at.unwrap_or_else(Utc::now).to_rfc3339(),
+                card.last_reviewed_at.map(|dt| dt.to_rfc3339()),
+                card.annotation_id,
+                card.cloze_text,
+                card.anki_guid,
+                card.anki_ord,
+            ],
+        )?;
+        let card_id = conn.last_insert_rowid();
+
+        for tag_id in card.tags.iter().flatten().filter_map(|tag| tag.id) {
+            conn.execute(
+                "INSERT OR IGNORE INTO card_tags (card_id, tag_id) VALUES (?, ?)",
+                params![card_id, tag_id],
+            )?;
+        }
+
+        Ok(card_id)
+    }
+
+    /// Updates what a card says and where it came from, leaving its schedule alone.
+    pub fn update_card_content(&mut self, card: &Card) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE cards
+             SET deck_id = ?, front = ?, back = ?, book_id = ?, page_number = ?, annotation_id = ?, cloze_text = ?
+             WHERE id = ?",
+            params![
+                card.deck_id,
+                card.front,
+                card.back,
+                card.book_id,
+                card.page_number,
+                card.annotation_id,
+                card.cloze_text,
+                card.id,
+            ],
+        )
+    }
+
+    /// Links a card to the Anki note and template it was exported as.
+    pub fn set_anki_key(conn: &Connection, card_id: i64, guid: &str, ord: u32) -> Result<usize> {
+        conn.execute(
+            "UPDATE cards SET anki_guid = ?, anki_ord = ? WHERE id = ?",
+            params![guid, ord, card_id],
+        )
+    }
+
+    pub fn delete_card(&mut self, id: i64) -> Result<usize> {
+        self.conn.execute("DELETE FROM cards WHERE id = ?", params![id])
+    }
+
+    pub fn get_card_by_id(&self, id: i64) -> Result<Option<Card>> {
+        let card = self.conn.query_row(
+            &format!("SELECT {} FROM cards WHERE id = ?", CARD_COLUMNS),
+            params![id],
+            Self::map_card,
+        ).optional()?;
+
+        match card {
+            Some(mut card) => {
+                card.tags = Some(self.get_tags_by_card_id(id)?);
+                Ok(Some(card))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_cards_by_deck_id(&self, deck_id: i64) -> Result<Vec<Card>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM cards WHERE deck_id = ? ORDER BY id",
+            CARD_COLUMNS
+        ))?;
+        let cards = stmt
+            .query_map(params![deck_id], Self::map_card)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(cards)
+    }
+
+    /// Cards made from a highlight, oldest first.
+    pub fn get_cards_by_annotation_id(&self, annotation_id: i64) -> Result<Vec<Card>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM cards WHERE annotation_id = ? ORDER BY id",
+            CARD_COLUMNS
+        ))?;
+        let cards = stmt
+            .query_map(params![annotation_id], Self::map_card)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(cards)
+    }
+
+    /// Cards tagged with `tag_id`, in any deck.
+    pub fn get_cards_by_tag_id(&self, tag_id: i64) -> Result<Vec<Card>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM cards
+             WHERE id IN (SELECT card_id FROM card_tags WHERE tag_id = ?)
+             ORDER BY id",
+            CARD_COLUMNS
+        ))?;
+        let cards = stmt
+            .query_map(params![tag_id], Self::map_card)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(cards)
+    }
+
+    /// Cards written from a book, optionally only those made from highlights
+    /// in one chapter.
+    pub fn get_cards_by_book_id(&self, book_id: i64, chapter_index: Option<i32>) -> Result<Vec<Card>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM cards
+             WHERE book_id = ?1
+               AND (?2 IS NULL OR annotation_id IN (SELECT id FROM annotations WHERE chapter_index = ?2))
+             ORDER BY id",
+            CARD_COLUMNS
+        ))?;
+        let cards = stmt
+            .query_map(params![book_id, chapter_index], Self::map_card)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(cards)
+    }
+
+    /// Cards due on or before `today`, most overdue first, optionally limited
+    /// to one deck.
+    pub fn get_due_cards(&self, deck_id: Option<i64>, today: &str, limit: i64) -> Result<Vec<Card>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM cards
+             WHERE (?1 IS NULL OR deck_id = ?1) AND due_date <= ?2
+             ORDER BY due_date, id
+             LIMIT ?3",
+            CARD_COLUMNS
+        ))?;
+        let cards = stmt
+            .query_map(params![deck_id, today, limit], Self::map_card)?
+            .collect::<Result<Vec<_>>>()?;
+        self.with_tags(cards)
+    }
+
+    fn with_tags(&self, mut cards: Vec<Card>) -> Result<Vec<Card>> {
+        for card in &mut cards {
+            if let Some(id) = card.id {
+                card.tags = Some(self.get_tags_by_card_id(id)?);
+            }
+        }
+        Ok(cards)
+    }
+
+    pub fn get_tags_by_card_id(&self, card_id: i64) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.title, t.color, t.icon
+             FROM tags t
+             JOIN card_tags ct ON ct.tag_id = t.id
+             WHERE ct.card_id = ?"
+        )?;
+
+        let tags = stmt.query_map(params![card_id], |row| {
+            Ok(Tag {
+                id: Some(row.get(0)?),
+                title: row.get(1)?,
+                color: row.get(2)?,
+                icon: row.get(3)?,
+            })
+        })?;
+
+        tags.collect()
+    }
+
+    /// Saves the card's new schedule and logs the review that produced it.
+    pub fn record_review(&mut self, card: &Card, review: &CardReview) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "UPDATE cards
+             SET due_date = ?, repetitions = ?, lapses = ?, interval_days = ?, ease_factor = ?,
+                 stability = ?, difficulty = ?, last_reviewed_at = ?
+             WHERE id = ?",
+            params![
+                card.due_date,
+                card.state.repetitions,
+                card.state.lapses,
+                card.state.interval_days,
+                card.state.ease_factor,
+                card.state.stability,
+                card.state.difficulty,
+                review.reviewed_at.to_rfc3339(),
+                card.id,
+            ],
+        )?;
+        let review_id = Self::insert_review(&tx, review)?;
+
+        tx.commit()?;
+        Ok(review_id)
+    }
+
+    /// Logs a review without touching the card, e.g. history carried over
+    /// from another app.
+    pub fn insert_review(conn: &Connection, review: &CardReview) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO card_reviews (card_id, grade, scheduler, elapsed_days, interval_days, reviewed_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                review.card_id,
+                review.grade.as_str(),
+                review.scheduler.as_str(),
+                review.elapsed_days,
+                review.interval_days,
+                review.reviewed_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// A card's review history, oldest first.
+    pub fn get_reviews_by_card_id(&self, card_id: i64) -> Result<Vec<CardReview>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, card_id, grade, scheduler, elapsed_days, interval_days, reviewed_at
+             FROM card_reviews WHERE card_id = ? ORDER BY reviewed_at, id"
+        )?;
+        let reviews = stmt.query_map(params![card_id], |row| {
+            let grade: String = row.get(2)?;
+            let scheduler: String = row.get(3)?;
+            Ok(CardReview {
+                id: row.get(0)?,
+                card_id: row.get(1)?,
+                grade: ReviewGrade::parse(&grade).unwrap_or(ReviewGrade::Good),
+                scheduler: SchedulerKind::parse(&scheduler).unwrap_or(SchedulerKind::Sm2),
+                elapsed_days: row.get(4)?,
+                interval_days: row.get(5)?,
+                reviewed_at: parse_datetime(row.get(6)?).unwrap_or_default(),
+            })
+        })?;
+        reviews.collect()
+    }
+
+    /// `(guid, ord)` of every card linked to an Anki note.
+    pub fn get_anki_keys(&self) -> Result<Vec<(String, u32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT anki_guid, COALESCE(anki_ord, 0) FROM cards WHERE anki_guid IS NOT NULL"
+        )?;
+        let keys = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        keys.collect()
+    }
+
+    /// Counts for a deck as of `today`; `retention_since` is the first day of
+    /// the window the retention rate is computed over. Days are local dates.
+    pub fn get_deck_statistics(&self, deck_id: i64, today: &str, retention_since: &str) -> Result<DeckStatistics> {
+        let (total_cards, new_cards, due_cards, mature_cards) = self.conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(last_reviewed_at IS NULL), 0),
+                    COALESCE(SUM(due_date <= ?2), 0),
+                    COALESCE(SUM(interval_days >= 21), 0)
+             FROM cards WHERE deck_id = ?1",
+            params![deck_id, today],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+
+        let (reviews_today, recent_reviews, recent_recalled): (u32, u32, u32) = self.conn.query_row(
+            "SELECT COALESCE(SUM(date(r.reviewed_at, 'localtime') = ?2), 0),
+                    COALESCE(SUM(date(r.reviewed_at, 'localtime') >= ?3), 0),
+                    COALESCE(SUM(date(r.reviewed_at, 'localtime') >= ?3 AND r.grade <> 'again'), 0)
+             FROM card_reviews r
+             JOIN cards c ON c.id = r.card_id
+             WHERE c.deck_id = ?1",
+            params![deck_id, today, retention_since],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        Ok(DeckStatistics {
+            deck_id,
+            total_cards,
+            new_cards,
+            due_cards,
+            mature_cards,
+            reviews_today,
+            retention: if recent_reviews == 0 {
+                None
+            } else {
+                Some(recent_recalled as f64 / recent_reviews as f64)
+            },
+        })
+    }
+}
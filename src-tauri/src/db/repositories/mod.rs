@@ -3,9 +3,29 @@ pub mod document_repository;
 pub mod tag_repository;
 pub mod task_repository;
 pub mod book_repository;
+pub mod chapter_repository;
+pub mod page_text_repository;
+pub mod reading_progress_repository;
+pub mod reading_session_repository;
+pub mod annotation_repository;
+pub mod bookmark_repository;
+pub mod reading_plan_repository;
+pub mod flashcard_repository;
+pub mod quiz_repository;
+pub mod pomodoro_repository;
 
 pub use user_repository::*;
 pub use document_repository::*;
 pub use tag_repository::*;
 pub use task_repository::*;
 pub use book_repository::*;
+pub use chapter_repository::*;
+pub use page_text_repository::*;
+pub use reading_progress_repository::*;
+pub use reading_session_repository::*;
+pub use annotation_repository::*;
+pub use bookmark_repository::*;
+pub use reading_plan_repository::*;
+pub use flashcard_repository::*;
+pub use quiz_repository::*;
+pub use pomodoro_repository::*;
@@ -0,0 +1,121 @@
+use rusqlite::{params, params_from_iter, types::Value, Connection, Result};
+use crate::db::models::search_hit::SearchHit;
+
+pub const SNIPPET_OPEN: &str = "<mark>";
+pub const SNIPPET_CLOSE: &str = "</mark>";
+
+// FTS5 wraps matches in these control characters rather than in the tags
+// themselves, so the page text can be escaped before the tags go in.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+pub struct PageTextRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> PageTextRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Replaces the indexed text of a document and marks it as indexed.
+    pub fn replace_document_pages(&mut self, document_id: i64, pages: &[(i32, String)]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM document_pages WHERE document_id = ?", params![document_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO document_pages (document_id, page_number, text) VALUES (?, ?, ?)"
+            )?;
+            for (page_number, text) in pages {
+                if !text.trim().is_empty() {
+                    // Keep the snippet markers unambiguous.
+                    let text = text.replace([MATCH_START, MATCH_END], "");
+                    stmt.execute(params![document_id, page_number, text])?;
+                }
+            }
+        }
+        tx.execute(
+            "UPDATE documents SET indexed_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![document_id],
+        )?;
+        tx.commit()
+    }
+
+    /// Documents that still need their text extracted: (id, file_path, mime_type).
+    pub fn get_unindexed_documents(&self) -> Result<Vec<(i64, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, file_path, mime_type FROM documents WHERE indexed_at IS NULL ORDER BY id"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect()
+    }
+
+    /// Ranked page hits for an FTS5 `match_query`, optionally restricted to
+    /// books carrying at least one of `tag_ids`.
+    pub fn search(
+        &self,
+        match_query: &str,
+        tag_ids: &[i32],
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchHit>> {
+        let mut sql = format!(
+            "SELECT b.id, p.document_id, b.title, p.page_number,
+                    snippet(document_pages_fts, 0, char({}), char({}), '…', 16),
+                    bm25(document_pages_fts) AS rank
+             FROM document_pages_fts
+             JOIN document_pages p ON p.id = document_pages_fts.rowid
+             JOIN books b ON b.document_id = p.document_id
+             WHERE document_pages_fts MATCH ?",
+            MATCH_START as u32, MATCH_END as u32
+        );
+
+        let mut values: Vec<Value> = vec![Value::Text(match_query.to_string())];
+
+        if !tag_ids.is_empty() {
+            let placeholders = vec!["?"; tag_ids.len()].join(", ");
+            sql.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM book_tags bt WHERE bt.book_id = b.id AND bt.tag_id IN ({}))",
+                placeholders
+            ));
+            values.extend(tag_ids.iter().map(|id| Value::Integer(*id as i64)));
+        }
+
+        sql.push_str(" ORDER BY rank LIMIT ? OFFSET ?");
+        values.push(Value::Integer(limit));
+        values.push(Value::Integer(offset));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let hits = stmt.query_map(params_from_iter(values), |row| {
+            Ok(SearchHit {
+                book_id: row.get(0)?,
+                document_id: row.get(1)?,
+                title: row.get(2)?,
+                page_number: row.get(3)?,
+                snippet: highlight_snippet(&row.get::<_, String>(4)?),
+                rank: row.get(5)?,
+            })
+        })?;
+
+        hits.collect()
+    }
+}
+
+/// HTML-escapes a raw FTS5 snippet and turns its match markers into
+/// `SNIPPET_OPEN`/`SNIPPET_CLOSE`, so the text is safe to render as HTML.
+pub fn highlight_snippet(raw: &str) -> String {
+    let mut snippet = String::with_capacity(raw.len() + 16);
+    for c in raw.chars() {
+        match c {
+            MATCH_START => snippet.push_str(SNIPPET_OPEN),
+            MATCH_END => snippet.push_str(SNIPPET_CLOSE),
+            '&' => snippet.push_str("&amp;"),
+            '<' => snippet.push_str("&lt;"),
+            '>' => snippet.push_str("&gt;"),
+            '"' => snippet.push_str("&quot;"),
+            '\'' => snippet.push_str("&#39;"),
+            _ => snippet.push(c),
+        }
+    }
+    snippet
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result, Row};
+use crate::db::models::pomodoro::Pomodoro;
+
+const POMODORO_COLUMNS: &str = "id, user_id, task_id, book_id, started_at, ended_at, focus_seconds";
+
+fn parse_datetime(value: String) -> Result<DateTime<Utc>> {
+    value.parse::<DateTime<Utc>>().map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+    })
+}
+
+pub struct PomodoroRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> PomodoroRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<Pomodoro> {
+        Ok(Pomodoro {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            task_id: row.get(2)?,
+            book_id: row.get(3)?,
+            started_at: parse_datetime(row.get(4)?)?,
+            ended_at: parse_datetime(row.get(5)?)?,
+            focus_seconds: row.get(6)?,
+        })
+    }
+
+    pub fn create_pomodoro(&mut self, pomodoro: &Pomodoro) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO pomodoros (user_id, task_id, book_id, started_at, ended_at, focus_seconds)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                pomodoro.user_id,
+                pomodoro.task_id,
+                pomodoro.book_id,
+                pomodoro.started_at.to_rfc3339(),
+                pomodoro.ended_at.to_rfc3339(),
+                pomodoro.focus_seconds,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// A user's pomodoros, most recent first, optionally only those linked
+    /// to a task or a book.
+    pub fn get_pomodoros(
+        &self,
+        user_id: i32,
+        task_id: Option<i32>,
+        book_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<Pomodoro>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM pomodoros
+             WHERE user_id = ?1 AND (?2 IS NULL OR task_id = ?2) AND (?3 IS NULL OR book_id = ?3)
+             ORDER BY ended_at DESC, id DESC
+             LIMIT ?4",
+            POMODORO_COLUMNS
+        ))?;
+        let pomodoros = stmt.query_map(params![user_id, task_id, book_id, limit], Self::map_row)?;
+        pomodoros.collect()
+    }
+}
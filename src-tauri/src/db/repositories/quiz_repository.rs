@@ -0,0 +1,285 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::quiz::{QuestionKind, Quiz, QuizAnswer, QuizAttempt, QuizQuestion, TopicScore};
+use crate::db::models::tag::Tag;
+
+const QUIZ_COLUMNS: &str =
+    "id, title, deck_id, tag_id, book_id, chapter_index, time_limit_seconds, created_at";
+
+const QUESTION_COLUMNS: &str =
+    "id, quiz_id, position, card_id, kind, prompt, statement, choices, answer";
+
+const ATTEMPT_COLUMNS: &str =
+    "id, quiz_id, user_id, started_at, expires_at, finished_at, correct_count, question_count, score";
+
+const ANSWER_COLUMNS: &str =
+    "id, attempt_id, question_id, response, is_correct, similarity, answered_at";
+
+fn parse_datetime(value: Option<String>) -> Option<DateTime<Utc>> {
+    value.and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok())
+}
+
+pub struct QuizRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> QuizRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_quiz(row: &Row) -> Result<Quiz> {
+        Ok(Quiz {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            deck_id: row.get(2)?,
+            tag_id: row.get(3)?,
+            book_id: row.get(4)?,
+            chapter_index: row.get(5)?,
+            time_limit_seconds: row.get(6)?,
+            created_at: parse_datetime(row.get(7)?),
+            questions: None,
+        })
+    }
+
+    fn map_question(row: &Row) -> Result<QuizQuestion> {
+        let kind: String = row.get(4)?;
+        Ok(QuizQuestion {
+            id: row.get(0)?,
+            quiz_id: row.get(1)?,
+            position: row.get(2)?,
+            card_id: row.get(3)?,
+            kind: QuestionKind::parse(&kind).unwrap_or(QuestionKind::Typed),
+            prompt: row.get(5)?,
+            statement: row.get(6)?,
+            choices: row
+                .get::<_, Option<String>>(7)?
+                .map(|choices_str| serde_json::from_str(&choices_str).unwrap_or_default()),
+            answer: row.get(8)?,
+        })
+    }
+
+    fn map_attempt(row: &Row) -> Result<QuizAttempt> {
+        Ok(QuizAttempt {
+            id: row.get(0)?,
+            quiz_id: row.get(1)?,
+            user_id: row.get(2)?,
+            started_at: parse_datetime(row.get(3)?).unwrap_or_default(),
+            expires_at: parse_datetime(row.get(4)?),
+            finished_at: parse_datetime(row.get(5)?),
+            correct_count: row.get(6)?,
+            question_count: row.get(7)?,
+            score: row.get(8)?,
+            answers: None,
+            solutions: None,
+        })
+    }
+
+    fn map_answer(row: &Row) -> Result<QuizAnswer> {
+        Ok(QuizAnswer {
+            id: row.get(0)?,
+            attempt_id: row.get(1)?,
+            question_id: row.get(2)?,
+            response: row.get(3)?,
+            is_correct: row.get(4)?,
+            similarity: row.get(5)?,
+            answered_at: parse_datetime(row.get(6)?).unwrap_or_default(),
+        })
+    }
+
+    /// Saves a quiz together with its questions.
+    pub fn create_quiz(&mut self, quiz: &Quiz) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO quizzes (title, deck_id, tag_id, book_id, chapter_index, time_limit_seconds, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                quiz.title,
+                quiz.deck_id,
+                quiz.tag_id,
+                quiz.book_id,
+                quiz.chapter_index,
+                quiz.time_limit_seconds,
+                quiz.created_at.unwrap_or_else(Utc::now).to_rfc3339(),
+            ],
+        )?;
+        let quiz_id = tx.last_insert_rowid();
+
+        for question in quiz.questions.iter().flatten() {
+            tx.execute(
+                "INSERT INTO quiz_questions (quiz_id, position, card_id, kind, prompt, statement, choices, answer)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    quiz_id,
+                    question.position,
+                    question.card_id,
+                    question.kind.as_str(),
+                    question.prompt,
+                    question.statement,
+                    question.choices.as_ref().map(|c| serde_json::to_string(c).unwrap_or_default()),
+                    question.answer,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(quiz_id)
+    }
+
+    pub fn delete_quiz(&mut self, id: i64) -> Result<usize> {
+        self.conn.execute("DELETE FROM quizzes WHERE id = ?", params![id])
+    }
+
+    pub fn get_quiz_by_id(&self, id: i64) -> Result<Option<Quiz>> {
+        let quiz = self.conn.query_row(
+            &format!("SELECT {} FROM quizzes WHERE id = ?", QUIZ_COLUMNS),
+            params![id],
+            Self::map_quiz,
+        ).optional()?;
+
+        match quiz {
+            Some(mut quiz) => {
+                quiz.questions = Some(self.get_questions_by_quiz_id(id)?);
+                Ok(Some(quiz))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Every quiz, newest first, without their questions.
+    pub fn get_quizzes(&self) -> Result<Vec<Quiz>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM quizzes ORDER BY created_at DESC, id DESC",
+            QUIZ_COLUMNS
+        ))?;
+        let quizzes = stmt.query_map([], Self::map_quiz)?;
+        quizzes.collect()
+    }
+
+    pub fn get_questions_by_quiz_id(&self, quiz_id: i64) -> Result<Vec<QuizQuestion>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM quiz_questions WHERE quiz_id = ? ORDER BY position",
+            QUESTION_COLUMNS
+        ))?;
+        let questions = stmt.query_map(params![quiz_id], Self::map_question)?;
+        questions.collect()
+    }
+
+    pub fn create_attempt(&mut self, attempt: &QuizAttempt) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO quiz_attempts (quiz_id, user_id, started_at, expires_at, question_count)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                attempt.quiz_id,
+                attempt.user_id,
+                attempt.started_at.to_rfc3339(),
+                attempt.expires_at.map(|dt| dt.to_rfc3339()),
+                attempt.question_count,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Records the end of an attempt and its result.
+    pub fn finish_attempt(&mut self, attempt: &QuizAttempt) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE quiz_attempts SET finished_at = ?, correct_count = ?, score = ? WHERE id = ?",
+            params![
+                attempt.finished_at.map(|dt| dt.to_rfc3339()),
+                attempt.correct_count,
+                attempt.score,
+                attempt.id,
+            ],
+        )
+    }
+
+    pub fn get_attempt_by_id(&self, id: i64) -> Result<Option<QuizAttempt>> {
+        let attempt = self.conn.query_row(
+            &format!("SELECT {} FROM quiz_attempts WHERE id = ?", ATTEMPT_COLUMNS),
+            params![id],
+            Self::map_attempt,
+        ).optional()?;
+
+        match attempt {
+            Some(mut attempt) => {
+                attempt.answers = Some(self.get_answers_by_attempt_id(id)?);
+                Ok(Some(attempt))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// A user's attempts, newest first, optionally only those at one quiz.
+    pub fn get_attempts(&self, user_id: i32, quiz_id: Option<i64>) -> Result<Vec<QuizAttempt>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM quiz_attempts
+             WHERE user_id = ?1 AND (?2 IS NULL OR quiz_id = ?2)
+             ORDER BY started_at DESC, id DESC",
+            ATTEMPT_COLUMNS
+        ))?;
+        let attempts = stmt.query_map(params![user_id, quiz_id], Self::map_attempt)?;
+        attempts.collect()
+    }
+
+    pub fn insert_answer(&mut self, answer: &QuizAnswer) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO quiz_answers (attempt_id, question_id, response, is_correct, similarity, answered_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                answer.attempt_id,
+                answer.question_id,
+                answer.response,
+                answer.is_correct,
+                answer.similarity,
+                answer.answered_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_answers_by_attempt_id(&self, attempt_id: i64) -> Result<Vec<QuizAnswer>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM quiz_answers WHERE attempt_id = ? ORDER BY answered_at, id",
+            ANSWER_COLUMNS
+        ))?;
+        let answers = stmt.query_map(params![attempt_id], Self::map_answer)?;
+        answers.collect()
+    }
+
+    /// Accuracy per tag over every answer the user has given, through the
+    /// tags of the cards the questions were asked from. Tags with fewer than
+    /// `min_answers` answers are left out; the rest come weakest first.
+    pub fn get_topic_scores(&self, user_id: i32, min_answers: u32) -> Result<Vec<TopicScore>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.title, t.color, t.icon, COUNT(*), COALESCE(SUM(qa.is_correct), 0)
+             FROM quiz_answers qa
+             JOIN quiz_attempts a ON a.id = qa.attempt_id
+             JOIN quiz_questions q ON q.id = qa.question_id
+             JOIN card_tags ct ON ct.card_id = q.card_id
+             JOIN tags t ON t.id = ct.tag_id
+             WHERE a.user_id = ?1
+             GROUP BY t.id
+             HAVING COUNT(*) >= ?2
+             ORDER BY CAST(SUM(qa.is_correct) AS REAL) / COUNT(*), COUNT(*) DESC, t.title"
+        )?;
+
+        let scores = stmt.query_map(params![user_id, min_answers], |row| {
+            let answered: u32 = row.get(4)?;
+            let correct: u32 = row.get(5)?;
+            Ok(TopicScore {
+                tag: Tag {
+                    id: Some(row.get(0)?),
+                    title: row.get(1)?,
+                    color: row.get(2)?,
+                    icon: row.get(3)?,
+                },
+                answered,
+                correct,
+                accuracy: correct as f64 / answered as f64,
+            })
+        })?;
+
+        scores.collect()
+    }
+}
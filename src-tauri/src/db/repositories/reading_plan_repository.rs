@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::reading_plan::{PlanUnit, ReadingPlan, ReadingPlanItem};
+use crate::db::models::task::{Task, TaskStatus};
+use crate::db::repositories::TaskRepository;
+
+const PLAN_COLUMNS: &str = "id, user_id, book_id, task_id, unit, target_date, created_at";
+
+/// A session task to insert under a plan, with the units it covers.
+pub type PlanSessionTask = (Task, i32, i32);
+
+pub struct ReadingPlanRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ReadingPlanRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<ReadingPlan> {
+        let unit: String = row.get(4)?;
+        Ok(ReadingPlan {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            book_id: row.get(2)?,
+            task_id: row.get(3)?,
+            unit: PlanUnit::parse(&unit).unwrap_or(PlanUnit::Pages),
+            target_date: row.get(5)?,
+            created_at: row.get::<_, Option<String>>(6)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+        })
+    }
+
+    /// Stores the plan with its parent task and one subtask per session, all
+    /// in one transaction. `plan.task_id` is ignored and `parent_id` of the
+    /// session tasks is set to the new parent task.
+    pub fn create_plan(&mut self, plan: &ReadingPlan, root: &Task, sessions: Vec<PlanSessionTask>) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+
+        let root_id = TaskRepository::insert_task(&tx, root)?;
+        tx.execute(
+            "INSERT INTO reading_plans (user_id, book_id, task_id, unit, target_date, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                plan.user_id,
+                plan.book_id,
+                root_id,
+                plan.unit.as_str(),
+                plan.target_date,
+                plan.created_at.unwrap_or_else(Utc::now).to_rfc3339(),
+            ],
+        )?;
+        let plan_id = tx.last_insert_rowid();
+
+        Self::insert_sessions(&tx, plan_id, root_id as i32, 0, sessions)?;
+
+        tx.commit()?;
+        Ok(plan_id)
+    }
+
+    fn insert_sessions(
+        conn: &Connection,
+        plan_id: i64,
+        root_id: i32,
+        first_position: i32,
+        sessions: Vec<PlanSessionTask>,
+    ) -> Result<()> {
+        for (index, (mut task, start_unit, end_unit)) in sessions.into_iter().enumerate() {
+            task.parent_id = Some(root_id);
+            task.position = first_position + index as i32;
+            let task_id = TaskRepository::insert_task(conn, &task)?;
+            conn.execute(
+                "INSERT INTO reading_plan_items (task_id, plan_id, start_unit, end_unit) VALUES (?, ?, ?, ?)",
+                params![task_id, plan_id, start_unit, end_unit],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Moves the open sessions in `open_task_ids`, in reading order, onto
+    /// `sessions` and the plan and its parent task to `target_date`. Open
+    /// sessions keep their task, with its status, tags and pomodoros; only
+    /// the sessions one schedule has more of than the other are added or
+    /// deleted.
+    pub fn reschedule(
+        &mut self,
+        plan: &ReadingPlan,
+        open_task_ids: &[i32],
+        sessions: Vec<PlanSessionTask>,
+        target_date: &str,
+    ) -> Result<()> {
+        let plan_id = plan.id.unwrap_or_default();
+        let tx = self.conn.transaction()?;
+
+        let mut open_task_ids = open_task_ids.iter();
+        let mut added = Vec::new();
+        for (task, start_unit, end_unit) in sessions {
+            let Some(task_id) = open_task_ids.next() else {
+                added.push((task, start_unit, end_unit));
+                continue;
+            };
+            tx.execute(
+                "UPDATE tasks SET title = ?, due_date = ? WHERE id = ?",
+                params![task.title, task.due_date, task_id],
+            )?;
+            tx.execute(
+                "UPDATE reading_plan_items SET start_unit = ?, end_unit = ? WHERE task_id = ?",
+                params![start_unit, end_unit, task_id],
+            )?;
+        }
+        for task_id in open_task_ids {
+            tx.execute("DELETE FROM tasks WHERE id = ?", params![task_id])?;
+        }
+
+        let first_position: i32 = tx.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE parent_id = ?",
+            params![plan.task_id],
+            |row| row.get(0),
+        )?;
+        Self::insert_sessions(&tx, plan_id, plan.task_id, first_position, added)?;
+
+        tx.execute(
+            "UPDATE reading_plans SET target_date = ? WHERE id = ?",
+            params![target_date, plan_id],
+        )?;
+        tx.execute(
+            "UPDATE tasks SET due_date = ? WHERE id = ?",
+            params![target_date, plan.task_id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_plan(&self, id: i64) -> Result<Option<ReadingPlan>> {
+        self.conn.query_row(
+            &format!("SELECT {} FROM reading_plans WHERE id = ?", PLAN_COLUMNS),
+            params![id],
+            Self::map_row,
+        ).optional()
+    }
+
+    pub fn get_plans(&self, book_id: Option<i64>) -> Result<Vec<ReadingPlan>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM reading_plans WHERE (?1 IS NULL OR book_id = ?1) ORDER BY target_date, id",
+            PLAN_COLUMNS
+        ))?;
+        let plans = stmt.query_map(params![book_id], Self::map_row)?;
+        plans.collect()
+    }
+
+    pub fn get_items(&self, plan_id: i64) -> Result<Vec<ReadingPlanItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT i.task_id, i.start_unit, i.end_unit, t.status, t.due_date
+             FROM reading_plan_items i
+             JOIN tasks t ON t.id = i.task_id
+             WHERE i.plan_id = ?
+             ORDER BY i.start_unit, t.position"
+        )?;
+        let items = stmt.query_map(params![plan_id], |row| {
+            let status: String = row.get(3)?;
+            Ok(ReadingPlanItem {
+                task_id: row.get(0)?,
+                start_unit: row.get(1)?,
+                end_unit: row.get(2)?,
+                status: TaskStatus::parse(&status).unwrap_or(TaskStatus::Todo),
+                due_date: row.get(4)?,
+            })
+        })?;
+        items.collect()
+    }
+
+    /// Plans with a session that is still open although its day is before `today`.
+    pub fn get_behind_plan_ids(&self, today: &str) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT i.plan_id
+             FROM reading_plan_items i
+             JOIN tasks t ON t.id = i.task_id
+             WHERE t.status IN ('todo', 'in_progress', 'blocked') AND t.due_date < ?
+             ORDER BY i.plan_id"
+        )?;
+        let ids = stmt.query_map(params![today], |row| row.get(0))?;
+        ids.collect()
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::reading_progress::ReadingProgress;
+
+pub struct ReadingProgressRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ReadingProgressRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<ReadingProgress> {
+        Ok(ReadingProgress {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            book_id: row.get(2)?,
+            current_page: row.get(3)?,
+            location: row.get(4)?,
+            chapter_index: row.get(5)?,
+            chapter_offset: row.get(6)?,
+            percent_complete: row.get(7)?,
+            last_opened_at: row.get::<_, Option<String>>(8)?
+                .and_then(|dt_str| dt_str.parse::<DateTime<Utc>>().ok()),
+        })
+    }
+
+    pub fn save_progress(&mut self, progress: &ReadingProgress) -> Result<i64> {
+        self.conn.query_row(
+            "INSERT INTO reading_progress (
+                user_id, book_id, current_page, location, chapter_index,
+                chapter_offset, percent_complete, last_opened_at
+             )
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (user_id, book_id) DO UPDATE SET
+                current_page = excluded.current_page,
+                location = excluded.location,
+                chapter_index = excluded.chapter_index,
+                chapter_offset = excluded.chapter_offset,
+                percent_complete = excluded.percent_complete,
+                last_opened_at = excluded.last_opened_at
+             RETURNING id",
+            params![
+                progress.user_id,
+                progress.book_id,
+                progress.current_page,
+                progress.location,
+                progress.chapter_index,
+                progress.chapter_offset,
+                progress.percent_complete,
+                progress.last_opened_at.map(|dt| dt.to_rfc3339()),
+            ],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_progress(&self, user_id: i32, book_id: i64) -> Result<Option<ReadingProgress>> {
+        self.conn.query_row(
+            "SELECT id, user_id, book_id, current_page, location, chapter_index,
+                    chapter_offset, percent_complete, last_opened_at
+             FROM reading_progress
+             WHERE user_id = ? AND book_id = ?",
+            params![user_id, book_id],
+            Self::map_row,
+        ).optional()
+    }
+
+    pub fn get_recent_progress(&self, user_id: i32, limit: i64) -> Result<Vec<ReadingProgress>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, user_id, book_id, current_page, location, chapter_index,
+                    chapter_offset, percent_complete, last_opened_at
+             FROM reading_progress
+             WHERE user_id = ?
+             ORDER BY last_opened_at DESC
+             LIMIT ?"
+        )?;
+
+        let progress = stmt.query_map(params![user_id, limit], Self::map_row)?;
+        progress.collect()
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::reading_session::{PageDwell, ReadingSession, ReadingTime};
+
+const SESSION_COLUMNS: &str =
+    "id, user_id, book_id, current_page, started_at, last_heartbeat_at, ended_at, end_reason";
+
+fn parse_datetime(value: String) -> Result<DateTime<Utc>> {
+    value.parse::<DateTime<Utc>>().map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+    })
+}
+
+pub struct ReadingSessionRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ReadingSessionRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<ReadingSession> {
+        Ok(ReadingSession {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            book_id: row.get(2)?,
+            current_page: row.get(3)?,
+            started_at: parse_datetime(row.get(4)?)?,
+            last_heartbeat_at: parse_datetime(row.get(5)?)?,
+            ended_at: row.get::<_, Option<String>>(6)?.map(parse_datetime).transpose()?,
+            end_reason: row.get(7)?,
+            pages: None,
+        })
+    }
+
+    pub fn start_session(
+        &mut self,
+        user_id: i32,
+        book_id: i64,
+        current_page: Option<i32>,
+        now: DateTime<Utc>,
+    ) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO reading_sessions (user_id, book_id, current_page, started_at, last_heartbeat_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![user_id, book_id, current_page, now.to_rfc3339(), now.to_rfc3339()],
+        )?;
+        let session_id = tx.last_insert_rowid();
+
+        if let Some(page) = current_page {
+            tx.execute(
+                "INSERT INTO reading_session_pages (session_id, page_number, visits) VALUES (?, ?, 1)",
+                params![session_id, page],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(session_id)
+    }
+
+    pub fn get_session(&self, session_id: i64) -> Result<Option<ReadingSession>> {
+        let session = self.conn.query_row(
+            &format!("SELECT {} FROM reading_sessions WHERE id = ?", SESSION_COLUMNS),
+            params![session_id],
+            Self::map_row,
+        ).optional()?;
+
+        match session {
+            Some(mut session) => {
+                session.pages = Some(self.get_session_pages(session_id)?);
+                Ok(Some(session))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_session_pages(&self, session_id: i64) -> Result<Vec<PageDwell>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT page_number, dwell_seconds, visits
+             FROM reading_session_pages
+             WHERE session_id = ?
+             ORDER BY page_number"
+        )?;
+        let pages = stmt.query_map(params![session_id], |row| {
+            Ok(PageDwell {
+                page_number: row.get(0)?,
+                dwell_seconds: row.get(1)?,
+                visits: row.get(2)?,
+            })
+        })?;
+        pages.collect()
+    }
+
+    /// Credits `dwell_seconds` to the page shown since the previous heartbeat,
+    /// then moves the session to `new_page` (counting a visit if it changed).
+    pub fn record_heartbeat(
+        &mut self,
+        session: &ReadingSession,
+        new_page: Option<i32>,
+        dwell_seconds: i64,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let session_id = session.id.unwrap_or_default();
+        let tx = self.conn.transaction()?;
+
+        if let Some(page) = session.current_page {
+            tx.execute(
+                "INSERT INTO reading_session_pages (session_id, page_number, dwell_seconds, visits)
+                 VALUES (?, ?, ?, 0)
+                 ON CONFLICT (session_id, page_number)
+                 DO UPDATE SET dwell_seconds = dwell_seconds + excluded.dwell_seconds",
+                params![session_id, page, dwell_seconds],
+            )?;
+        }
+
+        let page = new_page.or(session.current_page);
+        if let Some(page) = new_page.filter(|page| Some(*page) != session.current_page) {
+            tx.execute(
+                "INSERT INTO reading_session_pages (session_id, page_number, visits)
+                 VALUES (?, ?, 1)
+                 ON CONFLICT (session_id, page_number) DO UPDATE SET visits = visits + 1",
+                params![session_id, page],
+            )?;
+        }
+
+        tx.execute(
+            "UPDATE reading_sessions SET current_page = ?, last_heartbeat_at = ? WHERE id = ?",
+            params![page, now.to_rfc3339(), session_id],
+        )?;
+
+        tx.commit()
+    }
+
+    pub fn end_session(&mut self, session_id: i64, ended_at: DateTime<Utc>, reason: &str) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE reading_sessions SET ended_at = ?, end_reason = ? WHERE id = ? AND ended_at IS NULL",
+            params![ended_at.to_rfc3339(), reason, session_id],
+        )
+    }
+
+    /// Closes sessions left open by a crash or a killed app at their last
+    /// heartbeat, so no time is counted after the app stopped responding.
+    pub fn close_stale_sessions(&mut self, user_id: Option<i32>) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE reading_sessions
+             SET ended_at = last_heartbeat_at, end_reason = 'stale'
+             WHERE ended_at IS NULL AND (?1 IS NULL OR user_id = ?1)",
+            params![user_id],
+        )
+    }
+
+    pub fn get_minutes_per_day(&self, user_id: i32, from: &str, to: &str) -> Result<Vec<ReadingTime>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date(s.started_at, 'localtime') AS day, SUM(p.dwell_seconds) / 60.0
+             FROM reading_sessions s
+             JOIN reading_session_pages p ON p.session_id = s.id
+             WHERE s.user_id = ? AND date(s.started_at, 'localtime') BETWEEN ? AND ?
+             GROUP BY day
+             ORDER BY day"
+        )?;
+        let rows = stmt.query_map(params![user_id, from, to], |row| {
+            let day: String = row.get(0)?;
+            Ok(ReadingTime {
+                key: day.clone(),
+                id: None,
+                label: day,
+                minutes: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_minutes_per_book(&self, user_id: i32, from: &str, to: &str) -> Result<Vec<ReadingTime>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT b.id, b.title, SUM(p.dwell_seconds) / 60.0 AS minutes
+             FROM reading_sessions s
+             JOIN reading_session_pages p ON p.session_id = s.id
+             JOIN books b ON b.id = s.book_id
+             WHERE s.user_id = ? AND date(s.started_at, 'localtime') BETWEEN ? AND ?
+             GROUP BY b.id
+             ORDER BY minutes DESC"
+        )?;
+        let rows = stmt.query_map(params![user_id, from, to], |row| {
+            let id: i64 = row.get(0)?;
+            Ok(ReadingTime {
+                key: id.to_string(),
+                id: Some(id),
+                label: row.get(1)?,
+                minutes: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// A book with several tags counts toward each of them.
+    pub fn get_minutes_per_tag(&self, user_id: i32, from: &str, to: &str) -> Result<Vec<ReadingTime>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.title, SUM(p.dwell_seconds) / 60.0 AS minutes
+             FROM reading_sessions s
+             JOIN reading_session_pages p ON p.session_id = s.id
+             JOIN book_tags bt ON bt.book_id = s.book_id
+             JOIN tags t ON t.id = bt.tag_id
+             WHERE s.user_id = ? AND date(s.started_at, 'localtime') BETWEEN ? AND ?
+             GROUP BY t.id
+             ORDER BY minutes DESC"
+        )?;
+        let rows = stmt.query_map(params![user_id, from, to], |row| {
+            let id: i64 = row.get(0)?;
+            Ok(ReadingTime {
+                key: id.to_string(),
+                id: Some(id),
+                label: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                minutes: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
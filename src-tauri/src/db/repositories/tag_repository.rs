@@ -12,13 +12,19 @@ impl<'a> TagRepository<'a> {
     }
 
     pub fn create_tag(&mut self, tag: &Tag) -> Result<i64> {
-        let mut stmt = self.conn.prepare(
+        Self::insert_tag(self.conn, tag)
+    }
+
+    /// Inserts a tag on the given connection, so imports can create the tags
+    /// they are missing inside their own transaction.
+    pub fn insert_tag(conn: &Connection, tag: &Tag) -> Result<i64> {
+        let mut stmt = conn.prepare(
             "INSERT INTO tags (title, color, icon) VALUES (?, ?, ?)"
         )?;
         
         stmt.execute(params![tag.title, tag.color, tag.icon])?;
         
-        Ok(self.conn.last_insert_rowid())
+        Ok(conn.last_insert_rowid())
     }
 
     pub fn update_title(&mut self, id: i32, new_title: &str) -> Result<usize> {
@@ -1 +1,382 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use crate::db::models::tag::Tag;
+use crate::db::models::task::{RecurrenceFrequency, RecurrenceRule, Task, TaskFilter, TaskStatus};
+use crate::db::models::user_available_day::DayOfWeek;
 
+const TASK_COLUMNS: &str =
+    "id, title, description, status, created_at, due_date, completed_at, document_id, parent_id, position";
+
+/// IDs of every task below `?1`, at any depth. `UNION` rather than
+/// `UNION ALL` so a corrupt cycle can't recurse forever.
+const DESCENDANTS_CTE: &str = "
+    WITH RECURSIVE descendants(id) AS (
+        SELECT id FROM tasks WHERE parent_id = ?1
+        UNION
+        SELECT t.id FROM tasks t JOIN descendants d ON t.parent_id = d.id
+    )";
+
+pub struct TaskRepository<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> TaskRepository<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row(row: &Row) -> Result<Task> {
+        let status: String = row.get(3)?;
+        Ok(Task {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            status: TaskStatus::parse(&status).unwrap_or(TaskStatus::Todo),
+            created_at: row.get(4)?,
+            due_date: row.get(5)?,
+            completed_at: row.get(6)?,
+            document_id: row.get(7)?,
+            parent_id: row.get(8)?,
+            position: row.get(9)?,
+            tags: Vec::new(),
+            recurrence: None,
+        })
+    }
+
+    pub fn create_task(&mut self, task: &Task) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        let task_id = Self::insert_task(&tx, task)?;
+        tx.commit()?;
+        Ok(task_id)
+    }
+
+    /// Inserts a task with its tag links and, for a repeating task, its
+    /// recurrence rule. Reading plans and completed series use it to add
+    /// several tasks in one transaction.
+    pub fn insert_task(conn: &Connection, task: &Task) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO tasks (title, description, status, created_at, due_date, completed_at, document_id, parent_id, position)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                task.title,
+                task.description,
+                task.status.as_str(),
+                task.created_at,
+                task.due_date,
+                task.completed_at,
+                task.document_id,
+                task.parent_id,
+                task.position,
+            ],
+        )?;
+        let task_id = conn.last_insert_rowid();
+
+        for tag in &task.tags {
+            if let Some(tag_id) = tag.id {
+                conn.execute(
+                    "INSERT OR IGNORE INTO task_tags (task_id, tag_id) VALUES (?, ?)",
+                    params![task_id, tag_id],
+                )?;
+            }
+        }
+
+        if let Some(rule) = &task.recurrence {
+            Self::insert_recurrence(conn, task_id, rule)?;
+        }
+
+        Ok(task_id)
+    }
+
+    pub fn update_task(&mut self, task: &Task) -> Result<usize> {
+        Self::write_task(self.conn, task)
+    }
+
+    fn write_task(conn: &Connection, task: &Task) -> Result<usize> {
+        conn.execute(
+            "UPDATE tasks
+             SET title = ?, description = ?, status = ?, due_date = ?, completed_at = ?, document_id = ?
+             WHERE id = ?",
+            params![
+                task.title,
+                task.description,
+                task.status.as_str(),
+                task.due_date,
+                task.completed_at,
+                task.document_id,
+                task.id,
+            ],
+        )
+    }
+
+    /// Deletes the task together with all of its subtasks. Returns the number
+    /// of tasks removed.
+    pub fn delete_task(&mut self, id: i32) -> Result<usize> {
+        // Counted up front: rows removed by the foreign key cascade don't show
+        // up in the statement's change count.
+        let subtasks = self.get_descendant_ids(id)?.len();
+        let deleted = self.conn.execute(
+            &format!(
+                "{} DELETE FROM tasks WHERE id = ?1 OR id IN (SELECT id FROM descendants)",
+                DESCENDANTS_CTE
+            ),
+            params![id],
+        )?;
+        Ok(if deleted == 0 { 0 } else { subtasks + 1 })
+    }
+
+    pub fn get_descendant_ids(&self, id: i32) -> Result<Vec<i32>> {
+        let mut stmt = self.conn.prepare(&format!("{} SELECT id FROM descendants", DESCENDANTS_CTE))?;
+        let ids = stmt.query_map(params![id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    /// Saves an archived task and archives every subtask below it that isn't
+    /// already, clearing their completion time. Returns how many subtasks changed.
+    pub fn archive_task_tree(&mut self, task: &Task) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        Self::write_task(&tx, task)?;
+        let archived = tx.execute(
+            &format!(
+                "{} UPDATE tasks SET status = 'archived', completed_at = NULL
+                 WHERE id IN (SELECT id FROM descendants) AND status <> 'archived'",
+                DESCENDANTS_CTE
+            ),
+            params![task.id],
+        )?;
+        tx.commit()?;
+        Ok(archived)
+    }
+
+    /// Position after the last subtask of `parent_id`, or after the last
+    /// top-level task when `None`.
+    pub fn next_position(&self, parent_id: Option<i32>) -> Result<i32> {
+        Self::next_position_in(self.conn, parent_id)
+    }
+
+    fn next_position_in(conn: &Connection, parent_id: Option<i32>) -> Result<i32> {
+        conn.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM tasks WHERE parent_id IS ?",
+            params![parent_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Puts the task under `parent_id` at `position` among its new siblings,
+    /// renumbering them, and the siblings it left, so positions stay contiguous.
+    pub fn move_task(&mut self, id: i32, parent_id: Option<i32>, position: usize) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        let old_parent_id: Option<i32> =
+            tx.query_row("SELECT parent_id FROM tasks WHERE id = ?", params![id], |row| row.get(0))?;
+
+        let mut siblings = Self::sibling_ids(&tx, parent_id, id)?;
+        siblings.insert(position.min(siblings.len()), id);
+
+        tx.execute("UPDATE tasks SET parent_id = ? WHERE id = ?", params![parent_id, id])?;
+        Self::renumber(&tx, &siblings)?;
+        if old_parent_id != parent_id {
+            Self::renumber(&tx, &Self::sibling_ids(&tx, old_parent_id, id)?)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn sibling_ids(conn: &Connection, parent_id: Option<i32>, id: i32) -> Result<Vec<i32>> {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM tasks WHERE parent_id IS ? AND id <> ? ORDER BY position, id"
+        )?;
+        let ids = stmt.query_map(params![parent_id, id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    fn renumber(conn: &Connection, ids: &[i32]) -> Result<()> {
+        for (index, id) in ids.iter().enumerate() {
+            conn.execute(
+                "UPDATE tasks SET position = ? WHERE id = ?",
+                params![index as i32, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_task_by_id(&self, id: i32) -> Result<Option<Task>> {
+        let task = self.conn.query_row(
+            &format!("SELECT {} FROM tasks WHERE id = ?", TASK_COLUMNS),
+            params![id],
+            Self::map_row,
+        ).optional()?;
+
+        match task {
+            Some(mut task) => {
+                task.tags = self.get_tags_by_task_id(task.id)?;
+                task.recurrence = self.get_recurrence(task.id)?;
+                Ok(Some(task))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Tasks matching every set field of `filter`, soonest due first; tasks
+    /// without a due date come last.
+    pub fn get_tasks(&self, filter: &TaskFilter) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE (?1 IS NULL OR status = ?1)
+               AND (?2 IS NULL OR due_date >= ?2)
+               AND (?3 IS NULL OR due_date <= ?3)
+               AND (?4 IS NULL OR document_id = ?4)
+               AND (?5 IS NULL OR id IN (SELECT task_id FROM task_tags WHERE tag_id = ?5))
+             ORDER BY due_date IS NULL, due_date, created_at",
+            TASK_COLUMNS
+        ))?;
+
+        let tasks = stmt.query_map(
+            params![filter.status.as_ref().map(TaskStatus::as_str), filter.due_from, filter.due_to, filter.document_id, filter.tag_id],
+            Self::map_row,
+        )?;
+
+        let mut tasks: Vec<Task> = tasks.collect::<Result<_>>()?;
+        for task in &mut tasks {
+            task.tags = self.get_tags_by_task_id(task.id)?;
+            task.recurrence = self.get_recurrence(task.id)?;
+        }
+        Ok(tasks)
+    }
+
+    pub fn document_exists(&self, document_id: i64) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM documents WHERE id = ?)",
+            params![document_id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_tags_by_task_id(&self, task_id: i32) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.title, t.color, t.icon
+             FROM tags t
+             JOIN task_tags tt ON tt.tag_id = t.id
+             WHERE tt.task_id = ?"
+        )?;
+
+        let tags = stmt.query_map(params![task_id], |row| {
+            Ok(Tag {
+                id: Some(row.get(0)?),
+                title: row.get(1)?,
+                color: row.get(2)?,
+                icon: row.get(3)?,
+            })
+        })?;
+
+        tags.collect()
+    }
+
+    pub fn add_tags_to_task(&mut self, task_id: i32, tags: Vec<Tag>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for tag in tags {
+            if let Some(tag_id) = tag.id {
+                tx.execute(
+                    "INSERT OR IGNORE INTO task_tags (task_id, tag_id) VALUES (?, ?)",
+                    params![task_id, tag_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_tags_from_task(&mut self, task_id: i32, tags: Vec<Tag>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for tag in tags {
+            if let Some(tag_id) = tag.id {
+                tx.execute(
+                    "DELETE FROM task_tags WHERE task_id = ? AND tag_id = ?",
+                    params![task_id, tag_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_recurrence(&self, task_id: i32) -> Result<Option<RecurrenceRule>> {
+        self.conn.query_row(
+            "SELECT frequency, interval, weekdays, starts_on, until, count
+             FROM task_recurrences WHERE task_id = ?",
+            params![task_id],
+            |row| {
+                let frequency: String = row.get(0)?;
+                let weekdays: Option<String> = row.get(2)?;
+                Ok(RecurrenceRule {
+                    frequency: RecurrenceFrequency::parse(&frequency).unwrap_or(RecurrenceFrequency::Daily),
+                    interval: row.get(1)?,
+                    weekdays: weekdays
+                        .as_deref()
+                        .unwrap_or_default()
+                        .split(',')
+                        .filter_map(DayOfWeek::from_str)
+                        .collect(),
+                    starts_on: row.get(3)?,
+                    until: row.get(4)?,
+                    count: row.get(5)?,
+                })
+            },
+        ).optional()
+    }
+
+    fn insert_recurrence(conn: &Connection, task_id: i64, rule: &RecurrenceRule) -> Result<usize> {
+        let weekdays = rule.weekdays.iter().map(|day| day.as_str()).collect::<Vec<_>>().join(",");
+        conn.execute(
+            "INSERT OR REPLACE INTO task_recurrences (task_id, frequency, interval, weekdays, starts_on, until, count)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                task_id,
+                rule.frequency.as_str(),
+                rule.interval,
+                if weekdays.is_empty() { None } else { Some(weekdays) },
+                rule.starts_on,
+                rule.until,
+                rule.count,
+            ],
+        )
+    }
+
+    /// Attaches `rule` to the task, replacing any previous one, or removes it.
+    pub fn set_recurrence(&mut self, task_id: i32, rule: Option<&RecurrenceRule>) -> Result<usize> {
+        match rule {
+            Some(rule) => Self::insert_recurrence(self.conn, task_id as i64, rule),
+            None => self.conn.execute("DELETE FROM task_recurrences WHERE task_id = ?", params![task_id]),
+        }
+    }
+
+    /// Saves a recurring task that was just completed and creates its next
+    /// occurrence due on `next_due_date`, moving the rule over to it. Returns
+    /// the ID of the new task.
+    pub fn complete_recurring_task(&mut self, task: &Task, next_due_date: &str) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        Self::write_task(&tx, task)?;
+
+        let next = Task {
+            id: 0,
+            title: task.title.clone(),
+            description: task.description.clone(),
+            status: TaskStatus::Todo,
+            created_at: Utc::now().to_rfc3339(),
+            due_date: Some(next_due_date.to_string()),
+            completed_at: None,
+            document_id: task.document_id,
+            parent_id: task.parent_id,
+            position: Self::next_position_in(&tx, task.parent_id)?,
+            tags: task.tags.clone(),
+            recurrence: None,
+        };
+        let next_id = Self::insert_task(&tx, &next)?;
+
+        tx.execute(
+            "UPDATE task_recurrences SET task_id = ? WHERE task_id = ?",
+            params![next_id, task.id],
+        )?;
+        tx.commit()?;
+        Ok(next_id)
+    }
+}
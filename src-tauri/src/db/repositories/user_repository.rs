@@ -1,4 +1,4 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use crate::db::models::{
     user::User,
     user_available_day::DayOfWeek,
@@ -62,6 +62,14 @@ impl<'a> UserRepository<'a> {
         Ok(user_id)
     }
 
+    pub fn get_active_user_id(&self) -> Result<Option<i32>> {
+        self.conn.query_row(
+            "SELECT id FROM users WHERE status = 'active' ORDER BY id LIMIT 1",
+            [],
+            |row| row.get(0)
+        ).optional()
+    }
+
     pub fn check_if_there_is_active_user_status(&mut self) -> Result<bool> {
         self.conn.query_row(
             "SELECT EXISTS(SELECT 1 FROM users WHERE status = 'active')",
@@ -69,4 +77,21 @@ impl<'a> UserRepository<'a> {
             |row| row.get(0)
         )
     }
+
+    pub fn get_available_days(&self, user_id: i32) -> Result<Vec<DayOfWeek>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT day_of_week FROM user_available_days WHERE user_id = ? ORDER BY id"
+        )?;
+
+        let days = stmt.query_map(params![user_id], |row| row.get::<_, String>(0))?;
+        let mut available_days = Vec::new();
+        for day in days {
+            if let Some(day) = DayOfWeek::from_str(&day?) {
+                if !available_days.contains(&day) {
+                    available_days.push(day);
+                }
+            }
+        }
+        Ok(available_days)
+    }
 }
@@ -1,4 +1,5 @@
 use rusqlite::{Connection, Result};
+use log::{info, warn};
 use std::sync::{Arc, Mutex};
 use std::fs;
 use std::path::PathBuf;
@@ -6,16 +7,63 @@ use std::io;
 
 pub mod db;
 pub mod commands;
+pub mod services;
 
 use db::run_migrations;
+use db::repositories::ReadingSessionRepository;
+use services::{
+    set_pdfium_library_dir, spawn_text_indexing, spawn_thumbnail_refresh, FocusTimer, ThumbnailService,
+};
+use tauri::Manager;
 use commands::{
     create_user_command, check_if_there_is_active_user_status_command,
     insert_new_book_command, add_tags_to_book_command, 
     remove_tags_from_book_command, create_tag_command,
-    get_all_tags_command};
+    get_all_tags_command, create_document_command,
+    get_document_by_id_command, list_all_documents_command,
+    update_document_command, delete_document_command,
+    search_documents_by_title_command, mark_document_as_accessed_command,
+    import_book_command, get_book_thumbnail_command,
+    list_book_chapters_command, get_chapter_text_command,
+    search_library_command, save_reading_progress_command,
+    get_reading_progress_command, get_recent_reading_command,
+    start_reading_session_command, heartbeat_reading_session_command,
+    stop_reading_session_command, get_reading_session_command,
+    get_reading_minutes_per_day_command, get_reading_minutes_per_book_command,
+    get_reading_minutes_per_tag_command, create_annotation_command,
+    update_annotation_command, delete_annotation_command,
+    get_annotation_command, list_book_annotations_command,
+    add_tags_to_annotation_command, remove_tags_from_annotation_command,
+    export_annotations_command, export_annotated_pdf_command, import_annotations_command,
+    create_bookmark_command, update_bookmark_command, delete_bookmark_command,
+    open_bookmark_command, list_book_bookmarks_command,
+    list_recent_bookmarks_command,
+    create_task_command, update_task_command, set_task_status_command, delete_task_command,
+    get_task_by_id_command, list_tasks_command, add_tags_to_task_command,
+    remove_tags_from_task_command, set_task_recurrence_command,
+    clear_task_recurrence_command, list_upcoming_task_occurrences_command,
+    move_task_command, get_document_task_tree_command,
+    spawn_daily_rebalance, preview_reading_plan_command, create_reading_plan_command,
+    rebalance_reading_plan_command, list_reading_plans_command, delete_reading_plan_command,
+    create_deck_command, update_deck_command, delete_deck_command, list_decks_command,
+    create_card_command, create_cloze_card_command, list_annotation_cards_command,
+    update_card_command, delete_card_command, get_due_cards_command,
+    grade_card_command, get_deck_statistics_command,
+    import_anki_package_command, export_anki_package_command,
+    create_quiz_command, get_quiz_command, list_quizzes_command, delete_quiz_command,
+    start_quiz_attempt_command, answer_quiz_question_command, finish_quiz_attempt_command,
+    get_quiz_attempt_command, list_quiz_attempts_command, get_weakest_topics_command,
+    start_focus_timer_command, get_focus_timer_command, pause_focus_timer_command,
+    resume_focus_timer_command, skip_focus_phase_command, stop_focus_timer_command,
+    list_pomodoros_command};
 
 pub struct AppState {
     pub db_conn: Arc<Mutex<Connection>>,
+    pub library_dir: PathBuf,
+    pub thumbnail_dir: PathBuf,
+    /// Images and sounds referenced by flashcards.
+    pub media_dir: PathBuf,
+    pub focus_timer: FocusTimer,
 }
 
 impl AppState {
@@ -26,22 +74,60 @@ impl AppState {
             fs::create_dir_all(parent).map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
         }
 
+        let app_dir = db_path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        let library_dir = app_dir.join("library");
+        fs::create_dir_all(&library_dir)?;
+
+        let thumbnail_dir = app_dir.join("thumbnails");
+        fs::create_dir_all(&thumbnail_dir)?;
+
+        let media_dir = app_dir.join("media");
+        fs::create_dir_all(&media_dir)?;
+
         if !db_path.exists() {
             println!("Database at: {:?}", db_path);
         }
 
-        let conn = Connection::open(db_path)?;
+        let mut conn = Connection::open(db_path)?;
 
-        run_migrations(&conn)?;
+        let applied = run_migrations(&mut conn)?;
+        if applied.is_empty() {
+            info!("Database schema is up to date");
+        }
+        for migration in &applied {
+            info!("Applied migration v{}: {}", migration.version, migration.name);
+        }
+
+        // Any session still open at launch belongs to a run that was killed
+        // before it could stop it; close it at its last heartbeat.
+        let closed = ReadingSessionRepository::new(&mut conn).close_stale_sessions(None)?;
+        if closed > 0 {
+            info!("Closed {} stale reading session(s)", closed);
+        }
+
+        let db_conn = Arc::new(Mutex::new(conn));
+        let focus_timer = FocusTimer::new(Arc::clone(&db_conn));
 
         Ok(Self {
-            db_conn: Arc::new(Mutex::new(conn)),
+            db_conn,
+            library_dir,
+            thumbnail_dir,
+            media_dir,
+            focus_timer,
         })
     }
 
     pub fn db_conn(&self) -> Arc<Mutex<Connection>> {
         Arc::clone(&self.db_conn)
     }
+
+    pub fn thumbnails(&self) -> ThumbnailService {
+        ThumbnailService::new(&self.thumbnail_dir)
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -54,7 +140,22 @@ pub fn run() {
 
     let app_state = AppState::new(&db_path_str).expect("Falha ao inicializar o AppState");
 
+    let db_conn = app_state.db_conn();
+    let thumbnails = app_state.thumbnails();
+
     tauri::Builder::default()
+        .setup(move |app| {
+            // pdfium ships as a bundled resource; background work that renders
+            // PDFs starts only once it can be found.
+            match app.path().resource_dir() {
+                Ok(dir) => set_pdfium_library_dir(dir.join("pdfium")),
+                Err(err) => warn!("Could not resolve the resource folder: {}", err),
+            }
+            spawn_thumbnail_refresh(Arc::clone(&db_conn), thumbnails);
+            spawn_text_indexing(Arc::clone(&db_conn));
+            spawn_daily_rebalance(db_conn);
+            Ok(())
+        })
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             create_user_command,
@@ -63,7 +164,94 @@ pub fn run() {
             add_tags_to_book_command,
             remove_tags_from_book_command,
             get_all_tags_command,
-            create_tag_command
+            create_tag_command,
+            create_document_command,
+            get_document_by_id_command,
+            list_all_documents_command,
+            update_document_command,
+            delete_document_command,
+            search_documents_by_title_command,
+            mark_document_as_accessed_command,
+            import_book_command,
+            get_book_thumbnail_command,
+            list_book_chapters_command,
+            get_chapter_text_command,
+            search_library_command,
+            save_reading_progress_command,
+            get_reading_progress_command,
+            get_recent_reading_command,
+            start_reading_session_command,
+            heartbeat_reading_session_command,
+            stop_reading_session_command,
+            get_reading_session_command,
+            get_reading_minutes_per_day_command,
+            get_reading_minutes_per_book_command,
+            get_reading_minutes_per_tag_command,
+            create_annotation_command,
+            update_annotation_command,
+            delete_annotation_command,
+            get_annotation_command,
+            list_book_annotations_command,
+            add_tags_to_annotation_command,
+            remove_tags_from_annotation_command,
+            export_annotations_command,
+            export_annotated_pdf_command,
+            import_annotations_command,
+            create_bookmark_command,
+            update_bookmark_command,
+            delete_bookmark_command,
+            open_bookmark_command,
+            list_book_bookmarks_command,
+            list_recent_bookmarks_command,
+            create_task_command,
+            update_task_command,
+            set_task_status_command,
+            delete_task_command,
+            get_task_by_id_command,
+            list_tasks_command,
+            add_tags_to_task_command,
+            remove_tags_from_task_command,
+            set_task_recurrence_command,
+            clear_task_recurrence_command,
+            list_upcoming_task_occurrences_command,
+            move_task_command,
+            get_document_task_tree_command,
+            preview_reading_plan_command,
+            create_reading_plan_command,
+            rebalance_reading_plan_command,
+            list_reading_plans_command,
+            delete_reading_plan_command,
+            create_deck_command,
+            update_deck_command,
+            delete_deck_command,
+            list_decks_command,
+            create_card_command,
+            create_cloze_card_command,
+            list_annotation_cards_command,
+            update_card_command,
+            delete_card_command,
+            get_due_cards_command,
+            grade_card_command,
+            get_deck_statistics_command,
+            import_anki_package_command,
+            export_anki_package_command,
+            create_quiz_command,
+            get_quiz_command,
+            list_quizzes_command,
+            delete_quiz_command,
+            start_quiz_attempt_command,
+            answer_quiz_question_command,
+            finish_quiz_attempt_command,
+            get_quiz_attempt_command,
+            list_quiz_attempts_command,
+            get_weakest_topics_command,
+            start_focus_timer_command,
+            get_focus_timer_command,
+            pause_focus_timer_command,
+            resume_focus_timer_command,
+            skip_focus_phase_command,
+            stop_focus_timer_command,
+            list_pomodoros_command
         ])
         .run(tauri::generate_context!())
         .expect("Erro ao rodar a aplicação Tauri");
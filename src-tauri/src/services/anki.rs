@@ -0,0 +1,719 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, Connection, OpenFlags};
+use serde_json::{json, Map, Value};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::services::cloze;
+
+/// Anki card types, as stored in `cards.type`.
+pub const CARD_TYPE_NEW: i64 = 0;
+pub const CARD_TYPE_REVIEW: i64 = 2;
+
+/// Anki card queues, as stored in `cards.queue`. Negative queues are
+/// suspended or buried cards.
+pub const QUEUE_NEW: i64 = 0;
+const QUEUE_LEARNING: i64 = 1;
+pub const QUEUE_REVIEW: i64 = 2;
+const QUEUE_DAY_LEARNING: i64 = 3;
+
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+#[derive(Debug, Error)]
+pub enum AnkiError {
+    #[error("Malformed Anki package: {0}")]
+    Malformed(String),
+
+    #[error("Unsupported Anki package: {0}")]
+    Unsupported(String),
+
+    #[error("Collection error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl From<zip::result::ZipError> for AnkiError {
+    fn from(err: zip::result::ZipError) -> Self {
+        AnkiError::Malformed(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AnkiError {
+    fn from(err: serde_json::Error) -> Self {
+        AnkiError::Malformed(err.to_string())
+    }
+}
+
+/// The parts of an Anki collection Study Studio reads and writes. Ids are
+/// Anki's own.
+#[derive(Debug, Clone, Default)]
+pub struct AnkiPackage {
+    /// When the collection was created; review cards count their due day from it.
+    pub created: DateTime<Utc>,
+    pub models: Vec<AnkiModel>,
+    pub decks: Vec<AnkiDeck>,
+    pub notes: Vec<AnkiNote>,
+    pub cards: Vec<AnkiCard>,
+    pub reviews: Vec<AnkiReview>,
+    pub media: Vec<AnkiMedia>,
+}
+
+/// A note type. Templates are in `ord` order.
+#[derive(Debug, Clone)]
+pub struct AnkiModel {
+    pub id: i64,
+    pub name: String,
+    pub is_cloze: bool,
+    pub fields: Vec<String>,
+    pub templates: Vec<AnkiTemplate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiTemplate {
+    pub name: String,
+    pub question: String,
+    pub answer: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiDeck {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiNote {
+    pub id: i64,
+    pub guid: String,
+    pub model_id: i64,
+    pub tags: Vec<String>,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiCard {
+    pub id: i64,
+    pub note_id: i64,
+    pub deck_id: i64,
+    pub ord: u32,
+    pub card_type: i64,
+    pub queue: i64,
+    /// Day number for review cards, a timestamp for learning cards and a
+    /// position for new ones.
+    pub due: i64,
+    /// Days; negative values are learning steps in seconds.
+    pub interval: i64,
+    /// Ease factor in permille.
+    pub factor: i64,
+    pub reps: u32,
+    pub lapses: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiReview {
+    pub card_id: i64,
+    pub reviewed_at: DateTime<Utc>,
+    /// 1 (again) to 4 (easy); 0 marks a manual reschedule.
+    pub ease: u32,
+    pub interval: i64,
+    pub last_interval: i64,
+    pub factor: i64,
+    pub kind: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiMedia {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl AnkiModel {
+    pub fn basic(id: i64) -> Self {
+        Self {
+            id,
+            name: "Basic (Study Studio)".to_string(),
+            is_cloze: false,
+            fields: vec!["Front".to_string(), "Back".to_string()],
+            templates: vec![AnkiTemplate {
+                name: "Card 1".to_string(),
+                question: "{{Front}}".to_string(),
+                answer: "{{FrontSide}}\n\n<hr id=answer>\n\n{{Back}}".to_string(),
+            }],
+        }
+    }
+
+    pub fn cloze(id: i64) -> Self {
+        Self {
+            id,
+            name: "Cloze (Study Studio)".to_string(),
+            is_cloze: true,
+            fields: vec!["Text".to_string(), "Back Extra".to_string()],
+            templates: vec![AnkiTemplate {
+                name: "Cloze".to_string(),
+                question: "{{cloze:Text}}".to_string(),
+                answer: "{{cloze:Text}}<br>\n{{Back Extra}}".to_string(),
+            }],
+        }
+    }
+
+    /// Question and answer of card `ord` of `note`. The answer leaves out the
+    /// repeated question Anki templates usually start with.
+    pub fn render(&self, note: &AnkiNote, ord: u32) -> (String, String) {
+        if self.is_cloze {
+            let text = self.field(note, "Text").or_else(|| note.fields.first().map(String::as_str)).unwrap_or("");
+            let (front, mut back) = cloze::render_cloze_number(text, ord + 1);
+            for extra in note.fields.iter().skip(1).filter(|field| !field.trim().is_empty()) {
+                back.push_str("<br>");
+                back.push_str(extra);
+            }
+            return (front, back);
+        }
+
+        let mut fields: HashMap<&str, &str> = self.fields.iter()
+            .map(String::as_str)
+            .zip(note.fields.iter().map(String::as_str))
+            .collect();
+        let Some(template) = self.templates.get(ord as usize).or_else(|| self.templates.first()) else {
+            return (
+                note.fields.first().cloned().unwrap_or_default(),
+                note.fields.get(1).cloned().unwrap_or_default(),
+            );
+        };
+
+        let front = render_template(&template.question, &fields);
+        fields.insert("FrontSide", "");
+        let back = render_template(&template.answer, &fields);
+        (front.trim().to_string(), strip_answer_divider(&back).to_string())
+    }
+
+    fn field<'n>(&self, note: &'n AnkiNote, name: &str) -> Option<&'n str> {
+        let index = self.fields.iter().position(|field| field == name)?;
+        note.fields.get(index).map(String::as_str)
+    }
+}
+
+impl AnkiCard {
+    /// Local day the card is due, `today` for new cards.
+    pub fn due_date(&self, created: DateTime<Utc>, today: NaiveDate) -> NaiveDate {
+        let created_day = created.with_timezone(&Local).date_naive();
+        match self.queue {
+            QUEUE_REVIEW | QUEUE_DAY_LEARNING => created_day + Duration::days(self.due),
+            QUEUE_LEARNING => Local.timestamp_opt(self.due, 0)
+                .single()
+                .map(|due| due.date_naive())
+                .unwrap_or(today),
+            // Suspended or buried cards keep their due day.
+            _ if self.card_type == CARD_TYPE_REVIEW => created_day + Duration::days(self.due),
+            _ => today,
+        }
+    }
+}
+
+/// Renders a card template against note fields. Supports field references,
+/// filters (`{{text:Field}}`; `type:` fields are dropped) and conditional
+/// sections (`{{#Field}}...{{/Field}}`, `{{^Field}}...{{/Field}}`).
+pub fn render_template(template: &str, fields: &HashMap<&str, &str>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find("}}").map(|close| open + close) else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = rest[open + 2..close].trim();
+        rest = &rest[close + 2..];
+
+        if let Some(section) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+            let section = section.trim();
+            let end_tag = format!("{{{{/{}}}}}", section);
+            let (inner, after) = match rest.find(&end_tag) {
+                Some(end) => (&rest[..end], &rest[end + end_tag.len()..]),
+                None => (rest, ""),
+            };
+            let present = fields.get(section).is_some_and(|value| !value.trim().is_empty());
+            if present != tag.starts_with('^') {
+                out.push_str(&render_template(inner, fields));
+            }
+            rest = after;
+        } else if !tag.starts_with('/') {
+            let (filters, name) = match tag.rsplit_once(':') {
+                Some((filters, name)) => (filters, name.trim()),
+                None => ("", tag),
+            };
+            if filters.split(':').any(|filter| filter.trim() == "type") {
+                continue;
+            }
+            if let Some(value) = fields.get(name) {
+                out.push_str(value);
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn strip_answer_divider(back: &str) -> &str {
+    let trimmed = back.trim();
+    ["<hr id=answer>", "<hr id=\"answer\">", "<hr id='answer'>"]
+        .iter()
+        .find_map(|divider| trimmed.strip_prefix(divider))
+        .map(str::trim)
+        .unwrap_or(trimmed)
+}
+
+fn strip_html(text: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Anki's duplicate check: the first 32 bits of the SHA-1 of the sort field.
+fn field_checksum(field: &str) -> i64 {
+    let digest = Sha1::digest(strip_html(field).trim().as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+/// A collection extracted to a temporary file, removed when dropped.
+struct TempCollection {
+    path: PathBuf,
+}
+
+impl TempCollection {
+    fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_nanos())
+            .unwrap_or_default();
+        Self {
+            path: std::env::temp_dir().join(format!("study-studio-anki-{}-{}.db", std::process::id(), nanos)),
+        }
+    }
+}
+
+impl Drop for TempCollection {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn read_entry_bytes(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, AnkiError> {
+    let mut entry = archive.by_name(name)?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads an `.apkg` file. Only the schema 11 collection (`collection.anki2`
+/// or `collection.anki21`) is understood; packages written only in the
+/// compressed format of newer Anki versions are rejected.
+pub fn read_package(path: &Path) -> Result<AnkiPackage, AnkiError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+    let has = |name: &str| names.iter().any(|entry| entry == name);
+
+    let collection_name = if has("collection.anki21") {
+        "collection.anki21"
+    } else if has("collection.anki21b") {
+        return Err(AnkiError::Unsupported(
+            "this package uses the format of Anki 2.1.50 and later; export it again with \
+             \"Support older Anki versions\" enabled"
+                .to_string(),
+        ));
+    } else if has("collection.anki2") {
+        "collection.anki2"
+    } else {
+        return Err(AnkiError::Malformed("no collection found".to_string()));
+    };
+
+    let collection = TempCollection::new();
+    fs::write(&collection.path, read_entry_bytes(&mut archive, collection_name)?)?;
+    let mut package = {
+        let conn = Connection::open_with_flags(&collection.path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        read_collection(&conn)?
+    };
+
+    if has("media") {
+        let index: HashMap<String, String> = serde_json::from_slice(&read_entry_bytes(&mut archive, "media")?)?;
+        for (entry, name) in index {
+            if has(&entry) {
+                package.media.push(AnkiMedia { name, data: read_entry_bytes(&mut archive, &entry)? });
+            }
+        }
+        package.media.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    Ok(package)
+}
+
+fn read_collection(conn: &Connection) -> Result<AnkiPackage, AnkiError> {
+    let (created, models, decks): (i64, String, String) = conn.query_row(
+        "SELECT crt, models, decks FROM col",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    if models.trim().is_empty() {
+        return Err(AnkiError::Unsupported("the collection keeps note types outside the col table".to_string()));
+    }
+
+    let mut package = AnkiPackage {
+        created: Utc.timestamp_opt(created, 0).single().unwrap_or_default(),
+        models: parse_models(&serde_json::from_str(&models)?),
+        decks: parse_decks(&serde_json::from_str(&decks)?),
+        ..AnkiPackage::default()
+    };
+
+    let mut stmt = conn.prepare("SELECT id, guid, mid, tags, flds FROM notes ORDER BY id")?;
+    package.notes = stmt.query_map([], |row| {
+        let tags: String = row.get(3)?;
+        let fields: String = row.get(4)?;
+        Ok(AnkiNote {
+            id: row.get(0)?,
+            guid: row.get(1)?,
+            model_id: row.get(2)?,
+            tags: tags.split_whitespace().map(String::from).collect(),
+            fields: fields.split(FIELD_SEPARATOR).map(String::from).collect(),
+        })
+    })?.collect::<Result<_, _>>()?;
+
+    // Cards sitting in a filtered deck are read from their home deck.
+    let mut stmt = conn.prepare(
+        "SELECT id, nid, CASE WHEN odid != 0 THEN odid ELSE did END, ord, type, queue,
+                CASE WHEN odid != 0 AND odue != 0 THEN odue ELSE due END, ivl, factor, reps, lapses
+         FROM cards ORDER BY id"
+    )?;
+    package.cards = stmt.query_map([], |row| {
+        Ok(AnkiCard {
+            id: row.get(0)?,
+            note_id: row.get(1)?,
+            deck_id: row.get(2)?,
+            ord: row.get(3)?,
+            card_type: row.get(4)?,
+            queue: row.get(5)?,
+            due: row.get(6)?,
+            interval: row.get(7)?,
+            factor: row.get(8)?,
+            reps: row.get(9)?,
+            lapses: row.get(10)?,
+        })
+    })?.collect::<Result<_, _>>()?;
+
+    let mut stmt = conn.prepare("SELECT id, cid, ease, ivl, lastIvl, factor, type FROM revlog ORDER BY id")?;
+    package.reviews = stmt.query_map([], |row| {
+        let millis: i64 = row.get(0)?;
+        Ok(AnkiReview {
+            card_id: row.get(1)?,
+            reviewed_at: Utc.timestamp_millis_opt(millis).single().unwrap_or_default(),
+            ease: row.get(2)?,
+            interval: row.get(3)?,
+            last_interval: row.get(4)?,
+            factor: row.get(5)?,
+            kind: row.get(6)?,
+        })
+    })?.collect::<Result<_, _>>()?;
+
+    Ok(package)
+}
+
+fn sorted_by_ord(items: Option<&Value>) -> Vec<&Value> {
+    let mut items: Vec<&Value> = items.and_then(Value::as_array).map(|items| items.iter().collect()).unwrap_or_default();
+    items.sort_by_key(|item| item["ord"].as_i64().unwrap_or_default());
+    items
+}
+
+fn parse_models(models: &Value) -> Vec<AnkiModel> {
+    let Some(models) = models.as_object() else {
+        return Vec::new();
+    };
+
+    models.iter().map(|(key, model)| AnkiModel {
+        id: model["id"].as_i64().or_else(|| key.parse().ok()).unwrap_or_default(),
+        name: model["name"].as_str().unwrap_or_default().to_string(),
+        is_cloze: model["type"].as_i64() == Some(1),
+        fields: sorted_by_ord(model.get("flds"))
+            .into_iter()
+            .map(|field| field["name"].as_str().unwrap_or_default().to_string())
+            .collect(),
+        templates: sorted_by_ord(model.get("tmpls"))
+            .into_iter()
+            .map(|template| AnkiTemplate {
+                name: template["name"].as_str().unwrap_or_default().to_string(),
+                question: template["qfmt"].as_str().unwrap_or_default().to_string(),
+                answer: template["afmt"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect(),
+    }).collect()
+}
+
+fn parse_decks(decks: &Value) -> Vec<AnkiDeck> {
+    let Some(decks) = decks.as_object() else {
+        return Vec::new();
+    };
+
+    decks.iter().map(|(key, deck)| AnkiDeck {
+        id: deck["id"].as_i64().or_else(|| key.parse().ok()).unwrap_or_default(),
+        name: deck["name"].as_str().unwrap_or_default().to_string(),
+    }).collect()
+}
+
+const COLLECTION_SCHEMA: &str = r#"
+    CREATE TABLE col (
+        id INTEGER PRIMARY KEY, crt INTEGER NOT NULL, mod INTEGER NOT NULL, scm INTEGER NOT NULL,
+        ver INTEGER NOT NULL, dty INTEGER NOT NULL, usn INTEGER NOT NULL, ls INTEGER NOT NULL,
+        conf TEXT NOT NULL, models TEXT NOT NULL, decks TEXT NOT NULL, dconf TEXT NOT NULL, tags TEXT NOT NULL
+    );
+    CREATE TABLE notes (
+        id INTEGER PRIMARY KEY, guid TEXT NOT NULL, mid INTEGER NOT NULL, mod INTEGER NOT NULL,
+        usn INTEGER NOT NULL, tags TEXT NOT NULL, flds TEXT NOT NULL, sfld INTEGER NOT NULL,
+        csum INTEGER NOT NULL, flags INTEGER NOT NULL, data TEXT NOT NULL
+    );
+    CREATE TABLE cards (
+        id INTEGER PRIMARY KEY, nid INTEGER NOT NULL, did INTEGER NOT NULL, ord INTEGER NOT NULL,
+        mod INTEGER NOT NULL, usn INTEGER NOT NULL, type INTEGER NOT NULL, queue INTEGER NOT NULL,
+        due INTEGER NOT NULL, ivl INTEGER NOT NULL, factor INTEGER NOT NULL, reps INTEGER NOT NULL,
+        lapses INTEGER NOT NULL, left INTEGER NOT NULL, odue INTEGER NOT NULL, odid INTEGER NOT NULL,
+        flags INTEGER NOT NULL, data TEXT NOT NULL
+    );
+    CREATE TABLE revlog (
+        id INTEGER PRIMARY KEY, cid INTEGER NOT NULL, usn INTEGER NOT NULL, ease INTEGER NOT NULL,
+        ivl INTEGER NOT NULL, lastIvl INTEGER NOT NULL, factor INTEGER NOT NULL, time INTEGER NOT NULL,
+        type INTEGER NOT NULL
+    );
+    CREATE TABLE graves (usn INTEGER NOT NULL, oid INTEGER NOT NULL, type INTEGER NOT NULL);
+    CREATE INDEX ix_notes_usn ON notes (usn);
+    CREATE INDEX ix_cards_usn ON cards (usn);
+    CREATE INDEX ix_revlog_usn ON revlog (usn);
+    CREATE INDEX ix_cards_nid ON cards (nid);
+    CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid ON revlog (cid);
+    CREATE INDEX ix_notes_csum ON notes (csum);
+"#;
+
+fn models_json(models: &[AnkiModel], modified: i64) -> Value {
+    let models: Map<String, Value> = models.iter().map(|model| {
+        let fields: Vec<Value> = model.fields.iter().enumerate().map(|(ord, name)| json!({
+            "name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": [],
+        })).collect();
+        let templates: Vec<Value> = model.templates.iter().enumerate().map(|(ord, template)| json!({
+            "name": template.name, "ord": ord, "qfmt": template.question, "afmt": template.answer,
+            "bqfmt": "", "bafmt": "", "did": null, "bfont": "", "bsize": 0,
+        })).collect();
+        let model_json = json!({
+            "id": model.id,
+            "name": model.name,
+            "type": if model.is_cloze { 1 } else { 0 },
+            "mod": modified,
+            "usn": -1,
+            "sortf": 0,
+            "did": 1,
+            "tmpls": templates,
+            "flds": fields,
+            "css": ".card {\n font-family: arial;\n font-size: 20px;\n text-align: center;\n color: black;\n background-color: white;\n}\n",
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "latexsvg": false,
+            "req": [[0, "any", [0]]],
+            "tags": [],
+            "vers": [],
+        });
+        (model.id.to_string(), model_json)
+    }).collect();
+    Value::Object(models)
+}
+
+fn decks_json(decks: &[AnkiDeck], modified: i64) -> Value {
+    let default = AnkiDeck { id: 1, name: "Default".to_string() };
+    let decks: Map<String, Value> = std::iter::once(&default).chain(decks).map(|deck| {
+        (deck.id.to_string(), json!({
+            "id": deck.id,
+            "name": deck.name,
+            "mod": modified,
+            "usn": -1,
+            "lrnToday": [0, 0],
+            "revToday": [0, 0],
+            "newToday": [0, 0],
+            "timeToday": [0, 0],
+            "collapsed": false,
+            "browserCollapsed": false,
+            "desc": "",
+            "dyn": 0,
+            "conf": 1,
+            "extendNew": 0,
+            "extendRev": 0,
+        }))
+    }).collect();
+    Value::Object(decks)
+}
+
+fn deck_config_json() -> Value {
+    json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true,
+            "timer": 0, "replayq": true, "dyn": false,
+            "new": { "delays": [1.0, 10.0], "ints": [1, 4, 0], "initialFactor": 2500, "order": 1, "perDay": 20, "bury": false },
+            "rev": { "perDay": 200, "ease4": 1.3, "ivlFct": 1.0, "maxIvl": 36500, "bury": false, "hardFactor": 1.2 },
+            "lapse": { "delays": [10.0], "mult": 0.0, "minInt": 1, "leechFails": 8, "leechAction": 1 },
+        }
+    })
+}
+
+/// Writes `package` as an `.apkg` file Anki can import, with a schema 11
+/// collection so older Anki versions can read it too.
+pub fn write_package(path: &Path, package: &AnkiPackage) -> Result<(), AnkiError> {
+    let collection = TempCollection::new();
+    {
+        let conn = Connection::open(&collection.path)?;
+        write_collection(&conn, package)?;
+    }
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(&fs::read(&collection.path)?)?;
+
+    let index: Map<String, Value> = package.media.iter()
+        .enumerate()
+        .map(|(index, media)| (index.to_string(), Value::String(media.name.clone())))
+        .collect();
+    zip.start_file("media", options)?;
+    zip.write_all(serde_json::to_string(&index)?.as_bytes())?;
+    for (index, media) in package.media.iter().enumerate() {
+        zip.start_file(index.to_string(), options)?;
+        zip.write_all(&media.data)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_collection(conn: &Connection, package: &AnkiPackage) -> Result<(), AnkiError> {
+    let now = Utc::now();
+    let modified = now.timestamp();
+    conn.execute_batch(COLLECTION_SCHEMA)?;
+
+    let conf = json!({
+        "activeDecks": [1], "curDeck": 1, "newSpread": 0, "collapseTime": 1200, "timeLim": 0,
+        "estTimes": true, "dueCounts": true, "curModel": null, "nextPos": package.cards.len() + 1,
+        "sortType": "noteFld", "sortBackwards": false, "addToCur": true,
+    });
+    conn.execute(
+        "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags)
+         VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')",
+        params![
+            package.created.timestamp(),
+            now.timestamp_millis(),
+            now.timestamp_millis(),
+            conf.to_string(),
+            models_json(&package.models, modified).to_string(),
+            decks_json(&package.decks, modified).to_string(),
+            deck_config_json().to_string(),
+        ],
+    )?;
+
+    for note in &package.notes {
+        let sort_field = note.fields.first().map(String::as_str).unwrap_or("");
+        let tags = if note.tags.is_empty() { String::new() } else { format!(" {} ", note.tags.join(" ")) };
+        conn.execute(
+            "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data)
+             VALUES (?, ?, ?, ?, -1, ?, ?, ?, ?, 0, '')",
+            params![
+                note.id,
+                note.guid,
+                note.model_id,
+                modified,
+                tags,
+                note.fields.join(&FIELD_SEPARATOR.to_string()),
+                strip_html(sort_field),
+                field_checksum(sort_field),
+            ],
+        )?;
+    }
+
+    for card in &package.cards {
+        conn.execute(
+            "INSERT INTO cards (
+                id, nid, did, ord, mod, usn, type, queue, due, ivl, factor, reps, lapses, left, odue, odid, flags, data
+             )
+             VALUES (?, ?, ?, ?, ?, -1, ?, ?, ?, ?, ?, ?, ?, 0, 0, 0, 0, '')",
+            params![
+                card.id,
+                card.note_id,
+                card.deck_id,
+                card.ord,
+                modified,
+                card.card_type,
+                card.queue,
+                card.due,
+                card.interval,
+                card.factor,
+                card.reps,
+                card.lapses,
+            ],
+        )?;
+    }
+
+    for review in &package.reviews {
+        conn.execute(
+            "INSERT INTO revlog (id, cid, usn, ease, ivl, lastIvl, factor, time, type)
+             VALUES (?, ?, -1, ?, ?, ?, ?, 0, ?)",
+            params![
+                review.reviewed_at.timestamp_millis(),
+                review.card_id,
+                review.ease,
+                review.interval,
+                review.last_interval,
+                review.factor,
+                review.kind,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// File names a field refers to through `<img src="...">` or `[sound:...]`.
+pub fn media_references(field: &str) -> Vec<String> {
+    let mut names = Vec::new();
+
+    let mut rest = field;
+    while let Some(start) = rest.find("src=") {
+        rest = &rest[start + 4..];
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let name = match quote {
+            Some(quote) => rest[1..].split(quote).next().unwrap_or(""),
+            None => rest.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or(""),
+        };
+        if !name.is_empty() {
+            names.push(name.to_string());
+        }
+    }
+
+    let mut rest = field;
+    while let Some(start) = rest.find("[sound:") {
+        rest = &rest[start + 7..];
+        if let Some(end) = rest.find(']') {
+            names.push(rest[..end].to_string());
+        }
+    }
+
+    names
+}
@@ -0,0 +1,214 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use crate::db::models::{annotation::{Annotation, AnnotationKind}, chapter::Chapter, tag::Tag};
+
+/// Bumped whenever the JSON layout changes in a way older readers can't load.
+pub const ANNOTATION_EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Obsidian,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            "obsidian" => Some(Self::Obsidian),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            Self::Markdown | Self::Obsidian => "md",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Everything needed to write one book's annotations. This is also the JSON
+/// export layout, so a file written by `render_json` loads back with `parse_json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationExport {
+    pub format_version: u32,
+    pub book_title: String,
+    pub book_author: Option<String>,
+    /// Hash of the stored file, used to find the book again on import.
+    pub document_hash: Option<String>,
+    pub book_tags: Vec<Tag>,
+    pub annotations: Vec<Annotation>,
+}
+
+pub fn render(export: &AnnotationExport, chapters: &[Chapter], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(export, chapters)),
+        ExportFormat::Obsidian => Ok(render_obsidian(export, chapters)),
+        ExportFormat::Json => render_json(export),
+    }
+}
+
+pub fn render_json(export: &AnnotationExport) -> Result<String, String> {
+    serde_json::to_string_pretty(export).map_err(|err| err.to_string())
+}
+
+pub fn parse_json(contents: &str) -> Result<AnnotationExport, String> {
+    let export: AnnotationExport = serde_json::from_str(contents).map_err(|err| err.to_string())?;
+    if export.format_version > ANNOTATION_EXPORT_VERSION {
+        return Err(format!(
+            "Export format version {} is newer than the supported version {}",
+            export.format_version, ANNOTATION_EXPORT_VERSION
+        ));
+    }
+    Ok(export)
+}
+
+pub fn render_markdown(export: &AnnotationExport, chapters: &[Chapter]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}", export.book_title);
+    if let Some(author) = export.book_author.as_deref().filter(|a| !a.is_empty()) {
+        let _ = writeln!(out, "\n*{}*", author);
+    }
+    write_annotations(&mut out, export, chapters, false);
+    out
+}
+
+/// Markdown with YAML front matter and a block id per annotation, so notes
+/// can be linked from elsewhere in an Obsidian vault.
+pub fn render_obsidian(export: &AnnotationExport, chapters: &[Chapter]) -> String {
+    let mut out = String::from("---\n");
+    let _ = writeln!(out, "title: {}", yaml_string(&export.book_title));
+    if let Some(author) = export.book_author.as_deref().filter(|a| !a.is_empty()) {
+        let _ = writeln!(out, "author: {}", yaml_string(author));
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    let all_tags = export.book_tags.iter()
+        .chain(export.annotations.iter().flat_map(|a| a.tags.iter().flatten()));
+    for tag in all_tags {
+        let tag = obsidian_tag(&tag.title);
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if tags.is_empty() {
+        out.push_str("tags: []\n");
+    } else {
+        out.push_str("tags:\n");
+        for tag in &tags {
+            let _ = writeln!(out, "  - {}", yaml_string(tag));
+        }
+    }
+    let _ = writeln!(out, "source: study-studio");
+    let _ = writeln!(out, "exported_at: {}", yaml_string(&Utc::now().to_rfc3339()));
+    out.push_str("---\n\n");
+
+    let _ = writeln!(out, "# {}", export.book_title);
+    write_annotations(&mut out, export, chapters, true);
+    out
+}
+
+/// Writes the annotations under one heading per chapter (EPUB) or page (PDF),
+/// keeping the order they were given in.
+fn write_annotations(out: &mut String, export: &AnnotationExport, chapters: &[Chapter], block_ids: bool) {
+    let mut current_heading: Option<String> = None;
+
+    for annotation in &export.annotations {
+        let heading = section_heading(annotation, chapters);
+        if current_heading.as_ref() != Some(&heading) {
+            let _ = writeln!(out, "\n## {}", heading);
+            current_heading = Some(heading);
+        }
+
+        out.push('\n');
+        let text = annotation.selected_text.as_deref().map(str::trim).unwrap_or("");
+        if annotation.kind == AnnotationKind::Note || text.is_empty() {
+            let _ = write!(out, "**Note** {}", page_reference(annotation));
+        } else {
+            for line in text.lines() {
+                let _ = writeln!(out, "> {}", line.trim_end());
+            }
+            let _ = write!(out, "\n{}", page_reference(annotation));
+        }
+        if block_ids {
+            if let Some(id) = annotation.id {
+                let _ = write!(out, " ^annotation-{}", id);
+            }
+        }
+        out.push('\n');
+
+        if let Some(note) = annotation.note.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+            let _ = writeln!(out, "\n{}", note);
+        }
+
+        let tags: Vec<String> = annotation.tags.iter().flatten()
+            .map(|tag| format!("#{}", obsidian_tag(&tag.title)))
+            .filter(|tag| tag.len() > 1)
+            .collect();
+        if !tags.is_empty() {
+            let _ = writeln!(out, "\n{}", tags.join(" "));
+        }
+    }
+
+    if export.annotations.is_empty() {
+        out.push_str("\n_No annotations._\n");
+    }
+}
+
+fn section_heading(annotation: &Annotation, chapters: &[Chapter]) -> String {
+    if let Some(index) = annotation.chapter_index {
+        return chapters.iter()
+            .find(|chapter| chapter.chapter_index == index)
+            .and_then(|chapter| chapter.title.clone())
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or_else(|| format!("Chapter {}", index + 1));
+    }
+    match annotation.page_number {
+        Some(page) => format!("Page {}", page),
+        None => "Other".to_string(),
+    }
+}
+
+fn page_reference(annotation: &Annotation) -> String {
+    match (annotation.page_number, annotation.chapter_index) {
+        (Some(page), _) => format!("(p. {})", page),
+        (None, Some(index)) => format!("(ch. {})", index + 1),
+        (None, None) => String::new(),
+    }
+}
+
+/// Obsidian tags can't contain spaces, so they become dashes.
+fn obsidian_tag(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join("-").trim_start_matches('#').to_string()
+}
+
+fn yaml_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+/// File name for a book's export, with characters that are invalid on
+/// common file systems replaced.
+pub fn export_file_name(title: &str, format: ExportFormat) -> String {
+    let mut name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(120)
+        .collect();
+    name = name.trim().trim_matches('.').to_string();
+    if name.is_empty() {
+        name = "annotations".to_string();
+    }
+    format!("{}.{}", name, format.extension())
+}
@@ -0,0 +1,133 @@
+use crate::db::models::flashcard::ClozeSpan;
+
+/// What a hidden span shows on the question side when it has no hint.
+pub const CLOZE_BLANK: &str = "[...]";
+
+/// Marks `spans` of `text` as deletions using Anki's `{{c1::answer}}`
+/// syntax. All spans share one cloze number, so they are hidden together on
+/// a single card. Offsets are in characters, end exclusive.
+pub fn cloze_markup(text: &str, spans: &[ClozeSpan]) -> Result<String, String> {
+    if spans.is_empty() {
+        return Err("Mark at least one span to blank out".to_string());
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut sorted = spans.to_vec();
+    sorted.sort_by_key(|span| span.start);
+
+    let mut markup = String::new();
+    let mut cursor = 0;
+    for span in &sorted {
+        if span.start >= span.end || span.end > chars.len() {
+            return Err(format!(
+                "Span {}..{} is outside the highlighted text ({} characters)",
+                span.start,
+                span.end,
+                chars.len()
+            ));
+        }
+        if span.start < cursor {
+            return Err(format!("Span {}..{} overlaps another span", span.start, span.end));
+        }
+
+        let answer: String = chars[span.start..span.end].iter().collect();
+        if answer.trim().is_empty() {
+            return Err(format!("Span {}..{} only covers whitespace", span.start, span.end));
+        }
+        // The answer can't be escaped, and a trailing `}` would close the
+        // deletion early just like `}}`.
+        if answer.contains("::") || answer.contains("{{") || answer.contains("}}") || answer.ends_with('}') {
+            return Err(format!(
+                "Span {}..{} contains '::', '{{{{' or '}}}}', which a cloze deletion can't hold",
+                span.start, span.end
+            ));
+        }
+
+        markup.extend(&chars[cursor..span.start]);
+        markup.push_str("{{c1::");
+        markup.push_str(&answer);
+        markup.push_str("}}");
+        cursor = span.end;
+    }
+    markup.extend(&chars[cursor..]);
+
+    Ok(markup)
+}
+
+/// Renders cloze markup into the question and answer sides of a card. Every
+/// deletion is hidden on the question side, showing its hint as `[hint]` if
+/// it has one (`{{c1::answer::hint}}`). Text without deletions is returned
+/// unchanged on both sides.
+pub fn render_cloze(markup: &str) -> (String, String) {
+    render_deletions(markup, |_, _| true)
+}
+
+/// Renders the card for cloze number `number` the way Anki does: only the
+/// `c<number>` deletions are hidden, the others show their answer.
+pub fn render_cloze_number(markup: &str, number: u32) -> (String, String) {
+    render_deletions(markup, |n, _| n == number)
+}
+
+/// Cloze numbers used in `markup`, ascending and without repeats.
+pub fn cloze_numbers(markup: &str) -> Vec<u32> {
+    let mut numbers = Vec::new();
+    render_deletions(markup, |n, _| {
+        numbers.push(n);
+        true
+    });
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+/// Answers of the `c<number>` deletions, in text order.
+pub fn cloze_answers(markup: &str, number: u32) -> Vec<String> {
+    let mut answers = Vec::new();
+    render_deletions(markup, |n, answer| {
+        if n == number {
+            answers.push(answer.to_string());
+        }
+        true
+    });
+    answers
+}
+
+fn render_deletions(markup: &str, mut hidden: impl FnMut(u32, &str) -> bool) -> (String, String) {
+    let mut front = String::new();
+    let mut back = String::new();
+    let mut rest = markup;
+
+    while let Some(open) = rest.find("{{c") {
+        let Some(close) = rest[open..].find("}}").map(|close| open + close) else {
+            break;
+        };
+        let deletion = rest[open + 3..close]
+            .split_once("::")
+            .and_then(|(number, body)| Some((number.parse::<u32>().ok()?, body)));
+        let Some((number, body)) = deletion else {
+            front.push_str(&rest[..open + 3]);
+            back.push_str(&rest[..open + 3]);
+            rest = &rest[open + 3..];
+            continue;
+        };
+
+        let (answer, hint) = match body.split_once("::") {
+            Some((answer, hint)) => (answer, Some(hint)),
+            None => (body, None),
+        };
+
+        front.push_str(&rest[..open]);
+        back.push_str(&rest[..open]);
+        match hint {
+            _ if !hidden(number, answer) => front.push_str(answer),
+            Some(hint) => front.push_str(&format!("[{}]", hint)),
+            None => front.push_str(CLOZE_BLANK),
+        }
+        back.push_str(answer);
+        rest = &rest[close + 2..];
+    }
+
+    front.push_str(rest);
+    back.push_str(rest);
+    (front, back)
+}
@@ -0,0 +1,449 @@
+use roxmltree::{Document as XmlDocument, Node, ParsingOptions};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use thiserror::Error;
+use zip::ZipArchive;
+
+/// Largest archive entry read into memory, so a crafted file (a zip bomb)
+/// can't exhaust it. Generous for chapters and cover images.
+pub const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum EpubError {
+    #[error("Malformed EPUB: {0}")]
+    Malformed(String),
+
+    #[error("Chapter not found: {0}")]
+    ChapterNotFound(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl From<zip::result::ZipError> for EpubError {
+    fn from(err: zip::result::ZipError) -> Self {
+        EpubError::Malformed(err.to_string())
+    }
+}
+
+impl From<roxmltree::Error> for EpubError {
+    fn from(err: roxmltree::Error) -> Self {
+        EpubError::Malformed(err.to_string())
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct EpubMetadata {
+    pub title: Option<String>,
+    pub creators: Vec<String>,
+    pub language: Option<String>,
+    pub identifiers: Vec<String>,
+    pub subject: Option<String>,
+}
+
+/// One entry of the reading order (spine), in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct EpubChapter {
+    pub index: i32,
+    pub href: String,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EpubCover {
+    pub bytes: Vec<u8>,
+    pub media_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct EpubBook {
+    pub metadata: EpubMetadata,
+    pub chapters: Vec<EpubChapter>,
+    pub cover: Option<EpubCover>,
+}
+
+struct ManifestItem {
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+struct Package {
+    metadata: EpubMetadata,
+    manifest: HashMap<String, ManifestItem>,
+    spine: Vec<String>,
+    cover_id: Option<String>,
+    toc_id: Option<String>,
+}
+
+pub fn read_epub(path: &Path) -> Result<EpubBook, EpubError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let opf_path = find_package_path(&mut archive)?;
+    let package = parse_package(&read_entry(&mut archive, &opf_path)?)?;
+
+    let toc_titles = read_toc_titles(&mut archive, &package, &opf_path);
+
+    let chapters = package
+        .spine
+        .iter()
+        .filter_map(|idref| package.manifest.get(idref))
+        .enumerate()
+        .map(|(index, item)| {
+            let href = resolve_href(&opf_path, &item.href);
+            EpubChapter {
+                index: index as i32,
+                title: toc_titles.get(&href).cloned(),
+                href,
+            }
+        })
+        .collect();
+
+    let cover = cover_item(&package).and_then(|item| {
+        let href = resolve_href(&opf_path, &item.href);
+        read_entry_bytes(&mut archive, &href).ok().map(|bytes| EpubCover {
+            bytes,
+            media_type: item.media_type.clone(),
+        })
+    });
+
+    Ok(EpubBook {
+        metadata: package.metadata,
+        chapters,
+        cover,
+    })
+}
+
+/// Plain text of a chapter, `href` being the archive path from `EpubChapter`.
+pub fn read_chapter_text(path: &Path, href: &str) -> Result<String, EpubError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let html = read_entry(&mut archive, href)
+        .map_err(|_| EpubError::ChapterNotFound(href.to_string()))?;
+    Ok(html_to_text(&html))
+}
+
+/// Plain text of every chapter in reading order, keyed by chapter index.
+pub fn read_all_chapter_texts(path: &Path) -> Result<Vec<(i32, String)>, EpubError> {
+    let book = read_epub(path)?;
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let texts = book
+        .chapters
+        .iter()
+        .map(|chapter| {
+            let text = read_entry(&mut archive, &chapter.href)
+                .map(|html| html_to_text(&html))
+                .unwrap_or_default();
+            (chapter.index, text)
+        })
+        .collect();
+
+    Ok(texts)
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<String, EpubError> {
+    let bytes = read_entry_bytes(archive, name)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn read_entry_bytes(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, EpubError> {
+    let entry = archive.by_name(name)?;
+    let mut bytes = Vec::new();
+    // The size in the archive header can't be trusted, so the read itself is
+    // capped; one byte more than the limit tells an oversized entry apart.
+    entry.take(MAX_ENTRY_BYTES + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(EpubError::Malformed(format!(
+            "{} is larger than {} MiB",
+            name,
+            MAX_ENTRY_BYTES / (1024 * 1024)
+        )));
+    }
+    Ok(bytes)
+}
+
+fn parse_xml(text: &str) -> Result<XmlDocument<'_>, EpubError> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    Ok(XmlDocument::parse_with_options(text, options)?)
+}
+
+fn find_package_path(archive: &mut ZipArchive<File>) -> Result<String, EpubError> {
+    let container = read_entry(archive, "META-INF/container.xml")?;
+    let xml = parse_xml(&container)?;
+
+    xml.descendants()
+        .find(|node| node.tag_name().name() == "rootfile")
+        .and_then(|node| node.attribute("full-path"))
+        .map(str::to_string)
+        .ok_or_else(|| EpubError::Malformed("container.xml has no rootfile".to_string()))
+}
+
+fn parse_package(opf: &str) -> Result<Package, EpubError> {
+    let xml = parse_xml(opf)?;
+    let mut metadata = EpubMetadata::default();
+    let mut manifest = HashMap::new();
+    let mut spine = Vec::new();
+    let mut cover_id = None;
+    let mut toc_id = None;
+
+    for node in xml.descendants().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "title" if metadata.title.is_none() => metadata.title = element_text(node),
+            "creator" => metadata.creators.extend(element_text(node)),
+            "language" if metadata.language.is_none() => metadata.language = element_text(node),
+            "identifier" => metadata.identifiers.extend(element_text(node)),
+            "subject" if metadata.subject.is_none() => metadata.subject = element_text(node),
+            "meta" if node.attribute("name") == Some("cover") => {
+                cover_id = node.attribute("content").map(str::to_string);
+            }
+            "item" => {
+                if let (Some(id), Some(href)) = (node.attribute("id"), node.attribute("href")) {
+                    manifest.insert(
+                        id.to_string(),
+                        ManifestItem {
+                            href: href.to_string(),
+                            media_type: node.attribute("media-type").unwrap_or_default().to_string(),
+                            properties: node.attribute("properties").unwrap_or_default().to_string(),
+                        },
+                    );
+                }
+            }
+            "spine" => toc_id = node.attribute("toc").map(str::to_string),
+            "itemref" => {
+                if let Some(idref) = node.attribute("idref") {
+                    spine.push(idref.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Package {
+        metadata,
+        manifest,
+        spine,
+        cover_id,
+        toc_id,
+    })
+}
+
+fn element_text(node: Node) -> Option<String> {
+    let text: String = node
+        .descendants()
+        .filter(Node::is_text)
+        .filter_map(|n| n.text())
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn cover_item(package: &Package) -> Option<&ManifestItem> {
+    // EPUB 3 flags the cover in the manifest, EPUB 2 through a <meta name="cover">.
+    package
+        .manifest
+        .values()
+        .find(|item| item.properties.split_whitespace().any(|p| p == "cover-image"))
+        .or_else(|| package.cover_id.as_ref().and_then(|id| package.manifest.get(id)))
+        .filter(|item| item.media_type.starts_with("image/"))
+}
+
+/// Maps chapter archive paths to their table-of-contents label, reading the
+/// EPUB 3 nav document or, failing that, the EPUB 2 NCX.
+fn read_toc_titles(archive: &mut ZipArchive<File>, package: &Package, opf_path: &str) -> HashMap<String, String> {
+    let nav = package
+        .manifest
+        .values()
+        .find(|item| item.properties.split_whitespace().any(|p| p == "nav"));
+    let ncx = package.toc_id.as_ref().and_then(|id| package.manifest.get(id));
+
+    let mut titles = HashMap::new();
+
+    for (item, link_tag) in [(nav, "a"), (ncx, "navPoint")] {
+        let Some(item) = item else { continue };
+        let toc_path = resolve_href(opf_path, &item.href);
+        let Ok(text) = read_entry(archive, &toc_path) else { continue };
+        let Ok(xml) = parse_xml(&text) else { continue };
+
+        for node in xml.descendants().filter(|n| n.tag_name().name() == link_tag) {
+            let (label, target) = if link_tag == "a" {
+                (element_text(node), node.attribute("href"))
+            } else {
+                let label = node
+                    .children()
+                    .find(|n| n.tag_name().name() == "navLabel")
+                    .and_then(element_text);
+                let target = node
+                    .children()
+                    .find(|n| n.tag_name().name() == "content")
+                    .and_then(|n| n.attribute("src"));
+                (label, target)
+            };
+
+            if let (Some(label), Some(target)) = (label, target) {
+                titles.entry(resolve_href(&toc_path, target)).or_insert(label);
+            }
+        }
+
+        if !titles.is_empty() {
+            break;
+        }
+    }
+
+    titles
+}
+
+/// Resolves an href found in `base` (an archive path) to an archive path,
+/// dropping any fragment.
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+
+    let mut parts: Vec<&str> = match base.rfind('/') {
+        Some(pos) => base[..pos].split('/').collect(),
+        None => Vec::new(),
+    };
+
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(segment),
+        }
+    }
+
+    parts.join("/")
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = (char::from(bytes[i + 1]).to_digit(16), char::from(bytes[i + 2]).to_digit(16));
+            if let (Some(high), Some(low)) = hex {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Very small XHTML-to-text conversion: drops markup, keeps paragraph breaks.
+pub fn html_to_text(html: &str) -> String {
+    const BLOCK_TAGS: &[&str] = &[
+        "p", "div", "br", "li", "h1", "h2", "h3", "h4", "h5", "h6", "tr", "blockquote", "section",
+    ];
+    const SKIPPED_TAGS: &[&str] = &["head", "script", "style"];
+
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    let mut skipping: Option<String> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skipping.is_none() {
+            text.push_str(&decode_entities(&rest[..start]));
+        }
+
+        // A tag cut off at the end is dropped; its text was pushed above.
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+
+        if let Some(skipped) = &skipping {
+            if closing && *skipped == name {
+                skipping = None;
+            }
+            continue;
+        }
+
+        if !closing && !tag.ends_with('/') && SKIPPED_TAGS.contains(&name.as_str()) {
+            skipping = Some(name);
+        } else if BLOCK_TAGS.contains(&name.as_str()) && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+
+    if skipping.is_none() {
+        text.push_str(&decode_entities(rest));
+    }
+
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(input: &str) -> String {
+    if !input.contains('&') {
+        return input.to_string();
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';').filter(|&end| end <= 10) else {
+            output.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+            }
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+
+        match decoded {
+            Some(c) => {
+                output.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
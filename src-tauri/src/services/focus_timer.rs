@@ -0,0 +1,405 @@
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use thiserror::Error;
+use crate::db::models::pomodoro::Pomodoro;
+use crate::db::repositories::PomodoroRepository;
+
+/// Emitted with the new status whenever the timer moves to another phase.
+pub const PHASE_CHANGED_EVENT: &str = "focus-timer:phase-changed";
+/// Emitted with the final status when the configured cycles are done or the
+/// timer is stopped.
+pub const FINISHED_EVENT: &str = "focus-timer:finished";
+
+/// Called with an event name and the status it reports. The app forwards it
+/// to the webview; it must not call back into the timer.
+pub type FocusNotifier = Arc<dyn Fn(&str, &FocusTimerStatus) + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum FocusTimerError {
+    #[error("A focus timer is already running")]
+    AlreadyRunning,
+
+    #[error("No focus timer is running")]
+    NotRunning,
+
+    #[error("Invalid timer settings: {0}")]
+    InvalidConfig(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum FocusPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl FocusPhase {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Work => "work",
+            Self::ShortBreak => "short_break",
+            Self::LongBreak => "long_break",
+        }
+    }
+}
+
+/// Phase lengths in seconds. Every `long_break_every` work phases the break
+/// is a long one. With `cycles` set the timer finishes after that many work
+/// phases; otherwise it runs until stopped.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FocusTimerConfig {
+    pub work_seconds: u32,
+    pub short_break_seconds: u32,
+    pub long_break_seconds: u32,
+    pub long_break_every: u32,
+    pub cycles: Option<u32>,
+}
+
+impl Default for FocusTimerConfig {
+    fn default() -> Self {
+        Self {
+            work_seconds: 25 * 60,
+            short_break_seconds: 5 * 60,
+            long_break_seconds: 15 * 60,
+            long_break_every: 4,
+            cycles: None,
+        }
+    }
+}
+
+impl FocusTimerConfig {
+    pub fn validate(&self) -> Result<(), FocusTimerError> {
+        if self.work_seconds == 0 || self.short_break_seconds == 0 || self.long_break_seconds == 0 {
+            return Err(FocusTimerError::InvalidConfig("Every phase must last at least a second".to_string()));
+        }
+        if self.long_break_every == 0 {
+            return Err(FocusTimerError::InvalidConfig("Long breaks must come every 1 or more work phases".to_string()));
+        }
+        if self.cycles == Some(0) {
+            return Err(FocusTimerError::InvalidConfig("Run at least one work phase".to_string()));
+        }
+        Ok(())
+    }
+
+    fn phase_seconds(&self, phase: FocusPhase) -> u32 {
+        match phase {
+            FocusPhase::Work => self.work_seconds,
+            FocusPhase::ShortBreak => self.short_break_seconds,
+            FocusPhase::LongBreak => self.long_break_seconds,
+        }
+    }
+}
+
+/// What the timer is doing, as sent to the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusTimerStatus {
+    pub user_id: i32,
+    pub task_id: Option<i32>,
+    pub book_id: Option<i64>,
+    pub config: FocusTimerConfig,
+    pub phase: FocusPhase,
+    pub paused: bool,
+    pub finished: bool,
+    /// Work phases completed so far.
+    pub completed_pomodoros: u32,
+    pub phase_started_at: DateTime<Utc>,
+    /// When the current phase ends; unset while paused or finished.
+    pub phase_ends_at: Option<DateTime<Utc>>,
+    pub remaining_seconds: i64,
+}
+
+/// The phases of one timer run, apart from the thread that drives them.
+pub struct FocusCycle {
+    user_id: i32,
+    task_id: Option<i32>,
+    book_id: Option<i64>,
+    config: FocusTimerConfig,
+    phase: FocusPhase,
+    completed_pomodoros: u32,
+    phase_started_at: DateTime<Utc>,
+    ends_at: Option<DateTime<Utc>>,
+    /// Time left in the phase while paused.
+    remaining: Duration,
+}
+
+impl FocusCycle {
+    /// A cycle whose first work phase starts at `now`.
+    pub fn start(user_id: i32, task_id: Option<i32>, book_id: Option<i64>, config: FocusTimerConfig, now: DateTime<Utc>) -> Self {
+        let mut cycle = Self {
+            user_id,
+            task_id,
+            book_id,
+            config,
+            phase: FocusPhase::Work,
+            completed_pomodoros: 0,
+            phase_started_at: now,
+            ends_at: None,
+            remaining: Duration::zero(),
+        };
+        cycle.enter(FocusPhase::Work, now);
+        cycle
+    }
+
+    fn remaining_at(&self, now: DateTime<Utc>) -> Duration {
+        match self.ends_at {
+            Some(ends_at) => (ends_at - now).max(Duration::zero()),
+            None => self.remaining,
+        }
+    }
+
+    pub fn status(&self, now: DateTime<Utc>, finished: bool) -> FocusTimerStatus {
+        let remaining = if finished { Duration::zero() } else { self.remaining_at(now) };
+        FocusTimerStatus {
+            user_id: self.user_id,
+            task_id: self.task_id,
+            book_id: self.book_id,
+            config: self.config,
+            phase: self.phase,
+            paused: !finished && self.ends_at.is_none(),
+            finished,
+            completed_pomodoros: self.completed_pomodoros,
+            phase_started_at: self.phase_started_at,
+            phase_ends_at: if finished { None } else { self.ends_at },
+            // Round up, so a phase shows 0 only once it is over.
+            remaining_seconds: (remaining.num_milliseconds() + 999) / 1000,
+        }
+    }
+
+    fn enter(&mut self, phase: FocusPhase, at: DateTime<Utc>) {
+        self.phase = phase;
+        self.phase_started_at = at;
+        self.ends_at = Some(at + Duration::seconds(self.config.phase_seconds(phase) as i64));
+        self.remaining = Duration::zero();
+    }
+
+    /// Moves on from the current phase at `now`. A work phase that ran to its
+    /// end counts as a pomodoro and is returned; a skipped one doesn't count.
+    /// A phase that ended more than a whole phase before `now`, e.g. while
+    /// the computer slept, starts over from `now` instead, and neither it nor
+    /// the phases that would have followed count. Returns whether the run is
+    /// over as well.
+    pub fn advance(&mut self, now: DateTime<Utc>, completed: bool) -> (Option<Pomodoro>, bool) {
+        let at = match self.ends_at {
+            Some(ends_at) if completed => ends_at,
+            _ => now,
+        };
+        if now - at > Duration::seconds(self.config.phase_seconds(self.phase) as i64) {
+            info!("Focus timer restarted its {} phase, {}s after it ended", self.phase.as_str(), (now - at).num_seconds());
+            self.enter(self.phase, now);
+            return (None, false);
+        }
+
+        if self.phase != FocusPhase::Work {
+            self.enter(FocusPhase::Work, at);
+            return (None, false);
+        }
+
+        let mut pomodoro = None;
+        if completed {
+            self.completed_pomodoros += 1;
+            pomodoro = Some(Pomodoro {
+                id: None,
+                user_id: self.user_id,
+                task_id: self.task_id,
+                book_id: self.book_id,
+                started_at: self.phase_started_at,
+                ended_at: at,
+                focus_seconds: self.config.work_seconds,
+            });
+            if self.config.cycles == Some(self.completed_pomodoros) {
+                return (pomodoro, true);
+            }
+        }
+
+        let long_break = completed && self.completed_pomodoros % self.config.long_break_every == 0;
+        self.enter(if long_break { FocusPhase::LongBreak } else { FocusPhase::ShortBreak }, at);
+        (pomodoro, false)
+    }
+}
+
+struct TimerRun {
+    id: u64,
+    cycle: FocusCycle,
+    notifier: FocusNotifier,
+}
+
+struct TimerState {
+    run: Option<TimerRun>,
+    next_id: u64,
+}
+
+/// The focus timer kept in `AppState`. It runs on its own thread, so it keeps
+/// going when the webview reloads; the UI re-attaches by asking for its
+/// status. Completed work phases are saved as pomodoros.
+pub struct FocusTimer {
+    db_conn: Arc<Mutex<Connection>>,
+    shared: Arc<(Mutex<TimerState>, Condvar)>,
+}
+
+impl FocusTimer {
+    pub fn new(db_conn: Arc<Mutex<Connection>>) -> Self {
+        Self {
+            db_conn,
+            shared: Arc::new((Mutex::new(TimerState { run: None, next_id: 1 }), Condvar::new())),
+        }
+    }
+
+    pub fn start(
+        &self,
+        user_id: i32,
+        task_id: Option<i32>,
+        book_id: Option<i64>,
+        config: FocusTimerConfig,
+        notifier: FocusNotifier,
+    ) -> Result<FocusTimerStatus, FocusTimerError> {
+        config.validate()?;
+
+        let (lock, _) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+        if state.run.is_some() {
+            return Err(FocusTimerError::AlreadyRunning);
+        }
+
+        let now = Utc::now();
+        let id = state.next_id;
+        state.next_id += 1;
+        let run = TimerRun {
+            id,
+            cycle: FocusCycle::start(user_id, task_id, book_id, config, now),
+            notifier,
+        };
+        let status = run.cycle.status(now, false);
+        state.run = Some(run);
+        drop(state);
+
+        let shared = Arc::clone(&self.shared);
+        let db_conn = Arc::clone(&self.db_conn);
+        thread::spawn(move || run_timer(shared, db_conn, id));
+
+        info!("Focus timer started for user {}", user_id);
+        Ok(status)
+    }
+
+    /// The running timer, if any.
+    pub fn status(&self) -> Option<FocusTimerStatus> {
+        let (lock, _) = &*self.shared;
+        let state = lock.lock().unwrap();
+        state.run.as_ref().map(|run| run.cycle.status(Utc::now(), false))
+    }
+
+    pub fn pause(&self) -> Result<FocusTimerStatus, FocusTimerError> {
+        self.update(|cycle, now| {
+            if cycle.ends_at.is_some() {
+                cycle.remaining = cycle.remaining_at(now);
+                cycle.ends_at = None;
+            }
+        })
+    }
+
+    pub fn resume(&self) -> Result<FocusTimerStatus, FocusTimerError> {
+        self.update(|cycle, now| {
+            if cycle.ends_at.is_none() {
+                cycle.ends_at = Some(now + cycle.remaining);
+                cycle.remaining = Duration::zero();
+            }
+        })
+    }
+
+    /// Ends the current phase early. A skipped work phase is not saved and
+    /// doesn't count towards the long break.
+    pub fn skip(&self) -> Result<FocusTimerStatus, FocusTimerError> {
+        let status = self.update(|cycle, now| {
+            cycle.advance(now, false);
+        })?;
+        self.notify(PHASE_CHANGED_EVENT, &status);
+        Ok(status)
+    }
+
+    /// Stops the timer. The work phase in progress, if any, is dropped.
+    pub fn stop(&self) -> Result<FocusTimerStatus, FocusTimerError> {
+        let (lock, condvar) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+        let run = state.run.take().ok_or(FocusTimerError::NotRunning)?;
+        drop(state);
+        condvar.notify_all();
+
+        let status = run.cycle.status(Utc::now(), true);
+        (run.notifier)(FINISHED_EVENT, &status);
+        info!("Focus timer stopped after {} pomodoro(s)", status.completed_pomodoros);
+        Ok(status)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut FocusCycle, DateTime<Utc>)) -> Result<FocusTimerStatus, FocusTimerError> {
+        let (lock, condvar) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+        let run = state.run.as_mut().ok_or(FocusTimerError::NotRunning)?;
+
+        let now = Utc::now();
+        change(&mut run.cycle, now);
+        let status = run.cycle.status(now, false);
+        drop(state);
+        condvar.notify_all();
+        Ok(status)
+    }
+
+    fn notify(&self, event: &str, status: &FocusTimerStatus) {
+        let (lock, _) = &*self.shared;
+        let notifier = lock.lock().unwrap().run.as_ref().map(|run| Arc::clone(&run.notifier));
+        if let Some(notifier) = notifier {
+            notifier(event, status);
+        }
+    }
+}
+
+/// Sleeps until the current phase of run `id` ends, then moves it on, until
+/// the run finishes or is stopped. The timer lock is never held while saving
+/// or notifying.
+fn run_timer(shared: Arc<(Mutex<TimerState>, Condvar)>, db_conn: Arc<Mutex<Connection>>, id: u64) {
+    let (lock, condvar) = &*shared;
+    let mut state = lock.lock().unwrap();
+
+    loop {
+        let Some(run) = state.run.as_mut().filter(|run| run.id == id) else {
+            return;
+        };
+        let Some(ends_at) = run.cycle.ends_at else {
+            state = condvar.wait(state).unwrap();
+            continue;
+        };
+
+        let now = Utc::now();
+        if now < ends_at {
+            let wait = (ends_at - now).to_std().unwrap_or_default();
+            state = condvar.wait_timeout(state, wait).unwrap().0;
+            continue;
+        }
+
+        let (pomodoro, finished) = run.cycle.advance(now, true);
+        let status = run.cycle.status(now, finished);
+        let notifier = Arc::clone(&run.notifier);
+        if finished {
+            state.run = None;
+        }
+        drop(state);
+
+        if let Some(pomodoro) = pomodoro {
+            let mut conn = db_conn.lock().unwrap();
+            if let Err(err) = PomodoroRepository::new(&mut conn).create_pomodoro(&pomodoro) {
+                error!("Failed to save pomodoro: {}", err);
+            }
+        }
+
+        if finished {
+            info!("Focus timer finished after {} pomodoro(s)", status.completed_pomodoros);
+            notifier(FINISHED_EVENT, &status);
+            return;
+        }
+        notifier(PHASE_CHANGED_EVENT, &status);
+
+        state = lock.lock().unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LibraryError {
+    #[error("File not found: {0}")]
+    SourceNotFound(String),
+
+    #[error("Unsupported file type: {0}")]
+    UnsupportedFileType(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A file that has been copied into the library folder.
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    pub original_filename: String,
+    pub stored_filename: String,
+    pub file_path: PathBuf,
+    pub file_size: u64,
+    pub mime_type: String,
+    pub hash: String,
+    /// False when an identical file was already in the library, in which case
+    /// the copy must not be removed on rollback.
+    pub newly_created: bool,
+}
+
+/// App-owned folder holding every imported document under a content-addressed name.
+pub struct LibraryStore {
+    root: PathBuf,
+}
+
+impl LibraryStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn path_for(&self, stored_filename: &str) -> PathBuf {
+        self.root.join(stored_filename)
+    }
+
+    pub fn store_file(&self, source: &Path) -> Result<StoredFile, LibraryError> {
+        if !source.is_file() {
+            return Err(LibraryError::SourceNotFound(source.display().to_string()));
+        }
+        let hash = hash_file(source)?;
+        self.store_file_with_hash(source, hash)
+    }
+
+    /// Same as `store_file` for callers that already hashed the source.
+    pub fn store_file_with_hash(&self, source: &Path, hash: String) -> Result<StoredFile, LibraryError> {
+        if !source.is_file() {
+            return Err(LibraryError::SourceNotFound(source.display().to_string()));
+        }
+
+        let (extension, mime_type) = detect_file_type(source)?;
+        let stored_filename = format!("{}.{}", hash, extension);
+        let file_path = self.path_for(&stored_filename);
+
+        fs::create_dir_all(&self.root)?;
+
+        let newly_created = !file_path.exists();
+        if newly_created {
+            // Copy under a temporary name first so a crash never leaves a
+            // truncated file behind the final content-addressed name.
+            let partial_path = self.path_for(&format!("{}.partial", stored_filename));
+            fs::copy(source, &partial_path)?;
+            if let Err(err) = fs::rename(&partial_path, &file_path) {
+                let _ = fs::remove_file(&partial_path);
+                return Err(err.into());
+            }
+        }
+
+        let original_filename = source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| stored_filename.clone());
+
+        Ok(StoredFile {
+            original_filename,
+            stored_filename,
+            file_size: fs::metadata(&file_path)?.len(),
+            file_path,
+            mime_type: mime_type.to_string(),
+            hash,
+            newly_created,
+        })
+    }
+
+    /// Undoes `store_file` after a failed database insert.
+    pub fn discard(&self, stored: &StoredFile) -> Result<(), LibraryError> {
+        if stored.newly_created && stored.file_path.exists() {
+            fs::remove_file(&stored.file_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hex-encoded SHA-256 of the file content.
+pub fn hash_file(path: &Path) -> Result<String, io::Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn detect_file_type(path: &Path) -> Result<(&'static str, &'static str), LibraryError> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "pdf" => Ok(("pdf", "application/pdf")),
+        "epub" => Ok(("epub", "application/epub+zip")),
+        _ => Err(LibraryError::UnsupportedFileType(path.display().to_string())),
+    }
+}
@@ -0,0 +1,29 @@
+pub mod annotation_export;
+pub mod anki;
+pub mod cloze;
+pub mod epub;
+pub mod focus_timer;
+pub mod library_store;
+pub mod pdf;
+pub mod pdf_annotations;
+pub mod quiz;
+pub mod reading_planner;
+pub mod spaced_repetition;
+pub mod recurrence;
+pub mod text_index;
+pub mod thumbnail;
+
+pub use annotation_export::*;
+pub use anki::*;
+pub use cloze::*;
+pub use epub::*;
+pub use focus_timer::*;
+pub use library_store::*;
+pub use pdf::*;
+pub use pdf_annotations::*;
+pub use quiz::*;
+pub use reading_planner::*;
+pub use spaced_repetition::*;
+pub use recurrence::*;
+pub use text_index::*;
+pub use thumbnail::*;
@@ -0,0 +1,109 @@
+use lopdf::{decode_text_string, Dictionary, Document as PdfDocument, Object};
+use serde::Serialize;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PdfError {
+    #[error("PDF is encrypted and cannot be opened without a password")]
+    Encrypted,
+
+    #[error("Malformed PDF: {0}")]
+    Malformed(String),
+}
+
+impl From<lopdf::Error> for PdfError {
+    fn from(err: lopdf::Error) -> Self {
+        PdfError::Malformed(err.to_string())
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+    pub page_count: u32,
+}
+
+/// Opens a PDF, decrypting it when it only carries an owner password.
+pub fn open_pdf(path: &Path) -> Result<PdfDocument, PdfError> {
+    // lopdf can panic on some corrupt inputs; an import must never take the app down.
+    let loaded = panic::catch_unwind(AssertUnwindSafe(|| PdfDocument::load(path)))
+        .map_err(|_| PdfError::Malformed("parser panicked".to_string()))?;
+    let mut document = loaded?;
+
+    if document.is_encrypted() && document.decrypt("").is_err() {
+        return Err(PdfError::Encrypted);
+    }
+
+    Ok(document)
+}
+
+pub fn read_metadata(path: &Path) -> Result<PdfMetadata, PdfError> {
+    let document = open_pdf(path)?;
+    let page_count = document.get_pages().len() as u32;
+
+    let info = document
+        .trailer
+        .get(b"Info")
+        .and_then(|object| document.dereference(object))
+        .and_then(|(_, object)| object.as_dict());
+
+    let metadata = match info {
+        Ok(info) => PdfMetadata {
+            title: info_string(info, b"Title"),
+            author: info_string(info, b"Author"),
+            subject: info_string(info, b"Subject"),
+            keywords: info_string(info, b"Keywords")
+                .map(|keywords| split_keywords(&keywords))
+                .unwrap_or_default(),
+            page_count,
+        },
+        // The Info dictionary is optional.
+        Err(_) => PdfMetadata {
+            page_count,
+            ..PdfMetadata::default()
+        },
+    };
+
+    Ok(metadata)
+}
+
+fn info_string(info: &Dictionary, key: &[u8]) -> Option<String> {
+    info.get(key)
+        .ok()
+        .and_then(|object: &Object| decode_text_string(object).ok())
+        .map(|value| value.trim().trim_matches('\0').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn split_keywords(keywords: &str) -> Vec<String> {
+    keywords
+        .split([',', ';'])
+        .map(|keyword| keyword.trim().to_string())
+        .filter(|keyword| !keyword.is_empty())
+        .collect()
+}
+
+/// Text of every page, 1-based page numbers. Pages whose content cannot be
+/// decoded come back empty rather than failing the whole document.
+pub fn extract_page_texts(path: &Path) -> Result<Vec<(u32, String)>, PdfError> {
+    let document = open_pdf(path)?;
+    let page_numbers: Vec<u32> = document.get_pages().keys().copied().collect();
+
+    let pages = page_numbers
+        .into_iter()
+        .map(|page_number| {
+            let text = panic::catch_unwind(AssertUnwindSafe(|| document.extract_text(&[page_number])))
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            (page_number, text)
+        })
+        .collect();
+
+    Ok(pages)
+}
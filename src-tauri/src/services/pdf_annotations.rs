@@ -0,0 +1,492 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::warn;
+use lopdf::{decode_text_string, text_string, Dictionary, Document as PdfDocument, Object, ObjectId};
+use pdfium_render::prelude::{PdfPageText, PdfRect};
+use std::collections::HashMap;
+use std::path::Path;
+use crate::db::models::annotation::{Annotation, AnnotationKind, AnnotationRect, DEFAULT_ANNOTATION_COLOR};
+use crate::services::pdf::{open_pdf, PdfError};
+use crate::services::thumbnail::bind_pdfium;
+
+/// Prefix of the `/NM` name given to annotations we write; the annotation id
+/// follows, which keeps names unique within the file as the spec asks.
+pub const ANNOTATION_NAME_PREFIX: &str = "study-studio-";
+
+const ANNOTATION_AUTHOR: &str = "Study Studio";
+const NOTE_ICON_SIZE: f64 = 20.0;
+const DEFAULT_COLOR: [f32; 3] = [1.0, 0.92, 0.23];
+const LETTER_MEDIA_BOX: [f64; 4] = [0.0, 0.0, 612.0, 792.0];
+
+/// Writes a copy of `source` to `destination` with the annotations embedded
+/// as standard PDF annotation objects. The source file is never modified.
+///
+/// Highlights and underlines with rectangles become `/Highlight` and
+/// `/Underline` markup; notes, and highlights we have no rectangles for,
+/// become `/Text` sticky notes. Annotations imported from `source` (with a
+/// `pdf_ref`) are already in it and are not added again. Returns how many
+/// annotations were written.
+pub fn write_annotated_pdf(
+    source: &Path,
+    destination: &Path,
+    annotations: &[Annotation],
+) -> Result<usize, PdfError> {
+    let mut document = open_pdf(source)?;
+    let pages = document.get_pages();
+    let modified = pdf_date();
+    let mut written = 0;
+
+    for annotation in annotations.iter().filter(|annotation| annotation.pdf_ref.is_none()) {
+        let Some(page_id) = annotation.page_number
+            .and_then(|page| u32::try_from(page).ok())
+            .and_then(|page| pages.get(&page).copied())
+        else {
+            continue;
+        };
+
+        let media_box = page_media_box(&document, page_id);
+        let dictionary = build_annotation(annotation, page_id, media_box, &modified);
+        let annotation_id = document.add_object(dictionary);
+        attach_to_page(&mut document, page_id, annotation_id)?;
+        written += 1;
+    }
+
+    document.save(destination).map_err(|err| PdfError::Malformed(err.to_string()))?;
+    Ok(written)
+}
+
+/// An annotation found in a PDF made by another reader.
+#[derive(Debug, Clone)]
+pub struct PdfAnnotation {
+    pub page_number: u32,
+    pub kind: AnnotationKind,
+    pub rects: Vec<AnnotationRect>,
+    pub selected_text: Option<String>,
+    pub note: Option<String>,
+    pub color: String,
+    pub modified_at: Option<DateTime<Utc>>,
+    /// `page/index` of the annotation in the page's `/Annots`.
+    pub pdf_ref: String,
+}
+
+impl PdfAnnotation {
+    pub fn into_annotation(self, book_id: i64) -> Annotation {
+        Annotation {
+            id: None,
+            book_id,
+            kind: self.kind,
+            page_number: Some(self.page_number as i32),
+            chapter_index: None,
+            location: None,
+            rects: Some(self.rects).filter(|rects| !rects.is_empty()),
+            start_offset: None,
+            end_offset: None,
+            selected_text: self.selected_text,
+            color: self.color,
+            note: self.note,
+            created_at: self.modified_at,
+            updated_at: self.modified_at,
+            pdf_ref: Some(self.pdf_ref),
+            tags: None,
+        }
+    }
+}
+
+/// Reads the Highlight, Underline, Text and FreeText annotations of every
+/// page. Replies to an annotation (`/IRT`) are folded into its note, and the
+/// text under highlights is recovered with pdfium when it is available.
+pub fn read_pdf_annotations(path: &Path) -> Result<Vec<PdfAnnotation>, PdfError> {
+    let document = open_pdf(path)?;
+    let mut found: Vec<(Option<ObjectId>, PdfAnnotation)> = Vec::new();
+    let mut replies: Vec<(ObjectId, String)> = Vec::new();
+
+    for (page_number, page_id) in document.get_pages() {
+        for (index, (annotation_id, dictionary)) in page_annotation_dicts(&document, page_id).into_iter().enumerate() {
+            let subtype = dictionary.get(b"Subtype").and_then(Object::as_name).unwrap_or(b"");
+            let kind = match subtype {
+                b"Highlight" => AnnotationKind::Highlight,
+                b"Underline" => AnnotationKind::Underline,
+                b"Text" | b"FreeText" => AnnotationKind::Note,
+                _ => continue,
+            };
+            let contents = dictionary_text(dictionary, b"Contents");
+
+            if let Ok(parent_id) = dictionary.get(b"IRT").and_then(Object::as_reference) {
+                if let Some(contents) = contents {
+                    replies.push((parent_id, contents));
+                }
+                continue;
+            }
+
+            let rects = match kind {
+                AnnotationKind::Note => Vec::new(),
+                _ => quad_rects(&dictionary_numbers(dictionary, b"QuadPoints")),
+            };
+            let rects = if rects.is_empty() { dictionary_rect(dictionary).into_iter().collect() } else { rects };
+
+            found.push((annotation_id, PdfAnnotation {
+                page_number,
+                kind,
+                rects,
+                selected_text: None,
+                note: contents,
+                color: pdf_color_to_hex(&dictionary_numbers(dictionary, b"C"))
+                    .unwrap_or_else(|| DEFAULT_ANNOTATION_COLOR.to_string()),
+                modified_at: dictionary_date(dictionary, b"M")
+                    .or_else(|| dictionary_date(dictionary, b"CreationDate")),
+                pdf_ref: format!("{}/{}", page_number, index),
+            }));
+        }
+    }
+
+    for (parent_id, reply) in replies {
+        if let Some((_, parent)) = found.iter_mut().find(|(id, _)| *id == Some(parent_id)) {
+            parent.note = Some(match parent.note.take() {
+                Some(note) => format!("{}\n\n{}", note, reply),
+                None => reply,
+            });
+        }
+    }
+
+    let mut annotations: Vec<PdfAnnotation> = found.into_iter()
+        .map(|(_, annotation)| annotation)
+        .filter(|annotation| match annotation.kind {
+            AnnotationKind::Note => annotation.note.is_some(),
+            _ => !annotation.rects.is_empty(),
+        })
+        .collect();
+
+    fill_selected_text(path, &mut annotations);
+    Ok(annotations)
+}
+
+/// Annotation dictionaries of a page, with their object ids when they are
+/// indirect objects (needed to resolve replies).
+fn page_annotation_dicts(document: &PdfDocument, page_id: ObjectId) -> Vec<(Option<ObjectId>, &Dictionary)> {
+    let Ok(page) = document.get_dictionary(page_id) else {
+        return Vec::new();
+    };
+    let annots = page.get(b"Annots")
+        .and_then(|object| document.dereference(object))
+        .and_then(|(_, object)| object.as_array());
+
+    match annots {
+        Ok(annots) => annots.iter()
+            .filter_map(|object| match object {
+                Object::Reference(id) => document.get_dictionary(*id).ok().map(|d| (Some(*id), d)),
+                Object::Dictionary(dictionary) => Some((None, dictionary)),
+                _ => None,
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn dictionary_text(dictionary: &Dictionary, key: &[u8]) -> Option<String> {
+    dictionary.get(key)
+        .ok()
+        .and_then(|object| decode_text_string(object).ok())
+        .map(|text| text.replace('\r', "\n").trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn dictionary_numbers(dictionary: &Dictionary, key: &[u8]) -> Vec<f64> {
+    dictionary.get(key)
+        .and_then(Object::as_array)
+        .map(|values| values.iter().filter_map(object_to_f64).collect())
+        .unwrap_or_default()
+}
+
+fn dictionary_rect(dictionary: &Dictionary) -> Option<AnnotationRect> {
+    match dictionary_numbers(dictionary, b"Rect")[..] {
+        [x1, y1, x2, y2] => Some(AnnotationRect {
+            x: x1.min(x2),
+            y: y1.min(y2),
+            width: (x2 - x1).abs(),
+            height: (y2 - y1).abs(),
+        }),
+        _ => None,
+    }
+}
+
+/// One rectangle per `/QuadPoints` quadrilateral; each is the bounding box
+/// of its four corners, whatever order the producing reader wrote them in.
+pub fn quad_rects(quad_points: &[f64]) -> Vec<AnnotationRect> {
+    quad_points
+        .chunks_exact(8)
+        .map(|quad| {
+            let xs = [quad[0], quad[2], quad[4], quad[6]];
+            let ys = [quad[1], quad[3], quad[5], quad[7]];
+            let left = xs.iter().copied().fold(f64::MAX, f64::min);
+            let right = xs.iter().copied().fold(f64::MIN, f64::max);
+            let bottom = ys.iter().copied().fold(f64::MAX, f64::min);
+            let top = ys.iter().copied().fold(f64::MIN, f64::max);
+            AnnotationRect { x: left, y: bottom, width: right - left, height: top - bottom }
+        })
+        .collect()
+}
+
+/// A `/C` colour as hex; gray and CMYK are converted to RGB.
+pub fn pdf_color_to_hex(components: &[f64]) -> Option<String> {
+    let rgb = match components[..] {
+        [gray] => [gray, gray, gray],
+        [r, g, b] => [r, g, b],
+        [c, m, y, k] => [(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)],
+        _ => return None,
+    };
+    let [r, g, b] = rgb.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8);
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+fn dictionary_date(dictionary: &Dictionary, key: &[u8]) -> Option<DateTime<Utc>> {
+    parse_pdf_date(&dictionary_text(dictionary, key)?)
+}
+
+/// Parses a PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`); missing trailing fields
+/// default to their lowest value and a missing offset means UTC.
+pub fn parse_pdf_date(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.strip_prefix("D:").unwrap_or(raw);
+    let digits: String = raw.chars().take_while(char::is_ascii_digit).take(14).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+
+    let padded = format!("{}{}", digits, &"0101000000"[digits.len() - 4..]);
+    let local = NaiveDateTime::parse_from_str(&padded, "%Y%m%d%H%M%S").ok()?;
+
+    let rest: Vec<char> = raw.chars().skip(digits.len()).filter(|c| c.is_ascii_digit() || "+-Z".contains(*c)).collect();
+    let offset_minutes = match rest.first() {
+        Some(sign @ ('+' | '-')) => {
+            let numbers: String = rest[1..].iter().collect();
+            let hours: i64 = numbers.get(0..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+            let minutes: i64 = numbers.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let total = hours * 60 + minutes;
+            if *sign == '-' { -total } else { total }
+        }
+        _ => 0,
+    };
+
+    Some(local.and_utc() - chrono::Duration::minutes(offset_minutes))
+}
+
+/// Fills in the text under each highlight or underline that doesn't carry it.
+/// Skipped silently when pdfium can't be loaded: the annotation is still
+/// imported with its position and note.
+fn fill_selected_text(path: &Path, annotations: &mut [PdfAnnotation]) {
+    if annotations.iter().all(|a| a.kind == AnnotationKind::Note) {
+        return;
+    }
+
+    let pdfium = match bind_pdfium() {
+        Ok(pdfium) => pdfium,
+        Err(err) => {
+            warn!("Highlighted text not recovered from {:?}: {}", path, err);
+            return;
+        }
+    };
+    let document = match pdfium.load_pdf_from_file(path, None) {
+        Ok(document) => document,
+        Err(err) => {
+            warn!("Highlighted text not recovered from {:?}: {}", path, err);
+            return;
+        }
+    };
+
+    let mut by_page: HashMap<u32, Vec<&mut PdfAnnotation>> = HashMap::new();
+    for annotation in annotations.iter_mut().filter(|a| a.kind != AnnotationKind::Note) {
+        by_page.entry(annotation.page_number).or_default().push(annotation);
+    }
+
+    for (page_number, page_annotations) in by_page {
+        let Ok(page) = document.pages().get(page_number.saturating_sub(1) as u16) else {
+            continue;
+        };
+        let Ok(text) = page.text() else {
+            continue;
+        };
+
+        for annotation in page_annotations {
+            fill_from_page_text(&text, annotation);
+        }
+    }
+}
+
+/// Text inside the annotation's rectangles, one line per rectangle.
+fn fill_from_page_text(text: &PdfPageText, annotation: &mut PdfAnnotation) {
+    let selected: Vec<String> = annotation.rects.iter()
+        .map(|rect| {
+            text.inside_rect(PdfRect::new_from_values(
+                rect.y as f32,
+                rect.x as f32,
+                (rect.y + rect.height) as f32,
+                (rect.x + rect.width) as f32,
+            ))
+        })
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if !selected.is_empty() {
+        let selected = selected.join(" ");
+        // Some readers store the highlighted text itself as the comment.
+        if annotation.note.as_deref() == Some(selected.as_str()) {
+            annotation.note = None;
+        }
+        annotation.selected_text = Some(selected);
+    }
+}
+
+/// The annotation dictionary for one of our annotations on `page_id`: text
+/// markup when it has rectangles, a sticky note otherwise.
+pub fn build_annotation(annotation: &Annotation, page_id: ObjectId, media_box: [f64; 4], modified: &str) -> Dictionary {
+    let rects = annotation.rects.as_deref().unwrap_or_default();
+    let markup = match annotation.kind {
+        AnnotationKind::Highlight if !rects.is_empty() => Some("Highlight"),
+        AnnotationKind::Underline if !rects.is_empty() => Some("Underline"),
+        _ => None,
+    };
+
+    let mut dictionary = Dictionary::new();
+    dictionary.set("Type", Object::Name(b"Annot".to_vec()));
+    dictionary.set("P", Object::Reference(page_id));
+    dictionary.set("F", Object::Integer(4));
+    dictionary.set("T", text_string(ANNOTATION_AUTHOR));
+    dictionary.set("M", Object::string_literal(modified));
+    dictionary.set("C", Object::Array(parse_color(&annotation.color).iter().map(|c| Object::Real(*c)).collect()));
+    if let Some(id) = annotation.id {
+        dictionary.set("NM", text_string(&format!("{}{}", ANNOTATION_NAME_PREFIX, id)));
+    }
+
+    match markup {
+        Some(subtype) => {
+            dictionary.set("Subtype", Object::Name(subtype.as_bytes().to_vec()));
+            dictionary.set("Rect", number_array(&bounding_box(rects)));
+            dictionary.set("QuadPoints", number_array(&quad_points(rects)));
+            if let Some(note) = annotation.note.as_deref().filter(|n| !n.trim().is_empty()) {
+                dictionary.set("Contents", text_string(note));
+            }
+        }
+        None => {
+            // Sticky note at the annotation's first rectangle, or the top-left
+            // corner of the page when it only has a text anchor.
+            let (x, top) = match rects.first() {
+                Some(rect) => (rect.x, rect.y + rect.height),
+                None => (media_box[0] + NOTE_ICON_SIZE, media_box[3] - NOTE_ICON_SIZE),
+            };
+            dictionary.set("Subtype", Object::Name(b"Text".to_vec()));
+            dictionary.set("Rect", number_array(&[x, top - NOTE_ICON_SIZE, x + NOTE_ICON_SIZE, top]));
+            dictionary.set("Name", Object::Name(b"Comment".to_vec()));
+            dictionary.set("Open", Object::Boolean(false));
+            dictionary.set("Contents", text_string(&note_contents(annotation)));
+        }
+    }
+
+    dictionary
+}
+
+fn note_contents(annotation: &Annotation) -> String {
+    let quote = annotation.selected_text.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let note = annotation.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    match (quote, note) {
+        (Some(quote), Some(note)) => format!("\u{201c}{}\u{201d}\n\n{}", quote, note),
+        (Some(quote), None) => format!("\u{201c}{}\u{201d}", quote),
+        (None, Some(note)) => note.to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+fn attach_to_page(document: &mut PdfDocument, page_id: ObjectId, annotation_id: ObjectId) -> Result<(), PdfError> {
+    let existing = document.get_dictionary(page_id)?.get(b"Annots").ok().cloned();
+
+    match existing {
+        Some(Object::Reference(array_id)) => {
+            document.get_object_mut(array_id)?.as_array_mut()?.push(Object::Reference(annotation_id));
+        }
+        Some(Object::Array(mut annots)) => {
+            annots.push(Object::Reference(annotation_id));
+            document.get_dictionary_mut(page_id)?.set("Annots", Object::Array(annots));
+        }
+        _ => {
+            document.get_dictionary_mut(page_id)?
+                .set("Annots", Object::Array(vec![Object::Reference(annotation_id)]));
+        }
+    }
+    Ok(())
+}
+
+/// The page's `/MediaBox`, which may be inherited from its parent page tree nodes.
+fn page_media_box(document: &PdfDocument, page_id: ObjectId) -> [f64; 4] {
+    let mut node = document.get_dictionary(page_id).ok();
+    while let Some(dictionary) = node {
+        if let Ok(values) = dictionary.get(b"MediaBox").and_then(|o| document.dereference(o)).and_then(|(_, o)| o.as_array()) {
+            let numbers: Vec<f64> = values.iter().filter_map(object_to_f64).collect();
+            if let [x1, y1, x2, y2] = numbers[..] {
+                return [x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)];
+            }
+        }
+        node = dictionary.get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .ok();
+    }
+    LETTER_MEDIA_BOX
+}
+
+fn object_to_f64(object: &Object) -> Option<f64> {
+    match object {
+        Object::Integer(value) => Some(*value as f64),
+        Object::Real(value) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+fn number_array(values: &[f64]) -> Object {
+    Object::Array(values.iter().map(|v| Object::Real(*v as f32)).collect())
+}
+
+fn bounding_box(rects: &[AnnotationRect]) -> [f64; 4] {
+    let mut bounds = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    for rect in rects {
+        bounds[0] = bounds[0].min(rect.x);
+        bounds[1] = bounds[1].min(rect.y);
+        bounds[2] = bounds[2].max(rect.x + rect.width);
+        bounds[3] = bounds[3].max(rect.y + rect.height);
+    }
+    bounds
+}
+
+/// Quadrilaterals in the order viewers expect: top-left, top-right,
+/// bottom-left, bottom-right.
+pub fn quad_points(rects: &[AnnotationRect]) -> Vec<f64> {
+    rects.iter()
+        .flat_map(|r| {
+            let (left, bottom, right, top) = (r.x, r.y, r.x + r.width, r.y + r.height);
+            [left, top, right, top, left, bottom, right, bottom]
+        })
+        .collect()
+}
+
+/// `#rgb` or `#rrggbb` as PDF RGB components; anything else falls back to yellow.
+pub fn parse_color(color: &str) -> [f32; 3] {
+    let hex = color.trim().trim_start_matches('#');
+    if !hex.is_ascii() {
+        return DEFAULT_COLOR;
+    }
+    let expanded: String = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return DEFAULT_COLOR,
+    };
+
+    let mut rgb = [0.0; 3];
+    for (i, component) in rgb.iter_mut().enumerate() {
+        match u8::from_str_radix(&expanded[i * 2..i * 2 + 2], 16) {
+            Ok(value) => *component = value as f32 / 255.0,
+            Err(_) => return DEFAULT_COLOR,
+        }
+    }
+    rgb
+}
+
+fn pdf_date() -> String {
+    Utc::now().format("D:%Y%m%d%H%M%SZ").to_string()
+}
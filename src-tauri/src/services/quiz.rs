@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use crate::db::models::flashcard::Card;
+use crate::db::models::quiz::{QuestionKind, QuizQuestion};
+use crate::services::{cloze, epub};
+
+/// Options shown for a multiple choice question, the answer included.
+pub const MULTIPLE_CHOICE_OPTIONS: usize = 4;
+/// Lowest similarity at which a typed answer still counts as correct.
+pub const TYPED_ANSWER_THRESHOLD: f64 = 0.85;
+
+const TRUE_ANSWER: &str = "true";
+const FALSE_ANSWER: &str = "false";
+
+/// SplitMix64. Quizzes only need cheap, well-spread shuffles, and a seed
+/// makes a generated quiz reproducible.
+pub struct QuizRng {
+    state: u64,
+}
+
+impl QuizRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`; `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+fn plain_text(html: &str) -> String {
+    epub::html_to_text(html).lines().collect::<Vec<_>>().join(" ")
+}
+
+/// What a card asks, without markup.
+pub fn card_prompt(card: &Card) -> String {
+    plain_text(&card.front)
+}
+
+/// What a card expects as an answer, without markup. For cloze cards that is
+/// the hidden text rather than the whole sentence.
+pub fn card_answer(card: &Card) -> String {
+    if let Some(markup) = &card.cloze_text {
+        let answers = cloze::cloze_answers(markup, card.anki_ord.unwrap_or(0) + 1);
+        if !answers.is_empty() {
+            return plain_text(&answers.join(", "));
+        }
+    }
+    plain_text(&card.back)
+}
+
+/// Lowercases and drops punctuation and extra whitespace, so answers can be
+/// compared regardless of formatting.
+pub fn normalize_answer(text: &str) -> String {
+    plain_text(text)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance that also counts swapping two adjacent characters as
+/// one edit, the most common typo.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 0..a.len() {
+        current[0] = i + 1;
+        for j in 0..b.len() {
+            let substitution = previous[j] + usize::from(a[i] != b[j]);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                current[j + 1] = current[j + 1].min(before_previous[j - 1] + 1);
+            }
+        }
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Similarity of a typed answer to the expected one, from 0 to 1: one minus
+/// the edit distance between the normalized texts over the longer length.
+pub fn answer_similarity(expected: &str, response: &str) -> f64 {
+    let expected: Vec<char> = normalize_answer(expected).chars().collect();
+    let response: Vec<char> = normalize_answer(response).chars().collect();
+    let longest = expected.len().max(response.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&expected, &response) as f64 / longest as f64
+}
+
+/// Whether `response` answers `question`, and for typed questions how close
+/// it came.
+pub fn check_answer(question: &QuizQuestion, response: &str) -> (bool, Option<f64>) {
+    match question.kind {
+        QuestionKind::MultipleChoice | QuestionKind::TrueFalse => {
+            (normalize_answer(response) == normalize_answer(&question.answer), None)
+        }
+        QuestionKind::Typed => {
+            let similarity = answer_similarity(&question.answer, response);
+            (similarity >= TYPED_ANSWER_THRESHOLD, Some(similarity))
+        }
+    }
+}
+
+/// Picks up to `count` cards at random and asks each with one of `kinds`,
+/// spreading the kinds evenly. Wrong options come from the other cards'
+/// answers; a card with no distinct answer to contrast it with is asked as a
+/// typed question instead. Cards without text on both sides are left out.
+pub fn build_questions(cards: &[Card], kinds: &[QuestionKind], count: usize, rng: &mut QuizRng) -> Vec<QuizQuestion> {
+    let candidates: Vec<(&Card, String, String)> = cards.iter()
+        .map(|card| (card, card_prompt(card), card_answer(card)))
+        .filter(|(_, prompt, answer)| !prompt.is_empty() && !normalize_answer(answer).is_empty())
+        .collect();
+    if candidates.is_empty() || kinds.is_empty() {
+        return Vec::new();
+    }
+
+    let mut seen = HashSet::new();
+    let pool: Vec<&str> = candidates.iter()
+        .map(|(_, _, answer)| answer.as_str())
+        .filter(|answer| seen.insert(normalize_answer(answer)))
+        .collect();
+
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    rng.shuffle(&mut order);
+    let kind_offset = rng.below(kinds.len());
+
+    order.into_iter().take(count).enumerate().map(|(position, index)| {
+        let (card, prompt, answer) = &candidates[index];
+        let normalized = normalize_answer(answer);
+        let mut distractors: Vec<&str> = pool.iter()
+            .copied()
+            .filter(|other| normalize_answer(other) != normalized)
+            .collect();
+        rng.shuffle(&mut distractors);
+
+        let kind = match kinds[(kind_offset + position) % kinds.len()] {
+            QuestionKind::MultipleChoice | QuestionKind::TrueFalse if distractors.is_empty() => QuestionKind::Typed,
+            kind => kind,
+        };
+
+        let mut question = QuizQuestion {
+            id: None,
+            quiz_id: 0,
+            position: position as u32 + 1,
+            card_id: card.id,
+            kind,
+            prompt: prompt.clone(),
+            statement: None,
+            choices: None,
+            answer: answer.clone(),
+        };
+
+        match kind {
+            QuestionKind::MultipleChoice => {
+                let mut choices: Vec<String> = distractors.iter()
+                    .take(MULTIPLE_CHOICE_OPTIONS - 1)
+                    .map(|choice| choice.to_string())
+                    .collect();
+                choices.push(answer.clone());
+                rng.shuffle(&mut choices);
+                question.choices = Some(choices);
+            }
+            QuestionKind::TrueFalse => {
+                let truthful = rng.below(2) == 0;
+                question.statement = Some(if truthful { answer.clone() } else { distractors[0].to_string() });
+                question.answer = if truthful { TRUE_ANSWER } else { FALSE_ANSWER }.to_string();
+            }
+            QuestionKind::Typed => {}
+        }
+
+        question
+    }).collect()
+}
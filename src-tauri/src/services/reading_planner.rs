@@ -0,0 +1,36 @@
+use chrono::{Datelike, NaiveDate};
+use crate::db::models::user_available_day::DayOfWeek;
+
+/// Dates within `from..=to` that fall on one of `weekdays`.
+pub fn available_dates(from: NaiveDate, to: NaiveDate, weekdays: &[DayOfWeek]) -> Vec<NaiveDate> {
+    from.iter_days()
+        .take_while(|date| *date <= to)
+        .filter(|date| weekdays.iter().any(|day| day.weekday() == date.weekday()))
+        .collect()
+}
+
+/// Splits the units `first..=last` over `dates` as evenly as possible,
+/// earlier days taking the remainder. When there are fewer units than dates,
+/// the sessions are spread out and the remaining dates are left free.
+/// Returns `(date, start, end)` per session, in order.
+pub fn split_evenly(first: i32, last: i32, dates: &[NaiveDate]) -> Vec<(NaiveDate, i32, i32)> {
+    if last < first || dates.is_empty() {
+        return Vec::new();
+    }
+
+    let units = (last - first + 1) as i64;
+    let days = dates.len() as i64;
+    let mut sessions = Vec::new();
+    let mut start = first as i64;
+
+    for (index, date) in dates.iter().enumerate() {
+        // Round up so a short book still lands on the first day.
+        let end = first as i64 + ((index as i64 + 1) * units + days - 1) / days - 1;
+        if end >= start {
+            sessions.push((*date, start as i32, end as i32));
+            start = end + 1;
+        }
+    }
+
+    sessions
+}
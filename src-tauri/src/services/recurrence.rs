@@ -0,0 +1,136 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use crate::db::models::task::{RecurrenceFrequency, RecurrenceRule};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", date))
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+/// Lazily walks the dates of a rule in order, paired with their 1-based
+/// position in the series. Monthly rules keep the day of `starts_on` and fall
+/// back to the last day of shorter months.
+pub struct Occurrences {
+    frequency: RecurrenceFrequency,
+    interval: u64,
+    start: NaiveDate,
+    week_start: NaiveDate,
+    /// Days after Monday a weekly rule falls on, sorted.
+    offsets: Vec<u64>,
+    until: Option<NaiveDate>,
+    count: Option<u32>,
+    period: u64,
+    position: usize,
+    yielded: u32,
+    finished: bool,
+}
+
+impl Occurrences {
+    pub fn new(rule: &RecurrenceRule) -> Result<Self, String> {
+        let start = parse_date(&rule.starts_on)?;
+        let until = rule.until.as_deref().map(parse_date).transpose()?;
+
+        let mut offsets: Vec<u64> = if rule.weekdays.is_empty() {
+            vec![start.weekday().num_days_from_monday() as u64]
+        } else {
+            rule.weekdays.iter().map(|day| day.weekday().num_days_from_monday() as u64).collect()
+        };
+        offsets.sort_unstable();
+        offsets.dedup();
+
+        Ok(Self {
+            frequency: rule.frequency,
+            interval: rule.interval.max(1) as u64,
+            start,
+            week_start: start - Days::new(start.weekday().num_days_from_monday() as u64),
+            offsets,
+            until,
+            count: rule.count,
+            period: 0,
+            position: 0,
+            yielded: 0,
+            finished: false,
+        })
+    }
+
+    fn next_candidate(&mut self) -> Option<NaiveDate> {
+        match self.frequency {
+            RecurrenceFrequency::Daily => {
+                let date = self.start.checked_add_days(Days::new(self.period * self.interval));
+                self.period += 1;
+                date
+            }
+            RecurrenceFrequency::Weekly => {
+                let days = self.period * self.interval * 7 + self.offsets[self.position];
+                self.position += 1;
+                if self.position == self.offsets.len() {
+                    self.position = 0;
+                    self.period += 1;
+                }
+                self.week_start.checked_add_days(Days::new(days))
+            }
+            RecurrenceFrequency::Monthly => {
+                let months = u32::try_from(self.period * self.interval).ok()?;
+                self.period += 1;
+                self.start.checked_add_months(Months::new(months))
+            }
+        }
+    }
+}
+
+impl Iterator for Occurrences {
+    type Item = (u32, NaiveDate);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished || self.count.is_some_and(|count| self.yielded >= count) {
+            return None;
+        }
+
+        loop {
+            let Some(date) = self.next_candidate() else {
+                self.finished = true;
+                return None;
+            };
+            // The first week of a weekly rule can have days before the start.
+            if date < self.start {
+                continue;
+            }
+            if self.until.is_some_and(|until| date > until) {
+                self.finished = true;
+                return None;
+            }
+            self.yielded += 1;
+            return Some((self.yielded, date));
+        }
+    }
+}
+
+/// First occurrence strictly after `after`, if the series has one.
+pub fn next_occurrence_after(rule: &RecurrenceRule, after: NaiveDate) -> Result<Option<(u32, NaiveDate)>, String> {
+    Ok(Occurrences::new(rule)?.find(|(_, date)| *date > after))
+}
+
+/// Index of the occurrence falling on `date`, if the series has one that day.
+pub fn occurrence_on(rule: &RecurrenceRule, date: NaiveDate) -> Result<Option<u32>, String> {
+    Ok(Occurrences::new(rule)?
+        .take_while(|(_, occurrence)| *occurrence <= date)
+        .find(|(_, occurrence)| *occurrence == date)
+        .map(|(index, _)| index))
+}
+
+/// Occurrences falling within `from..=to`.
+pub fn occurrences_between(
+    rule: &RecurrenceRule,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(u32, NaiveDate)>, String> {
+    Ok(Occurrences::new(rule)?
+        .skip_while(|(_, date)| *date < from)
+        .take_while(|(_, date)| *date <= to)
+        .collect())
+}
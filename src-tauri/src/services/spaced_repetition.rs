@@ -0,0 +1,136 @@
+use crate::db::models::flashcard::{CardState, ReviewGrade, SchedulerKind};
+
+/// SM-2 never lets the ease factor drop below this.
+pub const MIN_EASE_FACTOR: f64 = 1.3;
+pub const MAX_INTERVAL_DAYS: u32 = 36500;
+
+/// Default FSRS-4.5 parameters.
+pub const FSRS_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461,
+    2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+/// Probability of recall FSRS schedules for.
+pub const FSRS_DESIRED_RETENTION: f64 = 0.9;
+const FSRS_DECAY: f64 = -0.5;
+const FSRS_FACTOR: f64 = 19.0 / 81.0;
+
+/// Applies one review to a card's state. `elapsed_days` is the time since the
+/// previous review and only matters to FSRS.
+pub fn review(kind: SchedulerKind, state: &CardState, grade: ReviewGrade, elapsed_days: u32) -> CardState {
+    match kind {
+        SchedulerKind::Sm2 => sm2(state, grade),
+        SchedulerKind::Fsrs => fsrs(state, grade, elapsed_days),
+    }
+}
+
+/// SuperMemo 2. The grades map to SM-2 qualities again = 1, hard = 3,
+/// good = 4 and easy = 5. A failed card starts over at one day; otherwise the
+/// interval goes 1, 6, then previous interval times the ease factor (before
+/// this review's adjustment), rounded up.
+pub fn sm2(state: &CardState, grade: ReviewGrade) -> CardState {
+    let quality = match grade {
+        ReviewGrade::Again => 1.0,
+        ReviewGrade::Hard => 3.0,
+        ReviewGrade::Good => 4.0,
+        ReviewGrade::Easy => 5.0,
+    };
+
+    let mut next = *state;
+    if grade == ReviewGrade::Again {
+        if state.repetitions > 0 {
+            next.lapses += 1;
+        }
+        next.repetitions = 0;
+        next.interval_days = 1;
+    } else {
+        next.interval_days = match state.repetitions {
+            0 => 1,
+            1 => 6,
+            _ => (state.interval_days as f64 * state.ease_factor).ceil() as u32,
+        };
+        next.repetitions += 1;
+    }
+
+    let miss = 5.0 - quality;
+    next.ease_factor = (state.ease_factor + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE_FACTOR);
+    next.interval_days = next.interval_days.clamp(1, MAX_INTERVAL_DAYS);
+    next
+}
+
+/// Probability of recalling a card `elapsed_days` after its last review.
+pub fn fsrs_retrievability(elapsed_days: u32, stability: f64) -> f64 {
+    if stability <= 0.0 {
+        return 0.0;
+    }
+    (1.0 + FSRS_FACTOR * elapsed_days as f64 / stability).powf(FSRS_DECAY)
+}
+
+/// Days until recall probability falls to the desired retention.
+pub fn fsrs_interval(stability: f64) -> u32 {
+    let days = stability / FSRS_FACTOR * (FSRS_DESIRED_RETENTION.powf(1.0 / FSRS_DECAY) - 1.0);
+    (days.round() as u32).clamp(1, MAX_INTERVAL_DAYS)
+}
+
+fn fsrs_initial_difficulty(rating: f64) -> f64 {
+    (FSRS_WEIGHTS[4] - (rating - 3.0) * FSRS_WEIGHTS[5]).clamp(1.0, 10.0)
+}
+
+/// FSRS-4.5 with the default weights. A card without stability is treated as
+/// new and takes its initial stability and difficulty from the grade, unless
+/// SM-2 already reviewed it: then, as for cards imported from Anki, its
+/// interval stands in for the stability and difficulty starts at the default.
+pub fn fsrs(state: &CardState, grade: ReviewGrade, elapsed_days: u32) -> CardState {
+    let w = &FSRS_WEIGHTS;
+    let rating = grade.rating() as f64;
+
+    let mut seeded = *state;
+    if seeded.stability <= 0.0 && seeded.repetitions > 0 {
+        seeded.stability = seeded.interval_days.max(1) as f64;
+        seeded.difficulty = w[4];
+    }
+    let state = &seeded;
+    let mut next = *state;
+
+    if state.stability <= 0.0 {
+        next.stability = w[grade.rating() as usize - 1];
+        next.difficulty = fsrs_initial_difficulty(rating);
+    } else {
+        let retrievability = fsrs_retrievability(elapsed_days, state.stability);
+        let difficulty = state.difficulty;
+
+        next.stability = if grade == ReviewGrade::Again {
+            let forget = w[11]
+                * difficulty.powf(-w[12])
+                * ((state.stability + 1.0).powf(w[13]) - 1.0)
+                * (w[14] * (1.0 - retrievability)).exp();
+            forget.min(state.stability)
+        } else {
+            let hard_penalty = if grade == ReviewGrade::Hard { w[15] } else { 1.0 };
+            let easy_bonus = if grade == ReviewGrade::Easy { w[16] } else { 1.0 };
+            state.stability
+                * (w[8].exp()
+                    * (11.0 - difficulty)
+                    * state.stability.powf(-w[9])
+                    * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus
+                    + 1.0)
+        };
+
+        // Mean reversion towards the initial difficulty of a card graded good.
+        let updated = difficulty - w[6] * (rating - 3.0);
+        next.difficulty = (w[7] * fsrs_initial_difficulty(3.0) + (1.0 - w[7]) * updated).clamp(1.0, 10.0);
+    }
+
+    if grade == ReviewGrade::Again {
+        if state.repetitions > 0 {
+            next.lapses += 1;
+        }
+        next.repetitions = 0;
+        next.interval_days = 1;
+    } else {
+        next.repetitions += 1;
+        next.interval_days = fsrs_interval(next.stability);
+    }
+    next
+}
@@ -0,0 +1,84 @@
+use log::{error, info, warn};
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use crate::db::repositories::PageTextRepository;
+use crate::services::{epub::read_all_chapter_texts, pdf::extract_page_texts};
+
+/// Page-level text of a stored file. EPUB chapters count as pages, numbered from 1.
+pub fn extract_document_pages(path: &Path, mime_type: &str) -> Result<Vec<(i32, String)>, String> {
+    match mime_type {
+        "application/pdf" => extract_page_texts(path)
+            .map(|pages| pages.into_iter().map(|(page, text)| (page as i32, text)).collect())
+            .map_err(|err| err.to_string()),
+        "application/epub+zip" => read_all_chapter_texts(path)
+            .map(|chapters| chapters.into_iter().map(|(index, text)| (index + 1, text)).collect())
+            .map_err(|err| err.to_string()),
+        _ => Err(format!("Unsupported file type: {}", mime_type)),
+    }
+}
+
+/// Extracts and indexes the text of every document not indexed yet.
+/// Returns how many documents were indexed.
+pub fn index_pending_documents(db_conn: &Arc<Mutex<Connection>>) -> usize {
+    let pending = {
+        let mut conn = db_conn.lock().unwrap();
+        match PageTextRepository::new(&mut conn).get_unindexed_documents() {
+            Ok(pending) => pending,
+            Err(err) => {
+                error!("Failed to list documents to index: {}", err);
+                return 0;
+            }
+        }
+    };
+
+    let mut indexed = 0;
+
+    for (document_id, file_path, mime_type) in pending {
+        // Extraction runs without the database lock; large books take a while.
+        let pages = match extract_document_pages(Path::new(&file_path), &mime_type) {
+            Ok(pages) => pages,
+            Err(err) => {
+                // Still mark it indexed (with no pages) so it is not retried on every launch.
+                warn!("Could not extract text from document {}: {}", document_id, err);
+                Vec::new()
+            }
+        };
+
+        let mut conn = db_conn.lock().unwrap();
+        match PageTextRepository::new(&mut conn).replace_document_pages(document_id, &pages) {
+            Ok(()) => indexed += 1,
+            Err(err) => error!("Failed to index document {}: {}", document_id, err),
+        }
+    }
+
+    indexed
+}
+
+pub fn spawn_text_indexing(db_conn: Arc<Mutex<Connection>>) {
+    thread::spawn(move || {
+        let indexed = index_pending_documents(&db_conn);
+        if indexed > 0 {
+            info!("Indexed the text of {} documents", indexed);
+        }
+    });
+}
+
+/// Turns free text typed by the user into a safe FTS5 query: every word is
+/// quoted (so operators and punctuation cannot break the syntax) and the last
+/// one is matched as a prefix to support search-as-you-type.
+pub fn build_match_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"", term))
+        .collect();
+
+    if terms.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}*", terms.join(" ")))
+}
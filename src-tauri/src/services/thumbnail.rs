@@ -0,0 +1,194 @@
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use log::{error, info, warn};
+use pdfium_render::prelude::*;
+use rusqlite::Connection;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use thiserror::Error;
+use crate::db::models::document::Document;
+use crate::db::repositories::DocumentRepository;
+use crate::services::epub::read_epub;
+
+pub const THUMBNAIL_WIDTH: u32 = 300;
+pub const THUMBNAIL_MAX_HEIGHT: u32 = 450;
+
+/// Folder holding the pdfium library bundled with the app, once known.
+static PDFIUM_LIBRARY_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum ThumbnailError {
+    #[error("PDF renderer unavailable: {0}")]
+    RendererUnavailable(String),
+
+    #[error("Render error: {0}")]
+    Render(String),
+
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Unsupported file type: {0}")]
+    UnsupportedFileType(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl From<PdfiumError> for ThumbnailError {
+    fn from(err: PdfiumError) -> Self {
+        ThumbnailError::Render(err.to_string())
+    }
+}
+
+/// Renders covers into a cache folder of PNGs named after the document hash.
+#[derive(Debug, Clone)]
+pub struct ThumbnailService {
+    dir: PathBuf,
+}
+
+impl ThumbnailService {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn path_for(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.png", hash))
+    }
+
+    pub fn generate(&self, source: &Path, mime_type: &str, hash: &str) -> Result<PathBuf, ThumbnailError> {
+        let cover = match mime_type {
+            "application/pdf" => render_pdf_first_page(source)?,
+            "application/epub+zip" => {
+                let cover = read_epub(source)
+                    .map_err(|err| ThumbnailError::Render(err.to_string()))?
+                    .cover
+                    .ok_or_else(|| ThumbnailError::Render("EPUB has no cover image".to_string()))?;
+                image::load_from_memory(&cover.bytes)?
+            }
+            _ => return Err(ThumbnailError::UnsupportedFileType(mime_type.to_string())),
+        };
+        self.save(&cover, hash)
+    }
+
+    fn save(&self, cover: &DynamicImage, hash: &str) -> Result<PathBuf, ThumbnailError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(hash);
+        cover
+            .resize(THUMBNAIL_WIDTH, THUMBNAIL_MAX_HEIGHT, FilterType::Triangle)
+            .save_with_format(&path, ImageFormat::Png)?;
+        Ok(path)
+    }
+}
+
+/// Points [`bind_pdfium`] at the bundled library. Tauri only knows the
+/// resource folder once the app is set up, so this is called from there.
+pub fn set_pdfium_library_dir(dir: PathBuf) {
+    if PDFIUM_LIBRARY_DIR.set(dir).is_err() {
+        warn!("The pdfium library folder was already set");
+    }
+}
+
+pub fn bind_pdfium() -> Result<Pdfium, ThumbnailError> {
+    // Prefer the library bundled with the app, then the system one.
+    let bindings = match PDFIUM_LIBRARY_DIR.get() {
+        Some(dir) => Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(dir))
+            .or_else(|_| Pdfium::bind_to_system_library()),
+        None => Pdfium::bind_to_system_library(),
+    }
+    .map_err(|err| ThumbnailError::RendererUnavailable(err.to_string()))?;
+    Ok(Pdfium::new(bindings))
+}
+
+fn render_pdf_first_page(source: &Path) -> Result<DynamicImage, ThumbnailError> {
+    let pdfium = bind_pdfium()?;
+    let document = pdfium.load_pdf_from_file(source, None)?;
+    let page = document.pages().get(0)?;
+
+    let config = PdfRenderConfig::new()
+        .set_target_width(THUMBNAIL_WIDTH as i32 * 2)
+        .set_maximum_height(THUMBNAIL_MAX_HEIGHT as i32 * 2);
+
+    let image = page.render_with_config(&config)?.as_image();
+    Ok(image)
+}
+
+/// Generates thumbnails for every document whose cached PNG is missing and
+/// records the new paths. Returns how many were generated.
+pub fn refresh_missing_thumbnails(db_conn: &Arc<Mutex<Connection>>, service: &ThumbnailService) -> usize {
+    let documents = {
+        let conn = db_conn.lock().unwrap();
+        match DocumentRepository::new(&conn).get_all() {
+            Ok(documents) => documents,
+            Err(err) => {
+                error!("Failed to list documents for thumbnails: {}", err);
+                return 0;
+            }
+        }
+    };
+
+    documents
+        .iter()
+        .filter(|document| {
+            !document
+                .thumbnail_path
+                .as_ref()
+                .is_some_and(|path| Path::new(path).is_file())
+        })
+        .filter(|document| generate_and_record(db_conn, service, document))
+        .count()
+}
+
+/// Renders the cover of one document, e.g. right after it was imported, and
+/// records its path. Returns whether a thumbnail was generated.
+pub fn refresh_document_thumbnail(db_conn: &Arc<Mutex<Connection>>, service: &ThumbnailService, document_id: i32) -> bool {
+    let document = {
+        let conn = db_conn.lock().unwrap();
+        DocumentRepository::new(&conn).get_by_id(document_id)
+    };
+    match document {
+        Ok(Some(document)) => generate_and_record(db_conn, service, &document),
+        Ok(None) => false,
+        Err(err) => {
+            error!("Failed to load document {} for its thumbnail: {}", document_id, err);
+            false
+        }
+    }
+}
+
+fn generate_and_record(db_conn: &Arc<Mutex<Connection>>, service: &ThumbnailService, document: &Document) -> bool {
+    // Render without holding the database lock; covers can take a while.
+    let path = match service.generate(Path::new(&document.file_path), &document.mime_type, &document.hash) {
+        Ok(path) => path,
+        Err(err) => {
+            warn!("No thumbnail for document {}: {}", document.id, err);
+            return false;
+        }
+    };
+
+    let conn = db_conn.lock().unwrap();
+    let path_str = path.to_string_lossy().to_string();
+    match DocumentRepository::new(&conn).set_thumbnail_path(document.id, Some(&path_str)) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("Failed to record thumbnail for document {}: {}", document.id, err);
+            false
+        }
+    }
+}
+
+pub fn spawn_thumbnail_refresh(db_conn: Arc<Mutex<Connection>>, service: ThumbnailService) {
+    thread::spawn(move || {
+        let generated = refresh_missing_thumbnails(&db_conn, &service);
+        if generated > 0 {
+            info!("Generated {} missing thumbnails", generated);
+        }
+    });
+}
+
+pub fn spawn_document_thumbnail(db_conn: Arc<Mutex<Connection>>, service: ThumbnailService, document_id: i32) {
+    thread::spawn(move || {
+        refresh_document_thumbnail(&db_conn, &service, document_id);
+    });
+}
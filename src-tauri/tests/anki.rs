@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fs;
+
+use app_lib::commands::anki_commands::AnkiCommands;
+use app_lib::commands::flashcard_commands::FlashcardCommands;
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::repositories::{FlashcardRepository, TagRepository};
+use app_lib::services::anki::{
+    media_references, read_package, render_template, write_package, AnkiCard, AnkiDeck, AnkiMedia, AnkiModel,
+    AnkiNote, AnkiPackage, AnkiReview, CARD_TYPE_NEW, CARD_TYPE_REVIEW, QUEUE_NEW, QUEUE_REVIEW,
+};
+use chrono::{NaiveDate, TimeZone, Utc};
+use rusqlite::Connection;
+
+fn note(id: i64, model_id: i64, fields: &[&str]) -> AnkiNote {
+    AnkiNote {
+        id,
+        guid: format!("guid-{}", id),
+        model_id,
+        tags: vec!["math".to_string()],
+        fields: fields.iter().map(|field| field.to_string()).collect(),
+    }
+}
+
+#[test]
+fn template_renders_fields_sections_and_filters() {
+    let fields = HashMap::from([("Front", "Rank"), ("Back", "dim im T"), ("Hint", "")]);
+    let template = "{{Front}}{{#Hint}} ({{Hint}}){{/Hint}}{{^Hint}}!{{/Hint}} {{text:Back}}{{type:Back}}";
+    assert_eq!(render_template(template, &fields), "Rank! dim im T");
+}
+
+#[test]
+fn models_render_basic_and_cloze_cards() {
+    let basic = AnkiModel::basic(1);
+    let (front, back) = basic.render(&note(1, 1, &["Kernel", "Vectors sent to zero"]), 0);
+    assert_eq!(front, "Kernel");
+    assert_eq!(back, "Vectors sent to zero");
+
+    let cloze = AnkiModel::cloze(2);
+    let text = note(2, 2, &["{{c1::Rank}} plus {{c2::nullity}} is n", ""]);
+    assert_eq!(cloze.render(&text, 1), ("Rank plus [...] is n".to_string(), "Rank plus nullity is n".to_string()));
+}
+
+#[test]
+fn media_references_find_images_and_sounds() {
+    let field = r#"<img src="basis.png"> and <img src='span.jpg'> [sound:lecture.mp3]"#;
+    assert_eq!(media_references(field), vec!["basis.png", "span.jpg", "lecture.mp3"]);
+}
+
+#[test]
+fn package_round_trips_through_apkg() {
+    let created = Utc.with_ymd_and_hms(2024, 3, 1, 4, 0, 0).unwrap();
+    let package = AnkiPackage {
+        created,
+        models: vec![AnkiModel::basic(1), AnkiModel::cloze(2)],
+        decks: vec![AnkiDeck { id: 10, name: "Linear Algebra".to_string() }],
+        notes: vec![
+            note(100, 1, &["Kernel", "<img src=\"kernel.png\">"]),
+            note(101, 2, &["{{c1::Rank}} plus nullity", ""]),
+        ],
+        cards: vec![
+            AnkiCard {
+                id: 1000, note_id: 100, deck_id: 10, ord: 0, card_type: CARD_TYPE_REVIEW, queue: QUEUE_REVIEW,
+                due: 12, interval: 6, factor: 2500, reps: 2, lapses: 0,
+            },
+            AnkiCard {
+                id: 1001, note_id: 101, deck_id: 10, ord: 0, card_type: CARD_TYPE_NEW, queue: QUEUE_NEW,
+                due: 1, interval: 0, factor: 0, reps: 0, lapses: 0,
+            },
+        ],
+        reviews: vec![AnkiReview {
+            card_id: 1000,
+            reviewed_at: Utc.with_ymd_and_hms(2024, 3, 7, 9, 30, 0).unwrap(),
+            ease: 3,
+            interval: 6,
+            last_interval: 1,
+            factor: 2500,
+            kind: 1,
+        }],
+        media: vec![AnkiMedia { name: "kernel.png".to_string(), data: vec![0x89, b'P', b'N', b'G'] }],
+    };
+
+    let path = std::env::temp_dir().join(format!("study-studio-anki-test-{}.apkg", std::process::id()));
+    write_package(&path, &package).unwrap();
+    let read = read_package(&path);
+    fs::remove_file(&path).unwrap();
+    let read = read.unwrap();
+
+    assert_eq!(read.decks.iter().filter(|deck| deck.name == "Linear Algebra").count(), 1);
+    assert_eq!(read.notes.len(), 2);
+    assert_eq!(read.notes[0].fields, package.notes[0].fields);
+    assert_eq!(read.notes[1].tags, vec!["math"]);
+    assert_eq!(read.cards.len(), 2);
+    assert_eq!(read.reviews.len(), 1);
+    assert_eq!(read.reviews[0].reviewed_at, package.reviews[0].reviewed_at);
+    assert_eq!(read.media.len(), 1);
+    assert_eq!(read.media[0].name, "kernel.png");
+    assert_eq!(read.media[0].data, package.media[0].data);
+
+    let review_card = read.cards.iter().find(|card| card.note_id == 100).unwrap();
+    let today = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+    assert_eq!(review_card.interval, 6);
+    assert_eq!(review_card.due_date(read.created, today), created.with_timezone(&chrono::Local).date_naive() + chrono::Duration::days(12));
+
+    let model = read.models.iter().find(|model| model.id == 2).unwrap();
+    let cloze_note = read.notes.iter().find(|note| note.id == 101).unwrap();
+    assert_eq!(model.render(cloze_note, 0).0, "[...] plus nullity");
+}
+
+#[test]
+fn export_only_packs_media_from_the_media_folder() {
+    let root = std::env::temp_dir().join(format!("study-studio-anki-media-{}", std::process::id()));
+    let media_dir = root.join("media");
+    fs::create_dir_all(&media_dir).unwrap();
+    fs::write(media_dir.join("kernel.png"), b"kernel").unwrap();
+    fs::write(root.join("secret.txt"), b"secret").unwrap();
+
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    let deck = FlashcardCommands::new(&mut conn).create_deck_method("Linear Algebra".to_string(), None, None).unwrap();
+    FlashcardCommands::new(&mut conn)
+        .create_card_method(
+            deck.id.unwrap(),
+            "<img src=\"kernel.png\"><img src=\"../secret.txt\">".to_string(),
+            format!("<img src=\"{}\">", root.join("secret.txt").display()),
+            None,
+            None,
+        )
+        .unwrap();
+
+    let path = root.join("export.apkg");
+    let summary = AnkiCommands::new(&mut conn)
+        .export_anki_package_method(vec![deck.id.unwrap()], path.to_string_lossy().to_string(), &media_dir);
+    let read = read_package(&path);
+    fs::remove_dir_all(&root).unwrap();
+
+    summary.unwrap();
+    let names: Vec<String> = read.unwrap().media.into_iter().map(|media| media.name).collect();
+    assert_eq!(names, vec!["kernel.png"]);
+}
+
+/// A deck with a review card whose history was left out of the package and
+/// a new card.
+fn package_without_history() -> AnkiPackage {
+    AnkiPackage {
+        created: Utc.with_ymd_and_hms(2024, 3, 1, 4, 0, 0).unwrap(),
+        models: vec![AnkiModel::basic(1)],
+        decks: vec![AnkiDeck { id: 10, name: "Linear Algebra".to_string() }],
+        notes: vec![note(100, 1, &["Kernel", "Vectors sent to zero"]), note(101, 1, &["Rank", "dim im T"])],
+        cards: vec![
+            AnkiCard {
+                id: 1000, note_id: 100, deck_id: 10, ord: 0, card_type: CARD_TYPE_REVIEW, queue: QUEUE_REVIEW,
+                due: 12, interval: 6, factor: 2500, reps: 3, lapses: 0,
+            },
+            AnkiCard {
+                id: 1001, note_id: 101, deck_id: 10, ord: 0, card_type: CARD_TYPE_NEW, queue: QUEUE_NEW,
+                due: 1, interval: 0, factor: 0, reps: 0, lapses: 0,
+            },
+        ],
+        ..AnkiPackage::default()
+    }
+}
+
+#[test]
+fn review_cards_without_history_stay_reviewed() {
+    let root = std::env::temp_dir().join(format!("study-studio-anki-history-{}", std::process::id()));
+    let media_dir = root.join("media");
+    fs::create_dir_all(&root).unwrap();
+    let source = root.join("source.apkg");
+    write_package(&source, &package_without_history()).unwrap();
+
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    let summary = AnkiCommands::new(&mut conn).import_anki_package_method(source.to_string_lossy().to_string(), &media_dir);
+    let exported = root.join("exported.apkg");
+    let export = AnkiCommands::new(&mut conn).export_anki_package_method(Vec::new(), exported.to_string_lossy().to_string(), &media_dir);
+    let read = read_package(&exported);
+    fs::remove_dir_all(&root).unwrap();
+
+    let summary = summary.unwrap();
+    assert_eq!((summary.cards, summary.reviews), (2, 0));
+    let deck_id = summary.decks[0].id.unwrap();
+    let statistics = FlashcardRepository::new(&mut conn).get_deck_statistics(deck_id, "2024-03-01", "2024-02-01").unwrap();
+    assert_eq!((statistics.total_cards, statistics.new_cards), (2, 1));
+
+    export.unwrap();
+    let read = read.unwrap();
+    let types: HashMap<String, (i64, i64, i64)> = read.cards.iter()
+        .map(|card| {
+            let guid = read.notes.iter().find(|note| note.id == card.note_id).unwrap().guid.clone();
+            (guid, (card.card_type, card.queue, card.interval))
+        })
+        .collect();
+    assert_eq!(types["guid-100"], (CARD_TYPE_REVIEW, QUEUE_REVIEW, 6));
+    assert_eq!(types["guid-101"], (CARD_TYPE_NEW, QUEUE_NEW, 0));
+}
+
+#[test]
+fn failed_import_creates_no_decks_or_tags() {
+    let path = std::env::temp_dir().join(format!("study-studio-anki-failed-{}.apkg", std::process::id()));
+    write_package(&path, &package_without_history()).unwrap();
+
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute_batch("CREATE TRIGGER reject_rank BEFORE INSERT ON cards WHEN NEW.front = 'Rank' BEGIN SELECT RAISE(ABORT, 'rejected'); END;")
+        .unwrap();
+    let media_dir = std::env::temp_dir().join(format!("study-studio-anki-failed-media-{}", std::process::id()));
+    let result = AnkiCommands::new(&mut conn).import_anki_package_method(path.to_string_lossy().to_string(), &media_dir);
+    fs::remove_file(&path).unwrap();
+
+    assert!(result.is_err());
+    assert!(FlashcardRepository::new(&mut conn).get_decks().unwrap().is_empty());
+    assert!(TagRepository::new(&mut conn).get_all_tags().unwrap().is_empty());
+}
@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use app_lib::commands::annotation_export_commands::AnnotationExportCommands;
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::annotation::{Annotation, AnnotationKind, AnnotationRect};
+use app_lib::db::models::{book::Book, document::Document, tag::Tag};
+use app_lib::db::repositories::{AnnotationRepository, BookRepository, DocumentRepository, TagRepository};
+use rusqlite::Connection;
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("study-studio-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn tag(conn: &mut Connection, title: &str) -> Tag {
+    let tag = Tag { id: None, title: title.to_string(), color: "#336699".to_string(), icon: None };
+    let id = TagRepository::new(conn).create_tag(&tag).unwrap();
+    Tag { id: Some(id as i32), ..tag }
+}
+
+/// Adds a book backed by a stored document with the given content hash.
+fn add_book(conn: &mut Connection, title: &str, author: Option<&str>, hash: &str, tags: Vec<Tag>) -> i64 {
+    let document = Document {
+        id: 0,
+        title: title.to_string(),
+        original_filename: format!("{}.pdf", title),
+        stored_filename: format!("{}.pdf", hash),
+        file_path: format!("/library/{}.pdf", hash),
+        file_size: "1024".to_string(),
+        mime_type: "application/pdf".to_string(),
+        hash: hash.to_string(),
+        page_count: 10,
+        created_at: None,
+        last_accessed: None,
+        thumbnail_path: None,
+        tags: None,
+        subject: None,
+        keywords: None,
+        language: None,
+        identifiers: None,
+    };
+    let document_id = DocumentRepository::new(conn).create(&document).unwrap();
+    let book = Book {
+        id: 0,
+        title: title.to_string(),
+        author: author.map(str::to_string),
+        file_path: Some(document.file_path.clone()),
+        document_id: Some(document_id),
+        tags: Some(tags),
+    };
+    BookRepository::insert_book(conn, &book).unwrap()
+}
+
+fn annotation(book_id: i64, kind: AnnotationKind, page_number: i32, text: Option<&str>, note: Option<&str>, tags: Vec<Tag>) -> Annotation {
+    Annotation {
+        id: None,
+        book_id,
+        kind,
+        page_number: Some(page_number),
+        chapter_index: None,
+        location: None,
+        rects: Some(vec![AnnotationRect { x: 72.0, y: 600.0, width: 144.5, height: 11.0 }]),
+        start_offset: Some(page_number * 10),
+        end_offset: text.map(|t| page_number * 10 + t.len() as i32),
+        selected_text: text.map(str::to_string),
+        color: "#ffeb3b".to_string(),
+        note: note.map(str::to_string),
+        created_at: None,
+        updated_at: None,
+        pdf_ref: None,
+        tags: Some(tags),
+    }
+}
+
+/// A book with a highlight on page 2, a tagged note and a tagged underline on page 5.
+fn annotated_library() -> (Connection, i64) {
+    let mut conn = setup();
+    let math = tag(&mut conn, "math");
+    let exam = tag(&mut conn, "exam prep");
+    let book_id = add_book(&mut conn, "Linear Algebra", Some("Sheldon Axler"), "abc123", vec![math.clone()]);
+
+    let mut repository = AnnotationRepository::new(&mut conn);
+    for annotation in [
+        annotation(book_id, AnnotationKind::Underline, 5, Some("Every operator has an eigenvalue."), None, vec![math]),
+        annotation(book_id, AnnotationKind::Highlight, 2, Some("A vector space is a set\nwith addition"), Some("definition"), Vec::new()),
+        annotation(book_id, AnnotationKind::Note, 5, None, Some("Revisit before the exam"), vec![exam]),
+    ] {
+        repository.create_annotation(&annotation).unwrap();
+    }
+    (conn, book_id)
+}
+
+fn export(conn: &mut Connection, book_id: i64, format: &str, dir: &Path) -> String {
+    let files = AnnotationExportCommands::new(conn)
+        .export_annotations_method(Some(book_id), None, format.to_string(), dir.to_string_lossy().to_string())
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].annotation_count, 3);
+    files[0].path.clone()
+}
+
+type Comparable = (AnnotationKind, Option<i32>, Option<Vec<AnnotationRect>>, Option<String>, Option<String>, Vec<String>);
+
+/// The fields an annotation keeps across export and import, IDs and timestamps aside.
+fn comparable(annotations: Vec<Annotation>) -> Vec<Comparable> {
+    annotations
+        .into_iter()
+        .map(|a| {
+            let tags = a.tags.unwrap_or_default().into_iter().map(|t| t.title).collect();
+            (a.kind, a.page_number, a.rects, a.selected_text, a.note, tags)
+        })
+        .collect()
+}
+
+#[test]
+fn json_export_imports_into_the_book_with_the_same_file() {
+    let dir = scratch_dir("annotation-json");
+    let (mut source, source_book) = annotated_library();
+    let path = export(&mut source, source_book, "json", &dir);
+    let exported = AnnotationRepository::new(&mut source).get_annotations_by_book_id(source_book, None).unwrap();
+
+    let mut target = setup();
+    let exam = tag(&mut target, "exam prep");
+    add_book(&mut target, "Unrelated", None, "fff000", Vec::new());
+    let target_book = add_book(&mut target, "Axler", None, "abc123", Vec::new());
+
+    let summary = AnnotationExportCommands::new(&mut target).import_annotations_method(path.clone(), None).unwrap();
+    assert_eq!((summary.book_id, summary.imported, summary.skipped), (target_book, 3, 0));
+
+    let imported = AnnotationRepository::new(&mut target).get_annotations_by_book_id(target_book, None).unwrap();
+    assert_eq!(comparable(imported.clone()), comparable(exported.clone()));
+    assert_eq!(
+        imported.iter().map(|a| a.created_at).collect::<Vec<_>>(),
+        exported.iter().map(|a| a.created_at).collect::<Vec<_>>()
+    );
+
+    // Existing tags are reused by title and missing ones created.
+    let tags = TagRepository::new(&mut target).get_all_tags().unwrap();
+    assert_eq!(tags.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["exam prep", "math"]);
+    assert_eq!(imported[2].tags.as_ref().unwrap()[0].id, exam.id);
+
+    let again = AnnotationExportCommands::new(&mut target).import_annotations_method(path, Some(target_book)).unwrap();
+    assert_eq!((again.imported, again.skipped), (0, 3));
+    assert_eq!(AnnotationRepository::new(&mut target).get_annotations_by_book_id(target_book, None).unwrap().len(), 3);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_json_import_creates_no_tags() {
+    let dir = scratch_dir("annotation-json-failed");
+    let (mut source, source_book) = annotated_library();
+    let path = export(&mut source, source_book, "json", &dir);
+
+    let mut target = setup();
+    let target_book = add_book(&mut target, "Axler", None, "abc123", Vec::new());
+    target
+        .execute_batch("CREATE TRIGGER reject_notes BEFORE INSERT ON annotations WHEN NEW.kind = 'note' BEGIN SELECT RAISE(ABORT, 'rejected'); END;")
+        .unwrap();
+
+    let result = AnnotationExportCommands::new(&mut target).import_annotations_method(path, Some(target_book));
+    assert!(result.is_err());
+    assert!(TagRepository::new(&mut target).get_all_tags().unwrap().is_empty());
+    assert!(AnnotationRepository::new(&mut target).get_annotations_by_book_id(target_book, None).unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn markdown_export_groups_annotations_by_page() {
+    let dir = scratch_dir("annotation-markdown");
+    let (mut conn, book_id) = annotated_library();
+    let path = export(&mut conn, book_id, "markdown", &dir);
+
+    assert!(path.ends_with("Linear Algebra.md"), "{}", path);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# Linear Algebra\n\
+         \n\
+         *Sheldon Axler*\n\
+         \n\
+         ## Page 2\n\
+         \n\
+         > A vector space is a set\n\
+         > with addition\n\
+         \n\
+         (p. 2)\n\
+         \n\
+         definition\n\
+         \n\
+         ## Page 5\n\
+         \n\
+         > Every operator has an eigenvalue.\n\
+         \n\
+         (p. 5)\n\
+         \n\
+         #math\n\
+         \n\
+         **Note** (p. 5)\n\
+         \n\
+         Revisit before the exam\n\
+         \n\
+         #exam-prep\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn obsidian_export_adds_front_matter_and_block_ids() {
+    let dir = scratch_dir("annotation-obsidian");
+    let (mut conn, book_id) = annotated_library();
+    let path = export(&mut conn, book_id, "obsidian", &dir);
+    let ids: Vec<i64> = AnnotationRepository::new(&mut conn)
+        .get_annotations_by_book_id(book_id, None)
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id.unwrap())
+        .collect();
+
+    let contents = fs::read_to_string(&path).unwrap();
+    let (front_matter, body) = contents.strip_prefix("---\n").unwrap().split_once("---\n\n").unwrap();
+
+    assert!(front_matter.starts_with(
+        "title: \"Linear Algebra\"\n\
+         author: \"Sheldon Axler\"\n\
+         tags:\n  - \"math\"\n  - \"exam-prep\"\n\
+         source: study-studio\n\
+         exported_at: \""
+    ), "{}", front_matter);
+    assert!(body.starts_with("# Linear Algebra\n\n## Page 2\n"), "{}", body);
+    assert!(!body.contains("*Sheldon Axler*"));
+    for (id, reference) in ids.iter().zip(["(p. 2)", "(p. 5)", "**Note** (p. 5)"]) {
+        assert!(body.contains(&format!("{} ^annotation-{}\n", reference, id)), "{}", body);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
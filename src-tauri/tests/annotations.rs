@@ -0,0 +1,163 @@
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::annotation::{Annotation, AnnotationKind, AnnotationRect, DEFAULT_ANNOTATION_COLOR};
+use app_lib::db::models::tag::Tag;
+use app_lib::db::repositories::AnnotationRepository;
+use chrono::{TimeZone, Utc};
+use rusqlite::{params, Connection};
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute_batch(
+        "INSERT INTO books (title, file_path) VALUES ('Algebra', '/library/algebra.pdf');
+         INSERT INTO books (title, file_path) VALUES ('Mechanics', '/library/mechanics.pdf');
+         INSERT INTO tags (title, color) VALUES ('math', '#ff0000');
+         INSERT INTO tags (title, color) VALUES ('exam', '#00ff00');
+         INSERT INTO book_tags (book_id, tag_id) VALUES (2, 1);",
+    )
+    .unwrap();
+    conn
+}
+
+fn tag(id: i32) -> Tag {
+    Tag { id: Some(id), title: String::new(), color: String::new(), icon: None }
+}
+
+fn highlight(book_id: i64, page_number: i32, start_offset: i32, text: &str) -> Annotation {
+    Annotation {
+        id: None,
+        book_id,
+        kind: AnnotationKind::Highlight,
+        page_number: Some(page_number),
+        chapter_index: None,
+        location: None,
+        rects: None,
+        start_offset: Some(start_offset),
+        end_offset: Some(start_offset + text.len() as i32),
+        selected_text: Some(text.to_string()),
+        color: DEFAULT_ANNOTATION_COLOR.to_string(),
+        note: None,
+        created_at: None,
+        updated_at: None,
+        pdf_ref: None,
+        tags: None,
+    }
+}
+
+fn texts(annotations: Vec<Annotation>) -> Vec<String> {
+    annotations.into_iter().map(|a| a.selected_text.unwrap()).collect()
+}
+
+#[test]
+fn annotations_round_trip_with_rects_and_tags() {
+    let mut conn = setup();
+    let created_at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap();
+    let rects = vec![
+        AnnotationRect { x: 72.0, y: 700.5, width: 200.0, height: 12.0 },
+        AnnotationRect { x: 72.0, y: 686.5, width: 120.25, height: 12.0 },
+    ];
+    let annotation = Annotation {
+        kind: AnnotationKind::Underline,
+        rects: Some(rects.clone()),
+        note: Some("see chapter 3".to_string()),
+        created_at: Some(created_at),
+        tags: Some(vec![tag(1), Tag { id: None, ..tag(2) }]),
+        ..highlight(1, 4, 10, "a group is a set")
+    };
+
+    let mut repository = AnnotationRepository::new(&mut conn);
+    let id = repository.create_annotation(&annotation).unwrap();
+    let stored = repository.get_annotation_by_id(id).unwrap().unwrap();
+
+    assert_eq!(stored.id, Some(id));
+    assert_eq!(stored.kind, AnnotationKind::Underline);
+    assert_eq!(stored.rects, Some(rects));
+    assert_eq!(stored.note.as_deref(), Some("see chapter 3"));
+    assert_eq!(stored.created_at, Some(created_at));
+    assert!(stored.updated_at.unwrap() > created_at);
+    // Tags without an ID are not linked.
+    let tags: Vec<String> = stored.tags.unwrap().into_iter().map(|t| t.title).collect();
+    assert_eq!(tags, vec!["math"]);
+
+    let updated = Annotation {
+        kind: AnnotationKind::Note,
+        rects: None,
+        color: "#4caf50".to_string(),
+        note: None,
+        ..repository.get_annotation_by_id(id).unwrap().unwrap()
+    };
+    assert_eq!(repository.update_annotation(&updated).unwrap(), 1);
+    let stored = repository.get_annotation_by_id(id).unwrap().unwrap();
+    assert_eq!((stored.kind, stored.rects, stored.color.as_str()), (AnnotationKind::Note, None, "#4caf50"));
+    assert!(stored.note.is_none());
+    assert_eq!(stored.created_at, Some(created_at));
+
+    assert!(repository.get_annotation_by_id(id + 1).unwrap().is_none());
+}
+
+#[test]
+fn book_annotations_are_listed_in_reading_order() {
+    let mut conn = setup();
+    let mut repository = AnnotationRepository::new(&mut conn);
+    for annotation in [
+        highlight(1, 3, 0, "third page"),
+        highlight(1, 1, 40, "later on page one"),
+        highlight(2, 1, 0, "other book"),
+        highlight(1, 1, 5, "early on page one"),
+        highlight(1, 3, 0, "third page again"),
+    ] {
+        repository.create_annotation(&annotation).unwrap();
+    }
+
+    assert_eq!(
+        texts(repository.get_annotations_by_book_id(1, None).unwrap()),
+        vec!["early on page one", "later on page one", "third page", "third page again"]
+    );
+    assert_eq!(
+        texts(repository.get_annotations_by_book_id(1, Some(3)).unwrap()),
+        vec!["third page", "third page again"]
+    );
+    assert!(repository.get_annotations_by_book_id(1, Some(2)).unwrap().is_empty());
+}
+
+#[test]
+fn tag_lookup_includes_annotations_of_tagged_books() {
+    let mut conn = setup();
+    let mut repository = AnnotationRepository::new(&mut conn);
+    let tagged = repository.create_annotation(&highlight(1, 2, 0, "tagged directly")).unwrap();
+    repository.create_annotation(&highlight(1, 1, 0, "untagged")).unwrap();
+    repository.create_annotation(&highlight(2, 5, 0, "in a math book")).unwrap();
+
+    repository.add_tags_to_annotation(tagged, vec![tag(1), tag(2), tag(1)]).unwrap();
+    assert_eq!(repository.get_tags_by_annotation_id(tagged).unwrap().len(), 2);
+
+    assert_eq!(texts(repository.get_annotations_by_tag_id(1).unwrap()), vec!["tagged directly", "in a math book"]);
+    assert_eq!(texts(repository.get_annotations_by_tag_id(2).unwrap()), vec!["tagged directly"]);
+
+    repository.remove_tags_from_annotation(tagged, vec![tag(1)]).unwrap();
+    assert_eq!(texts(repository.get_annotations_by_tag_id(1).unwrap()), vec!["in a math book"]);
+    let remaining: Vec<Option<i32>> = repository.get_tags_by_annotation_id(tagged).unwrap().into_iter().map(|t| t.id).collect();
+    assert_eq!(remaining, vec![Some(2)]);
+}
+
+#[test]
+fn deleting_an_annotation_or_its_book_drops_its_tag_links() {
+    let mut conn = setup();
+    let mut repository = AnnotationRepository::new(&mut conn);
+    let first = repository.create_annotation(&Annotation { tags: Some(vec![tag(1)]), ..highlight(1, 1, 0, "first") }).unwrap();
+    repository.create_annotation(&Annotation { tags: Some(vec![tag(2)]), ..highlight(2, 1, 0, "second") }).unwrap();
+
+    assert_eq!(repository.delete_annotation(first).unwrap(), 1);
+    assert_eq!(repository.delete_annotation(first).unwrap(), 0);
+    assert!(repository.get_annotation_by_id(first).unwrap().is_none());
+
+    conn.execute("DELETE FROM books WHERE id = 2", params![]).unwrap();
+    let counts: (i64, i64) = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM annotations), (SELECT COUNT(*) FROM annotation_tags)",
+            params![],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(counts, (0, 0));
+}
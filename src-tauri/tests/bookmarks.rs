@@ -0,0 +1,116 @@
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::bookmark::Bookmark;
+use app_lib::db::repositories::BookmarkRepository;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute_batch(
+        "INSERT INTO books (title, file_path) VALUES ('Algebra', '/library/algebra.pdf');
+         INSERT INTO books (title, file_path) VALUES ('Topology', '/library/topology.epub');",
+    )
+    .unwrap();
+    conn
+}
+
+fn day(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 4, day, 12, 0, 0).unwrap()
+}
+
+fn bookmark(book_id: i64, name: &str, page_number: Option<i32>, created_at: DateTime<Utc>) -> Bookmark {
+    Bookmark {
+        id: None,
+        book_id,
+        book_title: None,
+        name: name.to_string(),
+        page_number,
+        chapter_index: None,
+        location: None,
+        note: None,
+        color: None,
+        created_at: Some(created_at),
+        last_opened_at: None,
+    }
+}
+
+fn names(bookmarks: Vec<Bookmark>) -> Vec<String> {
+    bookmarks.into_iter().map(|b| b.name).collect()
+}
+
+#[test]
+fn bookmarks_are_stored_with_their_book_title() {
+    let mut conn = setup();
+    let mut repository = BookmarkRepository::new(&mut conn);
+    let epub = Bookmark {
+        chapter_index: Some(3),
+        location: Some("epubcfi(/6/8!/4/2/10)".to_string()),
+        note: Some("Tychonoff".to_string()),
+        color: Some("#2196f3".to_string()),
+        ..bookmark(2, "Products", None, day(2))
+    };
+    let id = repository.create_bookmark(&epub).unwrap();
+
+    let stored = repository.get_bookmark_by_id(id).unwrap().unwrap();
+    assert_eq!(stored.id, Some(id));
+    assert_eq!(stored.book_title.as_deref(), Some("Topology"));
+    assert_eq!((stored.chapter_index, stored.location.as_deref()), (Some(3), Some("epubcfi(/6/8!/4/2/10)")));
+    assert_eq!((stored.note.as_deref(), stored.color.as_deref()), (Some("Tychonoff"), Some("#2196f3")));
+    assert_eq!((stored.created_at, stored.last_opened_at), (Some(day(2)), None));
+
+    let renamed = Bookmark { name: "Box topology".to_string(), note: None, ..stored };
+    assert_eq!(repository.update_bookmark(&renamed).unwrap(), 1);
+    let stored = repository.get_bookmark_by_id(id).unwrap().unwrap();
+    assert_eq!((stored.name.as_str(), stored.note), ("Box topology", None));
+    assert_eq!(stored.created_at, Some(day(2)));
+
+    assert_eq!(repository.mark_opened(id).unwrap(), 1);
+    assert!(repository.get_bookmark_by_id(id).unwrap().unwrap().last_opened_at.unwrap() > day(2));
+
+    assert_eq!(repository.delete_bookmark(id).unwrap(), 1);
+    assert!(repository.get_bookmark_by_id(id).unwrap().is_none());
+    assert_eq!(repository.mark_opened(id).unwrap(), 0);
+}
+
+#[test]
+fn book_bookmarks_are_listed_by_position() {
+    let mut conn = setup();
+    let mut repository = BookmarkRepository::new(&mut conn);
+    for bookmark in [
+        bookmark(1, "Rings", Some(40), day(1)),
+        bookmark(1, "Groups, again", Some(12), day(5)),
+        bookmark(2, "Open sets", None, day(2)),
+        bookmark(1, "Groups", Some(12), day(3)),
+    ] {
+        repository.create_bookmark(&bookmark).unwrap();
+    }
+
+    assert_eq!(names(repository.get_bookmarks_by_book_id(1).unwrap()), vec!["Groups", "Groups, again", "Rings"]);
+    assert_eq!(names(repository.get_bookmarks_by_book_id(2).unwrap()), vec!["Open sets"]);
+    assert!(repository.get_bookmarks_by_book_id(3).unwrap().is_empty());
+}
+
+#[test]
+fn recent_bookmarks_put_the_last_opened_first() {
+    let mut conn = setup();
+    let mut repository = BookmarkRepository::new(&mut conn);
+    let oldest = repository.create_bookmark(&bookmark(1, "Oldest", Some(1), day(1))).unwrap();
+    repository.create_bookmark(&bookmark(2, "Newest", None, day(9))).unwrap();
+    repository.create_bookmark(&bookmark(1, "Middle", Some(5), day(4))).unwrap();
+    repository.create_bookmark(&bookmark(1, "Same day", Some(7), day(4))).unwrap();
+
+    assert_eq!(names(repository.get_recent_bookmarks(10).unwrap()), vec!["Newest", "Same day", "Middle", "Oldest"]);
+
+    repository.mark_opened(oldest).unwrap();
+    let recent = repository.get_recent_bookmarks(2).unwrap();
+    assert_eq!(
+        recent.into_iter().map(|b| (b.name, b.book_title.unwrap())).collect::<Vec<_>>(),
+        vec![("Oldest".to_string(), "Algebra".to_string()), ("Newest".to_string(), "Topology".to_string())]
+    );
+
+    // Bookmarks go with their book.
+    conn.execute("DELETE FROM books WHERE id = 1", params![]).unwrap();
+    let repository = BookmarkRepository::new(&mut conn);
+    assert_eq!(names(repository.get_recent_bookmarks(10).unwrap()), vec!["Newest"]);
+}
@@ -0,0 +1,64 @@
+use app_lib::db::models::flashcard::ClozeSpan;
+use app_lib::services::cloze::{cloze_markup, render_cloze};
+
+fn span(start: usize, end: usize) -> ClozeSpan {
+    ClozeSpan { start, end }
+}
+
+#[test]
+fn markup_wraps_spans_in_text_order() {
+    let markup = cloze_markup("Eigenvalues of a real symmetric matrix are real", &[span(43, 47), span(0, 11)]).unwrap();
+    assert_eq!(markup, "{{c1::Eigenvalues}} of a real symmetric matrix are {{c1::real}}");
+}
+
+#[test]
+fn markup_counts_characters_not_bytes() {
+    let markup = cloze_markup("Théorème de Rolle", &[span(12, 17)]).unwrap();
+    assert_eq!(markup, "Théorème de {{c1::Rolle}}");
+}
+
+#[test]
+fn markup_rejects_bad_spans() {
+    let text = "kernel and image";
+    assert!(cloze_markup(text, &[]).is_err());
+    assert!(cloze_markup(text, &[span(3, 3)]).is_err());
+    assert!(cloze_markup(text, &[span(11, 17)]).is_err());
+    assert!(cloze_markup(text, &[span(0, 6), span(4, 10)]).is_err());
+    assert!(cloze_markup(text, &[span(6, 7)]).is_err());
+}
+
+#[test]
+fn render_hides_answers_and_shows_hints() {
+    let (front, back) = render_cloze("{{c1::Paris}} is the capital of {{c2::France::country}}");
+    assert_eq!(front, "[...] is the capital of [country]");
+    assert_eq!(back, "Paris is the capital of France");
+}
+
+#[test]
+fn render_leaves_plain_text_alone() {
+    let (front, back) = render_cloze("no {{deletions}} here");
+    assert_eq!(front, "no {{deletions}} here");
+    assert_eq!(back, front);
+}
+
+#[test]
+fn markup_rejects_answers_containing_a_separator() {
+    let text = "std::vector grows geometrically";
+    let err = cloze_markup(text, &[span(0, 11)]).unwrap_err();
+    assert!(err.contains("'::'"), "{}", err);
+
+    // The text around the span may contain it.
+    assert_eq!(cloze_markup(text, &[span(5, 11)]).unwrap(), "std::{{c1::vector}} grows geometrically");
+}
+
+#[test]
+fn markup_rejects_answers_containing_braces() {
+    let text = "a {{template}} and a set {x}";
+    assert!(cloze_markup(text, &[span(2, 14)]).is_err());
+    assert!(cloze_markup(text, &[span(2, 8)]).is_err());
+    assert!(cloze_markup(text, &[span(8, 14)]).is_err());
+    assert!(cloze_markup(text, &[span(25, 28)]).is_err());
+
+    let markup = cloze_markup(text, &[span(25, 27)]).unwrap();
+    assert_eq!(render_cloze(&markup).1, text);
+}
@@ -0,0 +1,99 @@
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::document::Document;
+use app_lib::db::repositories::DocumentRepository;
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn
+}
+
+fn sample_document(title: &str) -> Document {
+    Document {
+        id: 0,
+        title: title.to_string(),
+        original_filename: format!("{}.pdf", title),
+        stored_filename: format!("{}-stored.pdf", title),
+        file_path: format!("/library/{}-stored.pdf", title),
+        file_size: "1024".to_string(),
+        mime_type: "application/pdf".to_string(),
+        hash: format!("hash-{}", title),
+        page_count: 12,
+        created_at: Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()),
+        last_accessed: None,
+        thumbnail_path: None,
+        tags: Some(vec!["math".to_string(), "algebra".to_string()]),
+        subject: Some("Vector spaces".to_string()),
+        keywords: Some(vec!["linear maps".to_string()]),
+        language: Some("en".to_string()),
+        identifiers: Some(vec!["urn:isbn:9783319110790".to_string()]),
+    }
+}
+
+#[test]
+fn create_and_get_round_trip() {
+    let conn = setup();
+    let repo = DocumentRepository::new(&conn);
+
+    let id = repo.create(&sample_document("algebra")).unwrap();
+    let stored = repo.get_by_id(id as i32).unwrap().expect("document should exist");
+
+    assert_eq!(stored.id, id as i32);
+    assert_eq!(stored.title, "algebra");
+    assert_eq!(stored.page_count, 12);
+    assert_eq!(stored.created_at, Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()));
+    assert_eq!(stored.last_accessed, None);
+    assert_eq!(stored.tags, Some(vec!["math".to_string(), "algebra".to_string()]));
+    assert_eq!(stored.subject.as_deref(), Some("Vector spaces"));
+    assert_eq!(stored.keywords, Some(vec!["linear maps".to_string()]));
+    assert_eq!(stored.language.as_deref(), Some("en"));
+    assert_eq!(stored.identifiers, Some(vec!["urn:isbn:9783319110790".to_string()]));
+}
+
+#[test]
+fn get_missing_document_returns_none() {
+    let conn = setup();
+    let repo = DocumentRepository::new(&conn);
+
+    assert!(repo.get_by_id(42).unwrap().is_none());
+}
+
+#[test]
+fn update_persists_every_column() {
+    let conn = setup();
+    let repo = DocumentRepository::new(&conn);
+
+    let id = repo.create(&sample_document("calculus")).unwrap();
+    let mut document = repo.get_by_id(id as i32).unwrap().unwrap();
+    let accessed = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+
+    document.title = "Calculus, 2nd edition".to_string();
+    document.page_count = 640;
+    document.last_accessed = Some(accessed);
+    document.thumbnail_path = Some("/thumbs/calculus.png".to_string());
+    document.tags = None;
+    repo.update(&document).unwrap();
+
+    let stored = repo.get_by_id(id as i32).unwrap().unwrap();
+    assert_eq!(stored.title, "Calculus, 2nd edition");
+    assert_eq!(stored.page_count, 640);
+    assert_eq!(stored.last_accessed, Some(accessed));
+    assert_ne!(stored.last_accessed, stored.created_at);
+    assert_eq!(stored.thumbnail_path.as_deref(), Some("/thumbs/calculus.png"));
+    assert_eq!(stored.tags, None);
+}
+
+#[test]
+fn delete_removes_document() {
+    let conn = setup();
+    let repo = DocumentRepository::new(&conn);
+
+    let keep = repo.create(&sample_document("keep")).unwrap();
+    let drop = repo.create(&sample_document("drop")).unwrap();
+    repo.delete(drop as i32).unwrap();
+
+    let remaining: Vec<i32> = repo.get_all().unwrap().iter().map(|d| d.id).collect();
+    assert_eq!(remaining, vec![keep as i32]);
+}
@@ -0,0 +1,135 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use app_lib::services::epub::{html_to_text, read_all_chapter_texts, read_chapter_text, read_epub};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/epub").join(name)
+}
+
+/// Zips the unpacked EPUB under `tests/fixtures/epub/<name>` into a temporary
+/// file, `mimetype` first as the format asks.
+fn pack(name: &str) -> PathBuf {
+    let root = fixture(name);
+    let path = std::env::temp_dir().join(format!("study-studio-{}-{}.epub", name, std::process::id()));
+
+    let mut files = Vec::new();
+    collect_files(&root, &mut files);
+    files.sort_by_key(|file| *file != root.join("mimetype"));
+
+    let mut zip = ZipWriter::new(File::create(&path).unwrap());
+    for file in files {
+        let entry = file.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/");
+        zip.start_file(entry, SimpleFileOptions::default()).unwrap();
+        zip.write_all(&fs::read(&file).unwrap()).unwrap();
+    }
+    zip.finish().unwrap();
+    path
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+fn titled(href: &str, title: Option<&str>) -> (String, Option<String>) {
+    (href.to_string(), title.map(str::to_string))
+}
+
+#[test]
+fn epub2_metadata_comes_from_the_opf() {
+    let path = pack("epub2");
+    let book = read_epub(&path);
+    fs::remove_file(&path).unwrap();
+    let book = book.unwrap();
+
+    assert_eq!(book.metadata.title.as_deref(), Some("Linear Algebra Done Right"));
+    assert_eq!(book.metadata.creators, vec!["Sheldon Axler", "Jane Doe"]);
+    assert_eq!(book.metadata.language.as_deref(), Some("en"));
+    assert_eq!(
+        book.metadata.identifiers,
+        vec!["urn:isbn:9783319110790", "urn:uuid:0b7a5c1e-3f52-4d1a-9d5c-2a4c3b7e9f10"]
+    );
+    assert_eq!(book.metadata.subject.as_deref(), Some("Mathematics"));
+
+    let cover = book.cover.unwrap();
+    assert_eq!(cover.media_type, "image/png");
+    assert_eq!(cover.bytes, b"\x89PNG\r\n\x1a\ncover");
+}
+
+#[test]
+fn epub2_spine_is_titled_from_the_ncx() {
+    let path = pack("epub2");
+    let book = read_epub(&path);
+    let text = read_chapter_text(&path, "OEBPS/Text/chapter 1.xhtml");
+    let all = read_all_chapter_texts(&path);
+    let missing = read_chapter_text(&path, "OEBPS/Text/missing.xhtml");
+    fs::remove_file(&path).unwrap();
+
+    // The unknown spine entry is skipped, the percent-encoded href decoded
+    // and the label of the first entry pointing at a file wins.
+    let chapters = book.unwrap().chapters;
+    assert_eq!(chapters.iter().map(|c| c.index).collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(
+        chapters.into_iter().map(|c| (c.href, c.title)).collect::<Vec<_>>(),
+        vec![
+            titled("OEBPS/Text/chapter 1.xhtml", Some("1 Vector Spaces")),
+            titled("OEBPS/Text/ch2.xhtml", Some("2 Finite-Dimensional Vector Spaces")),
+        ]
+    );
+
+    let text = text.unwrap();
+    assert!(text.starts_with("Vector Spaces\nLinear algebra is the study"), "{}", text);
+    assert_eq!(
+        all.unwrap(),
+        vec![(0, text), (1, "Span and Linear Independence".to_string())]
+    );
+    assert!(missing.is_err());
+}
+
+#[test]
+fn epub3_nav_document_titles_chapters_without_an_ncx() {
+    let path = pack("epub3_nav");
+    let book = read_epub(&path);
+    fs::remove_file(&path).unwrap();
+    let book = book.unwrap();
+
+    assert_eq!(book.metadata.title.as_deref(), Some("Topology Notes"));
+    assert!(book.metadata.creators.is_empty());
+    assert_eq!(book.metadata.language.as_deref(), Some("pt-BR"));
+    assert_eq!(book.cover.map(|cover| cover.media_type).as_deref(), Some("image/jpeg"));
+
+    // Nav links are relative to the nav document, not to the package.
+    assert_eq!(
+        book.chapters.into_iter().map(|c| (c.href, c.title)).collect::<Vec<_>>(),
+        vec![
+            titled("EPUB/chapters/one.xhtml", Some("Open Sets")),
+            titled("EPUB/chapters/two.xhtml", Some("Compactness")),
+            titled("EPUB/chapters/appendix.xhtml", None),
+        ]
+    );
+}
+
+#[test]
+fn html_to_text_keeps_blocks_and_decodes_entities() {
+    let html = fs::read_to_string(fixture("epub2/OEBPS/Text/chapter 1.xhtml")).unwrap();
+
+    assert_eq!(
+        html_to_text(&html),
+        "Vector Spaces\n\
+         Linear algebra is the study of linear maps on finite-dimensional vector spaces.\n\
+         R & C \u{2014} fields of scalars, caf\u{e9}.\n\
+         Next line"
+    );
+    assert_eq!(html_to_text("a &bogus; &amp b &#xZZ; <b>bold</b>"), "a &bogus; &amp b &#xZZ; bold");
+    assert_eq!(html_to_text("<p>unclosed <em"), "unclosed");
+}
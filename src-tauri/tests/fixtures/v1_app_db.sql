@@ -0,0 +1,54 @@
+-- Snapshot of an app.db created by the original single-batch schema,
+-- before schema_migrations existed.
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    status TEXT CHECK(status IN ('active', 'inactive')) NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    last_login DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE user_available_days (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    day_of_week TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE TABLE user_interests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    interest TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE TABLE books (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL,
+    author TEXT,
+    file_path TEXT NOT NULL
+);
+
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT,
+    color TEXT,
+    icon TEXT
+);
+
+CREATE TABLE book_tags (
+    book_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (book_id, tag_id),
+    FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+INSERT INTO users (name, email, status) VALUES ('Ana', 'ana@example.com', 'active');
+INSERT INTO user_available_days (user_id, day_of_week) VALUES (1, 'monday'), (1, 'thursday');
+INSERT INTO user_interests (user_id, interest) VALUES (1, 'mathematics');
+INSERT INTO books (title, author, file_path) VALUES ('Linear Algebra Done Right', 'Sheldon Axler', 'linear-algebra.pdf');
+INSERT INTO books (title, author, file_path) VALUES ('Calculus', NULL, 'calculus.pdf');
+INSERT INTO tags (title, color, icon) VALUES ('math', '#ff0000', NULL);
+INSERT INTO book_tags (book_id, tag_id) VALUES (1, 1), (2, 1);
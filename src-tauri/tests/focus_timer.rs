@@ -0,0 +1,124 @@
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::repositories::PomodoroRepository;
+use app_lib::services::focus_timer::{
+    FocusCycle, FocusNotifier, FocusPhase, FocusTimer, FocusTimerConfig, FocusTimerStatus, FINISHED_EVENT,
+    PHASE_CHANGED_EVENT,
+};
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+
+fn setup() -> Arc<Mutex<Connection>> {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute("INSERT INTO users (name, email, status) VALUES ('Ada', 'ada@example.com', 'active')", []).unwrap();
+    Arc::new(Mutex::new(conn))
+}
+
+fn listener() -> (FocusNotifier, Receiver<(String, FocusTimerStatus)>) {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let notifier: FocusNotifier = Arc::new(move |event, status| {
+        sender.lock().unwrap().send((event.to_string(), status.clone())).unwrap();
+    });
+    (notifier, receiver)
+}
+
+fn seconds(work: u32, short_break: u32, long_break: u32, long_break_every: u32, cycles: Option<u32>) -> FocusTimerConfig {
+    FocusTimerConfig { work_seconds: work, short_break_seconds: short_break, long_break_seconds: long_break, long_break_every, cycles }
+}
+
+#[test]
+fn runs_cycles_and_saves_completed_pomodoros() {
+    let db_conn = setup();
+    let timer = FocusTimer::new(Arc::clone(&db_conn));
+    let (notifier, events) = listener();
+
+    let started = timer.start(1, None, None, seconds(1, 1, 1, 2, Some(2)), notifier).unwrap();
+    assert_eq!((started.phase, started.remaining_seconds), (FocusPhase::Work, 1));
+
+    let wait = Duration::from_secs(5);
+    let phases: Vec<(String, FocusPhase, u32)> = (0..3)
+        .map(|_| events.recv_timeout(wait).unwrap())
+        .map(|(event, status)| (event, status.phase, status.completed_pomodoros))
+        .collect();
+    assert_eq!(phases, vec![
+        (PHASE_CHANGED_EVENT.to_string(), FocusPhase::ShortBreak, 1),
+        (PHASE_CHANGED_EVENT.to_string(), FocusPhase::Work, 1),
+        (FINISHED_EVENT.to_string(), FocusPhase::Work, 2),
+    ]);
+    assert!(timer.status().is_none());
+
+    let mut conn = db_conn.lock().unwrap();
+    let pomodoros = PomodoroRepository::new(&mut conn).get_pomodoros(1, None, None, 10).unwrap();
+    assert_eq!(pomodoros.len(), 2);
+    assert!(pomodoros.iter().all(|p| p.focus_seconds == 1 && p.ended_at > p.started_at));
+}
+
+#[test]
+fn pauses_skips_and_stops() {
+    let db_conn = setup();
+    let timer = FocusTimer::new(Arc::clone(&db_conn));
+    let (notifier, events) = listener();
+
+    assert!(timer.pause().is_err());
+    assert!(timer.start(1, None, None, seconds(0, 1, 1, 1, None), Arc::clone(&notifier)).is_err());
+    timer.start(1, None, None, seconds(60, 30, 90, 1, None), Arc::clone(&notifier)).unwrap();
+    assert!(timer.start(1, None, None, FocusTimerConfig::default(), notifier).is_err());
+
+    let paused = timer.pause().unwrap();
+    assert!(paused.paused && paused.phase_ends_at.is_none());
+    assert_eq!(paused.remaining_seconds, 60);
+    assert_eq!(timer.status().unwrap().remaining_seconds, 60);
+    let resumed = timer.resume().unwrap();
+    assert!(!resumed.paused && resumed.phase_ends_at.is_some());
+
+    let skipped = timer.skip().unwrap();
+    assert_eq!((skipped.phase, skipped.completed_pomodoros), (FocusPhase::ShortBreak, 0));
+    assert_eq!(events.recv_timeout(Duration::from_secs(1)).unwrap().0, PHASE_CHANGED_EVENT);
+    assert_eq!(timer.skip().unwrap().phase, FocusPhase::Work);
+    events.recv_timeout(Duration::from_secs(1)).unwrap();
+
+    let stopped = timer.stop().unwrap();
+    assert!(stopped.finished);
+    assert_eq!(events.recv_timeout(Duration::from_secs(1)).unwrap().0, FINISHED_EVENT);
+    assert!(timer.status().is_none());
+    assert!(timer.stop().is_err());
+
+    let mut conn = db_conn.lock().unwrap();
+    assert!(PomodoroRepository::new(&mut conn).get_pomodoros(1, None, None, 10).unwrap().is_empty());
+}
+
+#[test]
+fn phases_noticed_long_after_they_ended_start_over() {
+    let start = Utc.with_ymd_and_hms(2024, 5, 6, 9, 0, 0).unwrap();
+    let mut cycle = FocusCycle::start(1, None, None, seconds(60, 30, 90, 4, None), start);
+
+    // Noticed a little late: the work phase counts as of when it ended.
+    let (pomodoro, finished) = cycle.advance(start + chrono::Duration::seconds(62), true);
+    let pomodoro = pomodoro.unwrap();
+    assert!(!finished);
+    assert_eq!((pomodoro.started_at, pomodoro.ended_at), (start, start + chrono::Duration::seconds(60)));
+    let status = cycle.status(start + chrono::Duration::seconds(62), false);
+    assert_eq!((status.phase, status.completed_pomodoros, status.remaining_seconds), (FocusPhase::ShortBreak, 1, 28));
+
+    // Woken ten phases after the break ended: the break starts over, and no
+    // work phase is credited for the time asleep.
+    let woken = start + chrono::Duration::seconds(90 + 10 * 90);
+    let (pomodoro, finished) = cycle.advance(woken, true);
+    assert!(pomodoro.is_none() && !finished);
+    let status = cycle.status(woken, false);
+    assert_eq!((status.phase, status.completed_pomodoros), (FocusPhase::ShortBreak, 1));
+    assert_eq!((status.phase_started_at, status.remaining_seconds), (woken, 30));
+
+    let (pomodoro, _) = cycle.advance(woken + chrono::Duration::seconds(30), true);
+    assert!(pomodoro.is_none());
+    let later = woken + chrono::Duration::seconds(30 + 5 * 60);
+    let (pomodoro, _) = cycle.advance(later, true);
+    assert!(pomodoro.is_none());
+    let status = cycle.status(later, false);
+    assert_eq!((status.phase, status.completed_pomodoros, status.phase_started_at), (FocusPhase::Work, 1, later));
+}
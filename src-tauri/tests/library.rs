@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use app_lib::commands::library_commands::{ImportOutcome, ImportSource, LibraryCommands};
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::tag::Tag;
+use app_lib::db::repositories::{BookRepository, DocumentRepository, TagRepository};
+use app_lib::services::library_store::LibraryStore;
+use rusqlite::Connection;
+
+/// SHA-256 of the `b"hello"` files written below.
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("study-studio-library-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn import(conn: &mut Connection, library_dir: &Path, source: &Path, author: Option<&str>, tags: Vec<Tag>, attach: bool) -> ImportOutcome {
+    let source = ImportSource::read(source.to_str().unwrap()).unwrap();
+    LibraryCommands::new(conn, library_dir)
+        .import_book_method(source, None, author.map(str::to_string), tags, attach)
+        .unwrap()
+}
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn
+}
+
+#[test]
+fn stored_files_are_named_after_their_content() {
+    let dir = scratch_dir("content");
+    let store = LibraryStore::new(dir.join("library"));
+    fs::write(dir.join("Linear Algebra.PDF"), b"hello").unwrap();
+    fs::write(dir.join("copy.pdf"), b"hello").unwrap();
+
+    let first = store.store_file(&dir.join("Linear Algebra.PDF")).unwrap();
+    let second = store.store_file(&dir.join("copy.pdf")).unwrap();
+
+    assert_eq!(first.stored_filename, format!("{}.pdf", HELLO_SHA256));
+    assert_eq!(first.hash, HELLO_SHA256);
+    assert_eq!(first.original_filename, "Linear Algebra.PDF");
+    assert_eq!((first.file_size, first.mime_type.as_str()), (5, "application/pdf"));
+    assert!(first.newly_created);
+
+    assert_eq!(second.file_path, first.file_path);
+    assert!(!second.newly_created);
+    assert_eq!(fs::read_dir(store.root()).unwrap().count(), 1);
+
+    // Only the import that created the copy may remove it.
+    store.discard(&second).unwrap();
+    assert!(first.file_path.is_file());
+    store.discard(&first).unwrap();
+    assert!(!first.file_path.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_import_removes_the_copied_file() {
+    let dir = scratch_dir("rollback");
+    let library_dir = dir.join("library");
+    let source = dir.join("notes.pdf");
+    fs::write(&source, b"hello").unwrap();
+
+    let mut conn = setup();
+    conn.execute_batch(
+        "CREATE TRIGGER reject_books BEFORE INSERT ON books BEGIN SELECT RAISE(ABORT, 'books are read-only'); END;",
+    )
+    .unwrap();
+
+    let source = ImportSource::read(source.to_str().unwrap()).unwrap();
+    let result = LibraryCommands::new(&mut conn, &library_dir).import_book_method(source, None, None, Vec::new(), false);
+
+    assert!(result.is_err());
+    assert!(!library_dir.join(format!("{}.pdf", HELLO_SHA256)).exists());
+    let documents: i64 = conn.query_row("SELECT COUNT(*) FROM documents", [], |row| row.get(0)).unwrap();
+    assert_eq!(documents, 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn same_content_is_reported_as_a_duplicate_and_can_be_merged() {
+    let dir = scratch_dir("duplicate");
+    let library_dir = dir.join("library");
+    fs::write(dir.join("algebra.pdf"), b"hello").unwrap();
+    fs::write(dir.join("renamed copy.pdf"), b"hello").unwrap();
+
+    let mut conn = setup();
+    let ImportOutcome::Imported(imported) = import(&mut conn, &library_dir, &dir.join("algebra.pdf"), None, Vec::new(), false) else {
+        panic!("the first import should create a book");
+    };
+
+    let outcome = import(&mut conn, &library_dir, &dir.join("renamed copy.pdf"), None, Vec::new(), false);
+    assert!(matches!(
+        outcome,
+        ImportOutcome::Duplicate { book_id: Some(id), attached: false, .. } if id == imported.book_id
+    ));
+
+    let tag = Tag { id: None, title: "math".to_string(), color: "#00ff00".to_string(), icon: None };
+    let tag_id = TagRepository::new(&mut conn).create_tag(&tag).unwrap() as i32;
+    let tag = Tag { id: Some(tag_id), ..tag };
+    let outcome = import(&mut conn, &library_dir, &dir.join("renamed copy.pdf"), Some("Axler"), vec![tag], true);
+    assert!(matches!(
+        outcome,
+        ImportOutcome::Duplicate { book_id: Some(id), attached: true, .. } if id == imported.book_id
+    ));
+
+    let book = BookRepository::new(&mut conn).get_book_by_id(imported.book_id).unwrap().unwrap();
+    assert_eq!((book.title.as_str(), book.author.as_deref()), ("algebra", Some("Axler")));
+    assert_eq!(book.tags.unwrap().iter().map(|tag| tag.id).collect::<Vec<_>>(), vec![Some(tag_id)]);
+    let document = DocumentRepository::new(&conn).get_by_id(imported.document_id as i32).unwrap().unwrap();
+    assert_eq!(document.tags, Some(vec!["math".to_string()]));
+    assert_eq!(fs::read_dir(&library_dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn duplicate_of_a_document_without_a_book_recreates_it_on_attach() {
+    let dir = scratch_dir("orphan");
+    let library_dir = dir.join("library");
+    fs::write(dir.join("algebra.pdf"), b"hello").unwrap();
+
+    let mut conn = setup();
+    let ImportOutcome::Imported(imported) = import(&mut conn, &library_dir, &dir.join("algebra.pdf"), None, Vec::new(), false) else {
+        panic!("the first import should create a book");
+    };
+    BookRepository::new(&mut conn).delete_book(imported.book_id).unwrap();
+
+    let outcome = import(&mut conn, &library_dir, &dir.join("algebra.pdf"), None, Vec::new(), false);
+    assert!(matches!(
+        outcome,
+        ImportOutcome::Duplicate { book_id: None, document_id, attached: false, .. } if document_id == imported.document_id
+    ));
+
+    let ImportOutcome::Duplicate { book_id: Some(book_id), attached: true, .. } =
+        import(&mut conn, &library_dir, &dir.join("algebra.pdf"), None, Vec::new(), true)
+    else {
+        panic!("attaching should recreate the book");
+    };
+    let book = BookRepository::new(&mut conn).get_book_by_id(book_id).unwrap().unwrap();
+    assert_eq!(book.document_id, Some(imported.document_id));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,118 @@
+use app_lib::db::migrations::{
+    current_version, latest_version, migrate_to, run_migrations, MigrationError, MIGRATIONS,
+};
+use rusqlite::{params, Connection};
+
+const V1_FIXTURE: &str = include_str!("fixtures/v1_app_db.sql");
+
+fn v1_fixture() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(V1_FIXTURE).unwrap();
+    conn
+}
+
+fn count(conn: &Connection, table: &str) -> i64 {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn migrations_are_strictly_ordered() {
+    for pair in MIGRATIONS.windows(2) {
+        assert!(pair[0].version < pair[1].version, "migration {} is out of order", pair[1].version);
+    }
+    assert_eq!(MIGRATIONS.first().map(|m| m.version), Some(1));
+}
+
+#[test]
+fn fresh_database_applies_every_migration_once() {
+    let mut conn = Connection::open_in_memory().unwrap();
+
+    let applied = run_migrations(&mut conn).unwrap();
+    let versions: Vec<i64> = applied.iter().map(|m| m.version).collect();
+    let expected: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+    assert_eq!(versions, expected);
+    assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+    let applied_again = run_migrations(&mut conn).unwrap();
+    assert!(applied_again.is_empty());
+}
+
+#[test]
+fn v1_fixture_upgrades_step_by_step() {
+    let mut conn = v1_fixture();
+    assert_eq!(current_version(&conn).unwrap(), 0);
+
+    for migration in MIGRATIONS {
+        let applied = migrate_to(&mut conn, migration.version).unwrap();
+        assert!(applied.iter().all(|m| m.version <= migration.version));
+        assert_eq!(current_version(&conn).unwrap(), migration.version);
+
+        assert_eq!(count(&conn, "users"), 1);
+        assert_eq!(count(&conn, "user_available_days"), 2);
+        assert_eq!(count(&conn, "books"), 2);
+        assert_eq!(count(&conn, "book_tags"), 2);
+
+        let title: String = conn
+            .query_row("SELECT title FROM books WHERE id = ?", params![1], |row| row.get(0))
+            .unwrap();
+        assert_eq!(title, "Linear Algebra Done Right");
+    }
+}
+
+#[test]
+fn refuses_database_newer_than_binary() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute(
+        "INSERT INTO schema_migrations (version, name) VALUES (?, ?)",
+        params![latest_version() + 1, "from_the_future"],
+    )
+    .unwrap();
+
+    match run_migrations(&mut conn) {
+        Err(MigrationError::DatabaseTooNew { found, supported }) => {
+            assert_eq!(found, latest_version() + 1);
+            assert_eq!(supported, latest_version());
+        }
+        other => panic!("expected DatabaseTooNew, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_unknown_target_version() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    assert!(matches!(
+        migrate_to(&mut conn, latest_version() + 1),
+        Err(MigrationError::UnknownVersion(_))
+    ));
+}
+
+#[test]
+fn deletes_cascade_through_declared_foreign_keys() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute_batch(
+        "INSERT INTO documents (title, original_filename, stored_filename, file_path, file_size, mime_type, hash)
+         VALUES ('Notes', 'notes.pdf', 'abc.pdf', '/library/abc.pdf', '10', 'application/pdf', 'abc');
+         INSERT INTO document_pages (document_id, page_number, text) VALUES (1, 1, 'eigenvalues and eigenvectors');
+         INSERT INTO books (title, file_path, document_id) VALUES ('Notes', '/library/abc.pdf', 1);
+         INSERT INTO annotations (book_id, color, created_at, updated_at)
+         VALUES (1, '#ffeb3b', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');
+         INSERT INTO tags (title) VALUES ('algebra');
+         INSERT INTO annotation_tags (annotation_id, tag_id) VALUES (1, 1);",
+    )
+    .unwrap();
+
+    conn.execute("DELETE FROM books WHERE id = 1", []).unwrap();
+    assert_eq!(count(&conn, "annotations"), 0);
+    assert_eq!(count(&conn, "annotation_tags"), 0);
+    assert_eq!(count(&conn, "tags"), 1);
+
+    conn.execute("DELETE FROM documents WHERE id = 1", []).unwrap();
+    assert_eq!(count(&conn, "document_pages"), 0);
+    let matches: i64 = conn
+        .query_row("SELECT COUNT(*) FROM document_pages_fts WHERE document_pages_fts MATCH 'eigenvalues'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(matches, 0);
+}
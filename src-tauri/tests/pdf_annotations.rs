@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::Path;
+
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::annotation::{Annotation, AnnotationKind, AnnotationRect};
+use app_lib::db::repositories::AnnotationRepository;
+use app_lib::services::pdf_annotations::{
+    build_annotation, parse_color, parse_pdf_date, pdf_color_to_hex, quad_points, quad_rects,
+    read_pdf_annotations, write_annotated_pdf, ANNOTATION_NAME_PREFIX,
+};
+use chrono::{TimeZone, Utc};
+use lopdf::{decode_text_string, dictionary, Dictionary, Document, Object};
+use rusqlite::Connection;
+
+const PAGE_ID: (u32, u16) = (7, 0);
+const MEDIA_BOX: [f64; 4] = [0.0, 0.0, 612.0, 792.0];
+
+fn rect(x: f64, y: f64, width: f64, height: f64) -> AnnotationRect {
+    AnnotationRect { x, y, width, height }
+}
+
+fn annotation(kind: AnnotationKind, rects: Vec<AnnotationRect>) -> Annotation {
+    Annotation {
+        id: Some(12),
+        book_id: 1,
+        kind,
+        page_number: Some(1),
+        chapter_index: None,
+        location: None,
+        rects: Some(rects).filter(|rects| !rects.is_empty()),
+        start_offset: None,
+        end_offset: None,
+        selected_text: Some("spectral theorem".to_string()),
+        color: "#ff0000".to_string(),
+        note: Some("see chapter 7".to_string()),
+        created_at: None,
+        updated_at: None,
+        pdf_ref: None,
+        tags: None,
+    }
+}
+
+fn name(dictionary: &Dictionary, key: &[u8]) -> String {
+    String::from_utf8(dictionary.get(key).unwrap().as_name().unwrap().to_vec()).unwrap()
+}
+
+fn numbers(dictionary: &Dictionary, key: &[u8]) -> Vec<f64> {
+    dictionary.get(key).unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap() as f64).collect()
+}
+
+fn text(dictionary: &Dictionary, key: &[u8]) -> String {
+    decode_text_string(dictionary.get(key).unwrap()).unwrap()
+}
+
+#[test]
+fn colors_parse_short_and_long_hex() {
+    assert_eq!(parse_color("#ff0000"), [1.0, 0.0, 0.0]);
+    assert_eq!(parse_color(" #0F0 "), [0.0, 1.0, 0.0]);
+    assert_eq!(parse_color("0000ff"), [0.0, 0.0, 1.0]);
+
+    let yellow = [1.0, 0.92, 0.23];
+    for invalid in ["red", "#ff00", "#gg0000", "#ffé000", ""] {
+        assert_eq!(parse_color(invalid), yellow, "{:?}", invalid);
+    }
+}
+
+#[test]
+fn colors_convert_back_from_gray_rgb_and_cmyk() {
+    assert_eq!(pdf_color_to_hex(&[0.5]).as_deref(), Some("#808080"));
+    assert_eq!(pdf_color_to_hex(&[0.0, 0.4, 1.0]).as_deref(), Some("#0066ff"));
+    assert_eq!(pdf_color_to_hex(&[0.0, 1.0, 1.0, 0.0]).as_deref(), Some("#ff0000"));
+    assert_eq!(pdf_color_to_hex(&[2.0, -1.0, 0.0]).as_deref(), Some("#ff0000"));
+    assert_eq!(pdf_color_to_hex(&[]), None);
+    assert_eq!(pdf_color_to_hex(&[0.1, 0.2]), None);
+
+    let components = parse_color("#3366cc").map(f64::from);
+    assert_eq!(pdf_color_to_hex(&components).as_deref(), Some("#3366cc"));
+}
+
+#[test]
+fn dates_apply_the_offset_sign() {
+    let expected = Utc.with_ymd_and_hms(2020, 1, 2, 1, 4, 5).unwrap();
+    assert_eq!(parse_pdf_date("D:20200102030405+02'00'"), Some(expected));
+    assert_eq!(parse_pdf_date("D:20200101203405-04'30'"), Some(expected));
+    assert_eq!(parse_pdf_date("D:20200102010405Z"), Some(expected));
+    assert_eq!(parse_pdf_date("D:20200102010405Z00'00'"), Some(expected));
+    assert_eq!(parse_pdf_date("20200102030405+02"), Some(expected));
+}
+
+#[test]
+fn partial_dates_default_to_the_start_of_the_period() {
+    assert_eq!(parse_pdf_date("D:2020"), Some(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()));
+    assert_eq!(parse_pdf_date("D:202003"), Some(Utc.with_ymd_and_hms(2020, 3, 1, 0, 0, 0).unwrap()));
+    assert_eq!(parse_pdf_date("D:2020031514"), Some(Utc.with_ymd_and_hms(2020, 3, 15, 14, 0, 0).unwrap()));
+    assert_eq!(parse_pdf_date("D:202003151430+01"), Some(Utc.with_ymd_and_hms(2020, 3, 15, 13, 30, 0).unwrap()));
+
+    assert_eq!(parse_pdf_date("D:20"), None);
+    assert_eq!(parse_pdf_date("D:20201332"), None);
+    assert_eq!(parse_pdf_date("yesterday"), None);
+}
+
+#[test]
+fn quad_points_round_trip_to_rects() {
+    let rects = vec![rect(72.0, 700.0, 300.0, 12.0), rect(72.0, 686.0, 120.5, 12.0)];
+    let points = quad_points(&rects);
+
+    assert_eq!(points.len(), 16);
+    assert_eq!(points[..8], [72.0, 712.0, 372.0, 712.0, 72.0, 700.0, 372.0, 700.0]);
+    assert_eq!(quad_rects(&points), rects);
+}
+
+#[test]
+fn quad_rects_accept_any_corner_order_and_ignore_partial_quads() {
+    // Counter-clockwise from the bottom-left, as some readers write them,
+    // followed by a truncated quadrilateral.
+    let points = [10.0, 20.0, 60.0, 20.0, 60.0, 32.0, 10.0, 32.0, 1.0, 2.0, 3.0];
+    assert_eq!(quad_rects(&points), vec![rect(10.0, 20.0, 50.0, 12.0)]);
+    assert!(quad_rects(&[]).is_empty());
+}
+
+#[test]
+fn highlights_and_underlines_with_rects_become_markup() {
+    let rects = vec![rect(72.0, 700.0, 300.0, 12.0), rect(72.0, 686.0, 120.0, 12.0)];
+
+    for (kind, subtype) in [(AnnotationKind::Highlight, "Highlight"), (AnnotationKind::Underline, "Underline")] {
+        let dictionary = build_annotation(&annotation(kind, rects.clone()), PAGE_ID, MEDIA_BOX, "D:20240101000000Z");
+
+        assert_eq!(name(&dictionary, b"Subtype"), subtype);
+        assert_eq!(dictionary.get(b"P").unwrap().as_reference().unwrap(), PAGE_ID);
+        assert_eq!(text(&dictionary, b"Contents"), "see chapter 7");
+        assert_eq!(text(&dictionary, b"NM"), format!("{}12", ANNOTATION_NAME_PREFIX));
+        assert_eq!(numbers(&dictionary, b"Rect"), vec![72.0, 686.0, 372.0, 712.0]);
+        assert_eq!(quad_rects(&numbers(&dictionary, b"QuadPoints")), rects);
+        assert_eq!(numbers(&dictionary, b"C"), vec![1.0, 0.0, 0.0]);
+    }
+}
+
+#[test]
+fn notes_and_markup_without_rects_become_sticky_notes() {
+    let note = build_annotation(&annotation(AnnotationKind::Note, vec![rect(100.0, 500.0, 40.0, 10.0)]), PAGE_ID, MEDIA_BOX, "D:20240101000000Z");
+    assert_eq!(name(&note, b"Subtype"), "Text");
+    assert_eq!(text(&note, b"Contents"), "\u{201c}spectral theorem\u{201d}\n\nsee chapter 7");
+    assert!(note.get(b"QuadPoints").is_err());
+    assert_eq!(numbers(&note, b"Rect"), vec![100.0, 490.0, 120.0, 510.0]);
+
+    let mut unplaced = annotation(AnnotationKind::Highlight, Vec::new());
+    unplaced.id = None;
+    unplaced.note = None;
+    let dictionary = build_annotation(&unplaced, PAGE_ID, MEDIA_BOX, "D:20240101000000Z");
+    assert_eq!(name(&dictionary, b"Subtype"), "Text");
+    assert_eq!(text(&dictionary, b"Contents"), "\u{201c}spectral theorem\u{201d}");
+    assert!(dictionary.get(b"NM").is_err());
+    assert_eq!(numbers(&dictionary, b"Rect"), vec![20.0, 752.0, 40.0, 772.0]);
+}
+
+/// A two-page PDF as another reader leaves it: an indirect highlight and an
+/// inline sticky note on page 1.
+fn annotated_pdf(path: &Path) {
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+    let highlight_id = document.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Highlight",
+        "Rect" => vec![72.into(), 690.into(), 272.into(), 702.into()],
+        "QuadPoints" => vec![72.into(), 702.into(), 272.into(), 702.into(), 72.into(), 690.into(), 272.into(), 690.into()],
+        "C" => vec![1.into(), 0.into(), 0.into()],
+    });
+    let note = dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Text",
+        "Rect" => vec![500.into(), 700.into(), 520.into(), 720.into()],
+        "Contents" => Object::string_literal("check this"),
+    };
+
+    let mut kids = Vec::new();
+    for annots in [vec![highlight_id.into(), Object::Dictionary(note)], Vec::new()] {
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Annots" => annots,
+        });
+        kids.push(page_id.into());
+    }
+    document.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 2 }));
+    let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    document.trailer.set("Root", catalog_id);
+    document.save(path).unwrap();
+}
+
+#[test]
+fn imported_annotations_are_not_embedded_again_on_export() {
+    let dir = std::env::temp_dir().join(format!("study-studio-pdf-annotations-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("library-copy.pdf");
+    let exported = dir.join("exported.pdf");
+    annotated_pdf(&source);
+
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute("INSERT INTO books (title, file_path) VALUES ('Algebra', ?)", [source.to_string_lossy()]).unwrap();
+
+    let found = read_pdf_annotations(&source).unwrap();
+    assert_eq!(found.iter().map(|a| a.pdf_ref.as_str()).collect::<Vec<_>>(), vec!["1/0", "1/1"]);
+
+    let mut repository = AnnotationRepository::new(&mut conn);
+    for annotation in found {
+        repository.create_annotation(&annotation.into_annotation(1)).unwrap();
+    }
+    let mut added = annotation(AnnotationKind::Underline, vec![rect(72.0, 400.0, 100.0, 12.0)]);
+    added.id = None;
+    added.page_number = Some(2);
+    repository.create_annotation(&added).unwrap();
+
+    let stored = repository.get_annotations_by_book_id(1, None).unwrap();
+    assert_eq!(
+        stored.iter().map(|a| a.pdf_ref.as_deref()).collect::<Vec<_>>(),
+        vec![Some("1/0"), Some("1/1"), None]
+    );
+
+    assert_eq!(write_annotated_pdf(&source, &exported, &stored).unwrap(), 1);
+    let reread = read_pdf_annotations(&exported);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let reread = reread.unwrap();
+    assert_eq!(reread.len(), 3);
+    assert_eq!(
+        reread.iter().map(|a| (a.page_number, a.kind)).collect::<Vec<_>>(),
+        vec![(1, AnnotationKind::Highlight), (1, AnnotationKind::Note), (2, AnnotationKind::Underline)]
+    );
+}
@@ -0,0 +1,172 @@
+use app_lib::commands::flashcard_commands::FlashcardCommands;
+use app_lib::commands::quiz_commands::{QuizCommands, QuizInput};
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::flashcard::{Card, CardState};
+use app_lib::db::models::quiz::{QuestionKind, QuizQuestion};
+use app_lib::services::quiz::{
+    answer_similarity, build_questions, card_answer, check_answer, normalize_answer, QuizRng,
+    MULTIPLE_CHOICE_OPTIONS,
+};
+use rusqlite::Connection;
+
+fn card(id: i64, front: &str, back: &str) -> Card {
+    Card {
+        id: Some(id),
+        deck_id: 1,
+        front: front.to_string(),
+        back: back.to_string(),
+        book_id: None,
+        page_number: None,
+        due_date: "2024-01-01".to_string(),
+        state: CardState::default(),
+        created_at: None,
+        last_reviewed_at: None,
+        annotation_id: None,
+        cloze_text: None,
+        anki_guid: None,
+        anki_ord: None,
+        tags: None,
+    }
+}
+
+fn sample_cards() -> Vec<Card> {
+    vec![
+        card(1, "Dimension of the null space", "Nullity"),
+        card(2, "Dimension of the image", "Rank"),
+        card(3, "Maps with trivial kernel", "Injective maps"),
+        card(4, "Maps onto the codomain", "Surjective maps"),
+        card(5, "Invertible linear maps", "Isomorphisms"),
+    ]
+}
+
+fn typed(answer: &str) -> QuizQuestion {
+    QuizQuestion {
+        id: None,
+        quiz_id: 1,
+        position: 1,
+        card_id: None,
+        kind: QuestionKind::Typed,
+        prompt: "?".to_string(),
+        statement: None,
+        choices: None,
+        answer: answer.to_string(),
+    }
+}
+
+#[test]
+fn answers_are_compared_without_formatting() {
+    assert_eq!(normalize_answer("<b>Rank–Nullity</b>  theorem!"), "rank nullity theorem");
+    assert_eq!(answer_similarity("Rank", "rank."), 1.0);
+    assert_eq!(answer_similarity("", ""), 1.0);
+}
+
+#[test]
+fn typed_answers_tolerate_small_typos() {
+    let (correct, similarity) = check_answer(&typed("Isomorphisms"), "isomorphsims");
+    assert!(correct);
+    assert!((similarity.unwrap() - 11.0 / 12.0).abs() < 1e-9);
+
+    assert!(!check_answer(&typed("Rank"), "Tank").0);
+    assert!(!check_answer(&typed("Injective maps"), "surjective maps").0);
+}
+
+#[test]
+fn cloze_cards_are_answered_with_the_hidden_text() {
+    let mut cloze = card(1, "The [...] of T is [...]", "The rank of T is dim range T");
+    cloze.cloze_text = Some("The {{c1::rank}} of T is {{c1::dim range T}}".to_string());
+    assert_eq!(card_answer(&cloze), "rank, dim range T");
+
+    cloze.cloze_text = Some("The {{c1::rank}} of T is {{c2::dim range T}}".to_string());
+    cloze.anki_ord = Some(1);
+    assert_eq!(card_answer(&cloze), "dim range T");
+}
+
+#[test]
+fn questions_mix_kinds_with_distractors_from_other_cards() {
+    let cards = sample_cards();
+    let kinds = [QuestionKind::MultipleChoice, QuestionKind::TrueFalse, QuestionKind::Typed];
+    let questions = build_questions(&cards, &kinds, 10, &mut QuizRng::new(7));
+
+    assert_eq!(questions.len(), cards.len());
+    assert_eq!(questions.iter().map(|q| q.position).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    for kind in kinds {
+        assert!(questions.iter().any(|q| q.kind == kind));
+    }
+
+    for question in &questions {
+        let source = cards.iter().find(|c| c.id == question.card_id).unwrap();
+        assert_eq!(question.prompt, source.front);
+        match question.kind {
+            QuestionKind::MultipleChoice => {
+                let choices = question.choices.as_ref().unwrap();
+                assert_eq!(choices.len(), MULTIPLE_CHOICE_OPTIONS);
+                assert_eq!(choices.iter().filter(|c| **c == source.back).count(), 1);
+                assert_eq!(question.answer, source.back);
+                assert!(check_answer(question, &source.back).0);
+            }
+            QuestionKind::TrueFalse => {
+                let statement = question.statement.as_ref().unwrap();
+                let expected = if *statement == source.back { "true" } else { "false" };
+                assert_eq!(question.answer, expected);
+                assert!(check_answer(question, expected).0);
+            }
+            QuestionKind::Typed => assert_eq!(question.answer, source.back),
+        }
+    }
+}
+
+#[test]
+fn generation_is_reproducible_from_a_seed() {
+    let cards = sample_cards();
+    let kinds = [QuestionKind::MultipleChoice, QuestionKind::TrueFalse];
+    let first = build_questions(&cards, &kinds, 3, &mut QuizRng::new(42));
+    let second = build_questions(&cards, &kinds, 3, &mut QuizRng::new(42));
+
+    assert_eq!(first.len(), 3);
+    for (a, b) in first.iter().zip(&second) {
+        assert_eq!((a.card_id, a.kind, &a.choices, &a.statement), (b.card_id, b.kind, &b.choices, &b.statement));
+    }
+}
+
+#[test]
+fn lone_answers_fall_back_to_typed_questions() {
+    let cards = vec![card(1, "Dimension of the image", "Rank"), card(2, "Also the image dimension", "rank"), card(3, "", "Nullity")];
+    let questions = build_questions(&cards, &[QuestionKind::MultipleChoice], 10, &mut QuizRng::new(1));
+
+    assert_eq!(questions.len(), 2);
+    assert!(questions.iter().all(|q| q.kind == QuestionKind::Typed));
+}
+
+#[test]
+fn answers_are_only_sent_once_the_attempt_is_over() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute("INSERT INTO users (name, email, status) VALUES ('Ada', 'ada@example.com', 'active')", []).unwrap();
+    let deck = FlashcardCommands::new(&mut conn).create_deck_method("Linear Algebra".to_string(), None, None).unwrap();
+    for card in sample_cards() {
+        FlashcardCommands::new(&mut conn).create_card_method(deck.id.unwrap(), card.front, card.back, None, None).unwrap();
+    }
+
+    let mut commands = QuizCommands::new(&mut conn);
+    let quiz = commands
+        .create_quiz_method(QuizInput { deck_id: deck.id, kinds: Some(vec!["typed".to_string()]), ..QuizInput::default() })
+        .unwrap();
+    let questions = quiz.questions.clone().unwrap();
+    let sent = serde_json::to_value(&quiz).unwrap();
+    assert!(sent["questions"].as_array().unwrap().iter().all(|question| question.get("answer").is_none()));
+    assert!(!serde_json::to_string(&commands.get_quiz_method(quiz.id.unwrap()).unwrap()).unwrap().contains("Nullity"));
+
+    let attempt = commands.start_quiz_attempt_method(None, quiz.id.unwrap()).unwrap();
+    let attempt_id = attempt.id.unwrap();
+    commands.answer_quiz_question_method(attempt_id, questions[0].id.unwrap(), questions[0].answer.clone()).unwrap();
+    assert!(commands.get_quiz_attempt_method(attempt_id).unwrap().solutions.is_none());
+
+    let finished = commands.finish_quiz_attempt_method(attempt_id).unwrap();
+    assert_eq!((finished.correct_count, finished.question_count), (1, 5));
+    let solutions = finished.solutions.unwrap();
+    assert_eq!(
+        solutions.iter().map(|solution| (solution.question_id, solution.answer.clone())).collect::<Vec<_>>(),
+        questions.iter().map(|question| (question.id.unwrap(), question.answer.clone())).collect::<Vec<_>>()
+    );
+    assert_eq!(commands.finish_quiz_attempt_method(attempt_id).unwrap().solutions.unwrap().len(), 5);
+}
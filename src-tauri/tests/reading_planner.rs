@@ -0,0 +1,41 @@
+use app_lib::services::reading_planner::split_evenly;
+use chrono::{Days, NaiveDate};
+
+fn days(count: u64) -> Vec<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+    (0..count).map(|offset| first + Days::new(offset)).collect()
+}
+
+fn ranges(sessions: &[(NaiveDate, i32, i32)]) -> Vec<(i32, i32)> {
+    sessions.iter().map(|(_, start, end)| (*start, *end)).collect()
+}
+
+#[test]
+fn splits_exactly_when_units_divide_evenly() {
+    let dates = days(3);
+    let sessions = split_evenly(1, 6, &dates);
+
+    assert_eq!(ranges(&sessions), vec![(1, 2), (3, 4), (5, 6)]);
+    assert!(sessions.iter().map(|(date, _, _)| *date).eq(dates));
+}
+
+#[test]
+fn earlier_days_take_the_remainder() {
+    assert_eq!(ranges(&split_evenly(1, 10, &days(3))), vec![(1, 4), (5, 7), (8, 10)]);
+    assert_eq!(ranges(&split_evenly(21, 31, &days(4))), vec![(21, 23), (24, 26), (27, 29), (30, 31)]);
+}
+
+#[test]
+fn fewer_units_than_days_spreads_sessions_out() {
+    let dates = days(5);
+    let sessions = split_evenly(1, 2, &dates);
+
+    assert_eq!(sessions, vec![(dates[0], 1, 1), (dates[2], 2, 2)]);
+    assert_eq!(split_evenly(7, 7, &dates), vec![(dates[0], 7, 7)]);
+}
+
+#[test]
+fn nothing_to_split_gives_no_sessions() {
+    assert!(split_evenly(5, 4, &days(3)).is_empty());
+    assert!(split_evenly(1, 10, &[]).is_empty());
+}
@@ -0,0 +1,101 @@
+use app_lib::commands::reading_plan_commands::ReadingPlanCommands;
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::reading_plan::ReadingPlanItem;
+use app_lib::db::models::task::TaskStatus;
+use app_lib::db::repositories::{ReadingPlanRepository, TaskRepository};
+use chrono::{Days, Local, NaiveDate};
+use rusqlite::{params, Connection};
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute_batch(
+        "INSERT INTO users (name, email, status) VALUES ('Ada', 'ada@example.com', 'active');
+         INSERT INTO user_available_days (user_id, day_of_week)
+         VALUES (1, 'monday'), (1, 'tuesday'), (1, 'wednesday'), (1, 'thursday'),
+                (1, 'friday'), (1, 'saturday'), (1, 'sunday');
+         INSERT INTO documents (title, original_filename, stored_filename, file_path, file_size, mime_type, hash, page_count)
+         VALUES ('Algebra', 'algebra.pdf', 'h.pdf', '/library/h.pdf', '1024', 'application/pdf', 'h', 28);
+         INSERT INTO books (title, file_path, document_id) VALUES ('Algebra', '/library/h.pdf', 1);
+         INSERT INTO tags (title, color) VALUES ('exam', '#ff0000');",
+    )
+    .unwrap();
+    conn
+}
+
+/// `today + days` as the `YYYY-MM-DD` the planner uses; rebalancing always
+/// schedules from today.
+fn day(days: u64) -> String {
+    let today: NaiveDate = Local::now().date_naive();
+    (today + Days::new(days)).format("%Y-%m-%d").to_string()
+}
+
+fn items(conn: &mut Connection, plan_id: i64) -> Vec<ReadingPlanItem> {
+    ReadingPlanRepository::new(conn).get_items(plan_id).unwrap()
+}
+
+#[test]
+fn rebalancing_keeps_open_session_tasks() {
+    let mut conn = setup();
+    let plan = ReadingPlanCommands::new(&mut conn)
+        .create_reading_plan_method(Some(1), 1, day(13), None, None)
+        .unwrap();
+    let plan_id = plan.id.unwrap();
+    let sessions = items(&mut conn, plan_id);
+    assert_eq!(sessions.len(), 14);
+    assert_eq!((sessions[0].start_unit, sessions[13].end_unit), (1, 28));
+
+    let ids: Vec<i32> = sessions.iter().map(|item| item.task_id).collect();
+    conn.execute_batch(&format!(
+        "UPDATE tasks SET status = 'done' WHERE id = {};
+         UPDATE tasks SET status = 'in_progress' WHERE id = {};
+         UPDATE tasks SET status = 'blocked' WHERE id = {};
+         INSERT INTO task_tags (task_id, tag_id) VALUES ({}, 1);",
+        ids[0], ids[1], ids[2], ids[2]
+    ))
+    .unwrap();
+    conn.execute(
+        "INSERT INTO pomodoros (user_id, task_id, book_id, started_at, ended_at, focus_seconds)
+         VALUES (1, ?, 1, '2024-01-01T09:00:00+00:00', '2024-01-01T09:25:00+00:00', 1500)",
+        params![ids[1]],
+    )
+    .unwrap();
+
+    // Pages 3-28 over a week: the first seven open sessions move, the rest go.
+    let preview = ReadingPlanCommands::new(&mut conn).rebalance_reading_plan_method(plan_id, Some(day(6))).unwrap();
+    assert_eq!(preview.sessions.len(), 7);
+
+    let rebalanced = items(&mut conn, plan_id);
+    assert_eq!(rebalanced.iter().map(|item| item.task_id).collect::<Vec<_>>(), ids[..8].to_vec());
+    assert_eq!((rebalanced[0].start_unit, rebalanced[0].end_unit), (1, 2));
+    assert_eq!(
+        rebalanced[1..].iter().map(|item| (item.start_unit, item.end_unit, item.due_date.clone().unwrap())).collect::<Vec<_>>(),
+        preview.sessions.iter().map(|session| (session.start_unit, session.end_unit, session.date.clone())).collect::<Vec<_>>()
+    );
+    assert_eq!((preview.sessions[0].start_unit, preview.sessions[0].date.clone()), (3, day(0)));
+    assert_eq!((preview.sessions[6].end_unit, preview.sessions[6].date.clone()), (28, day(6)));
+    assert_eq!(
+        rebalanced.iter().map(|item| item.status).take(4).collect::<Vec<_>>(),
+        vec![TaskStatus::Done, TaskStatus::InProgress, TaskStatus::Blocked, TaskStatus::Todo]
+    );
+
+    let repository = TaskRepository::new(&mut conn);
+    let blocked = repository.get_task_by_id(ids[2]).unwrap().unwrap();
+    let session = &preview.sessions[1];
+    assert_eq!(blocked.title, format!("Read pp. {}-{}", session.start_unit, session.end_unit));
+    assert_eq!(blocked.tags.iter().map(|tag| tag.title.as_str()).collect::<Vec<_>>(), vec!["exam"]);
+    assert!(repository.get_task_by_id(ids[8]).unwrap().is_none());
+    assert_eq!(repository.get_task_by_id(plan.task_id).unwrap().unwrap().due_date, Some(day(6)));
+
+    let pomodoro_task: Option<i32> =
+        conn.query_row("SELECT task_id FROM pomodoros", params![], |row| row.get(0)).unwrap();
+    assert_eq!(pomodoro_task, Some(ids[1]));
+
+    // A later deadline adds sessions after the ones that are kept.
+    ReadingPlanCommands::new(&mut conn).rebalance_reading_plan_method(plan_id, Some(day(9))).unwrap();
+    let extended = items(&mut conn, plan_id);
+    assert_eq!(extended.len(), 11);
+    assert_eq!(extended.iter().map(|item| item.task_id).take(8).collect::<Vec<_>>(), ids[..8].to_vec());
+    assert!(extended[8..].iter().all(|item| !ids.contains(&item.task_id) && item.status == TaskStatus::Todo));
+    assert_eq!((extended[1].start_unit, extended[10].end_unit), (3, 28));
+}
@@ -0,0 +1,110 @@
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::reading_session::ReadingTime;
+use app_lib::db::repositories::ReadingSessionRepository;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::{params, Connection};
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn.execute_batch(
+        "INSERT INTO users (name, email, status) VALUES ('Ada', 'ada@example.com', 'active');
+         INSERT INTO users (name, email, status) VALUES ('Alan', 'alan@example.com', 'active');
+         INSERT INTO books (title, file_path) VALUES ('Algebra', '/library/algebra.pdf');
+         INSERT INTO books (title, file_path) VALUES ('Mechanics', '/library/mechanics.pdf');
+         INSERT INTO tags (title, color) VALUES ('math', '#ff0000');
+         INSERT INTO tags (title, color) VALUES ('physics', '#0000ff');
+         INSERT INTO book_tags (book_id, tag_id) VALUES (1, 1);
+         INSERT INTO book_tags (book_id, tag_id) VALUES (2, 1);
+         INSERT INTO book_tags (book_id, tag_id) VALUES (2, 2);",
+    )
+    .unwrap();
+    conn
+}
+
+/// Noon UTC keeps the day the same in the local time zone the queries use.
+fn noon(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 4, day, 12, 0, 0).unwrap()
+}
+
+/// Reads one session: `(page, seconds)` heartbeats, each after that many seconds.
+fn read(conn: &mut Connection, user_id: i32, book_id: i64, start: DateTime<Utc>, heartbeats: &[(i32, i64)]) -> i64 {
+    let mut repository = ReadingSessionRepository::new(conn);
+    let session_id = repository.start_session(user_id, book_id, Some(1), start).unwrap();
+    let mut now = start;
+    for (page, seconds) in heartbeats {
+        now += Duration::seconds(*seconds);
+        let session = repository.get_session(session_id).unwrap().unwrap();
+        repository.record_heartbeat(&session, Some(*page), *seconds, now).unwrap();
+    }
+    session_id
+}
+
+fn minutes(times: Vec<ReadingTime>) -> Vec<(String, f64)> {
+    times.into_iter().map(|time| (time.label, time.minutes)).collect()
+}
+
+#[test]
+fn heartbeats_credit_the_page_shown_since_the_last_one() {
+    let mut conn = setup();
+    let session_id = read(&mut conn, 1, 1, noon(1), &[(2, 30), (2, 45), (1, 15), (1, 10)]);
+
+    let repository = ReadingSessionRepository::new(&mut conn);
+    let session = repository.get_session(session_id).unwrap().unwrap();
+    assert_eq!(session.current_page, Some(1));
+    assert_eq!(session.last_heartbeat_at, noon(1) + Duration::seconds(100));
+
+    let pages: Vec<(i32, i64, i64)> =
+        session.pages.unwrap().into_iter().map(|p| (p.page_number, p.dwell_seconds, p.visits)).collect();
+    assert_eq!(pages, vec![(1, 40, 2), (2, 60, 1)]);
+}
+
+#[test]
+fn ending_and_closing_stale_sessions() {
+    let mut conn = setup();
+    let ended = read(&mut conn, 1, 1, noon(1), &[(2, 60)]);
+    let stale = read(&mut conn, 1, 1, noon(2), &[(3, 60)]);
+    let other_user = read(&mut conn, 2, 2, noon(2), &[]);
+
+    let mut repository = ReadingSessionRepository::new(&mut conn);
+    assert_eq!(repository.end_session(ended, noon(1) + Duration::minutes(5), "stopped").unwrap(), 1);
+    assert_eq!(repository.end_session(ended, noon(1) + Duration::minutes(9), "stopped").unwrap(), 0);
+
+    assert_eq!(repository.close_stale_sessions(Some(1)).unwrap(), 1);
+    let session = repository.get_session(stale).unwrap().unwrap();
+    assert_eq!(session.ended_at, Some(noon(2) + Duration::seconds(60)));
+    assert_eq!(session.end_reason.as_deref(), Some("stale"));
+    assert!(repository.get_session(other_user).unwrap().unwrap().ended_at.is_none());
+
+    assert_eq!(repository.close_stale_sessions(None).unwrap(), 1);
+    let session = repository.get_session(ended).unwrap().unwrap();
+    assert_eq!((session.ended_at, session.end_reason.as_deref()), (Some(noon(1) + Duration::minutes(5)), Some("stopped")));
+}
+
+#[test]
+fn reading_time_adds_up_by_day_book_and_tag() {
+    let mut conn = setup();
+    read(&mut conn, 1, 1, noon(1), &[(2, 600), (3, 300)]);
+    read(&mut conn, 1, 2, noon(1), &[(2, 120)]);
+    read(&mut conn, 1, 2, noon(3), &[(2, 1800)]);
+    read(&mut conn, 1, 1, noon(9), &[(2, 6000)]);
+    read(&mut conn, 2, 1, noon(1), &[(2, 6000)]);
+    conn.execute("INSERT INTO books (title, file_path) VALUES ('Untagged', '/library/untagged.pdf')", params![]).unwrap();
+    read(&mut conn, 1, 3, noon(2), &[(2, 60)]);
+
+    let repository = ReadingSessionRepository::new(&mut conn);
+    assert_eq!(
+        minutes(repository.get_minutes_per_day(1, "2024-04-01", "2024-04-08").unwrap()),
+        vec![("2024-04-01".to_string(), 17.0), ("2024-04-02".to_string(), 1.0), ("2024-04-03".to_string(), 30.0)]
+    );
+    assert_eq!(
+        minutes(repository.get_minutes_per_book(1, "2024-04-01", "2024-04-08").unwrap()),
+        vec![("Mechanics".to_string(), 32.0), ("Algebra".to_string(), 15.0), ("Untagged".to_string(), 1.0)]
+    );
+    // Mechanics counts toward both of its tags; untagged books toward none.
+    assert_eq!(
+        minutes(repository.get_minutes_per_tag(1, "2024-04-01", "2024-04-08").unwrap()),
+        vec![("math".to_string(), 47.0), ("physics".to_string(), 32.0)]
+    );
+    assert!(repository.get_minutes_per_day(1, "2024-05-01", "2024-05-31").unwrap().is_empty());
+}
@@ -0,0 +1,90 @@
+use app_lib::db::models::task::{RecurrenceFrequency, RecurrenceRule};
+use app_lib::db::models::user_available_day::DayOfWeek;
+use app_lib::services::recurrence::{format_date, next_occurrence_after, parse_date, Occurrences};
+
+fn rule(frequency: RecurrenceFrequency, interval: u32, starts_on: &str) -> RecurrenceRule {
+    RecurrenceRule {
+        frequency,
+        interval,
+        weekdays: Vec::new(),
+        starts_on: starts_on.to_string(),
+        until: None,
+        count: None,
+    }
+}
+
+fn dates(rule: &RecurrenceRule, limit: usize) -> Vec<(u32, String)> {
+    Occurrences::new(rule).unwrap().take(limit).map(|(n, date)| (n, format_date(date))).collect()
+}
+
+fn numbered(expected: &[&str]) -> Vec<(u32, String)> {
+    expected.iter().enumerate().map(|(i, date)| (i as u32 + 1, date.to_string())).collect()
+}
+
+#[test]
+fn weekly_rules_walk_several_weekdays_every_other_week() {
+    // 2024-01-03 is a Wednesday, so the Monday of its week comes before the start.
+    let mut every_other_week = rule(RecurrenceFrequency::Weekly, 2, "2024-01-03");
+    every_other_week.weekdays = vec![DayOfWeek::Friday, DayOfWeek::Monday, DayOfWeek::Friday];
+
+    assert_eq!(
+        dates(&every_other_week, 5),
+        numbered(&["2024-01-05", "2024-01-15", "2024-01-19", "2024-01-29", "2024-02-02"])
+    );
+
+    // Without weekdays the rule falls on the weekday of the start.
+    let plain = rule(RecurrenceFrequency::Weekly, 3, "2024-01-03");
+    assert_eq!(dates(&plain, 3), numbered(&["2024-01-03", "2024-01-24", "2024-02-14"]));
+}
+
+#[test]
+fn monthly_rules_clamp_to_short_months_and_keep_the_start_day() {
+    let monthly = rule(RecurrenceFrequency::Monthly, 1, "2024-01-31");
+    assert_eq!(
+        dates(&monthly, 5),
+        numbered(&["2024-01-31", "2024-02-29", "2024-03-31", "2024-04-30", "2024-05-31"])
+    );
+
+    let quarterly = rule(RecurrenceFrequency::Monthly, 3, "2024-11-30");
+    assert_eq!(dates(&quarterly, 3), numbered(&["2024-11-30", "2025-02-28", "2025-05-30"]));
+}
+
+#[test]
+fn count_and_until_end_the_series() {
+    let mut counted = rule(RecurrenceFrequency::Daily, 3, "2024-03-01");
+    counted.count = Some(4);
+    assert_eq!(dates(&counted, 10), numbered(&["2024-03-01", "2024-03-04", "2024-03-07", "2024-03-10"]));
+
+    let mut bounded = rule(RecurrenceFrequency::Weekly, 1, "2024-03-04");
+    bounded.until = Some("2024-03-25".to_string());
+    assert_eq!(dates(&bounded, 10), numbered(&["2024-03-04", "2024-03-11", "2024-03-18", "2024-03-25"]));
+
+    bounded.until = Some("2024-03-24".to_string());
+    bounded.count = Some(2);
+    assert_eq!(dates(&bounded, 10), numbered(&["2024-03-04", "2024-03-11"]));
+
+    bounded.count = None;
+    bounded.until = Some("2024-03-03".to_string());
+    assert!(dates(&bounded, 10).is_empty());
+}
+
+#[test]
+fn next_occurrence_is_strictly_after_the_given_date() {
+    let mut weekly = rule(RecurrenceFrequency::Weekly, 2, "2024-01-03");
+    weekly.weekdays = vec![DayOfWeek::Monday, DayOfWeek::Friday];
+    weekly.count = Some(3);
+
+    let next = |after: &str| {
+        next_occurrence_after(&weekly, parse_date(after).unwrap())
+            .unwrap()
+            .map(|(n, date)| (n, format_date(date)))
+    };
+
+    assert_eq!(next("2023-12-25"), Some((1, "2024-01-05".to_string())));
+    assert_eq!(next("2024-01-05"), Some((2, "2024-01-15".to_string())));
+    assert_eq!(next("2024-01-16"), Some((3, "2024-01-19".to_string())));
+    assert_eq!(next("2024-01-19"), None);
+
+    weekly.starts_on = "2024-13-01".to_string();
+    assert!(next_occurrence_after(&weekly, parse_date("2024-01-01").unwrap()).is_err());
+}
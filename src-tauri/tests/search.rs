@@ -0,0 +1,136 @@
+use app_lib::commands::search_commands::{SearchCommandError, SearchCommands};
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::{book::Book, document::Document, tag::Tag};
+use app_lib::db::repositories::{highlight_snippet, BookRepository, DocumentRepository, PageTextRepository, TagRepository};
+use rusqlite::Connection;
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn
+}
+
+fn tag(conn: &mut Connection, title: &str) -> Tag {
+    let tag = Tag { id: None, title: title.to_string(), color: "#336699".to_string(), icon: None };
+    let id = TagRepository::new(conn).create_tag(&tag).unwrap();
+    Tag { id: Some(id as i32), ..tag }
+}
+
+/// Adds a book whose document has the given page texts, numbered from 1.
+fn add_book(conn: &mut Connection, title: &str, tags: Vec<Tag>, pages: &[&str]) -> i64 {
+    let document = Document {
+        id: 0,
+        title: title.to_string(),
+        original_filename: format!("{}.pdf", title),
+        stored_filename: format!("{}.pdf", title),
+        file_path: format!("/library/{}.pdf", title),
+        file_size: "1024".to_string(),
+        mime_type: "application/pdf".to_string(),
+        hash: format!("hash-{}", title),
+        page_count: pages.len() as i32,
+        created_at: None,
+        last_accessed: None,
+        thumbnail_path: None,
+        tags: None,
+        subject: None,
+        keywords: None,
+        language: None,
+        identifiers: None,
+    };
+    let document_id = DocumentRepository::new(conn).create(&document).unwrap();
+    let book = Book {
+        id: 0,
+        title: title.to_string(),
+        author: None,
+        file_path: Some(document.file_path.clone()),
+        document_id: Some(document_id),
+        tags: Some(tags),
+    };
+    let book_id = BookRepository::insert_book(conn, &book).unwrap();
+
+    let pages: Vec<(i32, String)> = pages.iter().enumerate().map(|(i, text)| (i as i32 + 1, text.to_string())).collect();
+    PageTextRepository::new(conn).replace_document_pages(document_id, &pages).unwrap();
+    book_id
+}
+
+fn search(conn: &mut Connection, query: &str, tag_ids: Vec<i32>) -> Vec<(String, i32)> {
+    SearchCommands::new(conn)
+        .search_library_method(query.to_string(), tag_ids, None, None)
+        .unwrap()
+        .into_iter()
+        .map(|hit| (hit.title, hit.page_number))
+        .collect()
+}
+
+#[test]
+fn hits_are_ranked_by_relevance() {
+    let mut conn = setup();
+    add_book(&mut conn, "Physics", Vec::new(), &[
+        "A long page on waves, energy, momentum and forces that mentions an eigenvalue only once in passing.",
+    ]);
+    add_book(&mut conn, "Algebra", Vec::new(), &[
+        "Nothing to see here.",
+        "Eigenvalue, eigenvalue: every eigenvalue of a symmetric matrix is real.",
+    ]);
+
+    assert_eq!(search(&mut conn, "eigenvalue", Vec::new()), vec![("Algebra".to_string(), 2), ("Physics".to_string(), 1)]);
+
+    // The last word matches as a prefix, and other pages never match.
+    assert_eq!(search(&mut conn, "symmetric eigen", Vec::new()), vec![("Algebra".to_string(), 2)]);
+    assert!(search(&mut conn, "momentum eigenvalue matrix", Vec::new()).is_empty());
+
+    let page = SearchCommands::new(&mut conn)
+        .search_library_method("eigenvalue".to_string(), Vec::new(), Some(1), Some(1))
+        .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].title, "Physics");
+}
+
+#[test]
+fn snippets_mark_matches_and_escape_the_page_text() {
+    let mut conn = setup();
+    add_book(&mut conn, "Analysis", Vec::new(), &["If x < y & y < z then <b>x < z</b>: transitivity\u{2} holds."]);
+
+    let hits = SearchCommands::new(&mut conn)
+        .search_library_method("transitivity".to_string(), Vec::new(), None, None)
+        .unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(
+        hits[0].snippet,
+        "If x &lt; y &amp; y &lt; z then &lt;b&gt;x &lt; z&lt;/b&gt;: <mark>transitivity</mark> holds."
+    );
+    assert_eq!(highlight_snippet("\u{2}\"q\"\u{3} & 'a'"), "<mark>&quot;q&quot;</mark> &amp; &#39;a&#39;");
+}
+
+#[test]
+fn tag_filter_keeps_books_with_any_of_the_tags() {
+    let mut conn = setup();
+    let math = tag(&mut conn, "math");
+    let physics = tag(&mut conn, "physics");
+    let history = tag(&mut conn, "history");
+    add_book(&mut conn, "Algebra", vec![math.clone()], &["groups act on sets"]);
+    add_book(&mut conn, "Mechanics", vec![physics.clone(), math.clone()], &["rotation groups act on space"]);
+    add_book(&mut conn, "Untagged", Vec::new(), &["groups of people"]);
+
+    let titles = |hits: Vec<(String, i32)>| {
+        let mut titles: Vec<String> = hits.into_iter().map(|(title, _)| title).collect();
+        titles.sort();
+        titles
+    };
+
+    assert_eq!(titles(search(&mut conn, "groups", Vec::new())), vec!["Algebra", "Mechanics", "Untagged"]);
+    assert_eq!(titles(search(&mut conn, "groups", vec![physics.id.unwrap()])), vec!["Mechanics"]);
+    assert_eq!(
+        titles(search(&mut conn, "groups", vec![physics.id.unwrap(), math.id.unwrap()])),
+        vec!["Algebra", "Mechanics"]
+    );
+    assert!(search(&mut conn, "groups", vec![history.id.unwrap()]).is_empty());
+}
+
+#[test]
+fn blank_queries_are_rejected() {
+    let mut conn = setup();
+    let result = SearchCommands::new(&mut conn).search_library_method(" \" ".to_string(), Vec::new(), None, None);
+    assert!(matches!(result, Err(SearchCommandError::InvalidInput(_))));
+}
@@ -0,0 +1,146 @@
+use app_lib::db::models::flashcard::{CardState, ReviewGrade, SchedulerKind};
+use app_lib::services::spaced_repetition::{
+    fsrs, fsrs_interval, fsrs_retrievability, review, sm2, FSRS_WEIGHTS, MIN_EASE_FACTOR,
+};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+fn sm2_run(grades: &[ReviewGrade]) -> Vec<CardState> {
+    let mut state = CardState::default();
+    grades
+        .iter()
+        .map(|grade| {
+            state = sm2(&state, *grade);
+            state
+        })
+        .collect()
+}
+
+#[test]
+fn sm2_good_reviews_follow_the_classic_intervals() {
+    let states = sm2_run(&[ReviewGrade::Good; 4]);
+
+    let intervals: Vec<u32> = states.iter().map(|state| state.interval_days).collect();
+    assert_eq!(intervals, vec![1, 6, 15, 38]);
+    assert!(states.iter().all(|state| close(state.ease_factor, 2.5)));
+    assert_eq!(states[3].repetitions, 4);
+}
+
+#[test]
+fn sm2_grades_adjust_the_ease_factor() {
+    let start = CardState::default();
+
+    assert!(close(sm2(&start, ReviewGrade::Easy).ease_factor, 2.6));
+    assert!(close(sm2(&start, ReviewGrade::Hard).ease_factor, 2.36));
+    assert!(close(sm2(&start, ReviewGrade::Again).ease_factor, 1.96));
+}
+
+#[test]
+fn sm2_lapse_restarts_the_card() {
+    let states = sm2_run(&[ReviewGrade::Good, ReviewGrade::Good, ReviewGrade::Again, ReviewGrade::Good]);
+
+    assert_eq!(states[2].interval_days, 1);
+    assert_eq!(states[2].repetitions, 0);
+    assert_eq!(states[2].lapses, 1);
+    // After relearning the ladder starts over at one day.
+    assert_eq!(states[3].interval_days, 1);
+    assert_eq!(states[3].repetitions, 1);
+}
+
+#[test]
+fn sm2_failing_a_new_card_is_not_a_lapse() {
+    let state = sm2(&CardState::default(), ReviewGrade::Again);
+    assert_eq!(state.lapses, 0);
+}
+
+#[test]
+fn sm2_ease_factor_never_drops_below_the_floor() {
+    let states = sm2_run(&[ReviewGrade::Again; 6]);
+    assert!(close(states[5].ease_factor, MIN_EASE_FACTOR));
+
+    let hard = sm2_run(&[ReviewGrade::Good, ReviewGrade::Good, ReviewGrade::Hard, ReviewGrade::Hard]);
+    // Hard still passes: 6 * 2.5 = 15, then 15 * 2.36 = 35.4 rounded up.
+    assert_eq!(hard[2].interval_days, 15);
+    assert_eq!(hard[3].interval_days, 36);
+}
+
+#[test]
+fn fsrs_first_review_uses_the_initial_stability() {
+    let start = CardState::default();
+    let intervals: Vec<u32> = [ReviewGrade::Again, ReviewGrade::Hard, ReviewGrade::Good, ReviewGrade::Easy]
+        .iter()
+        .map(|grade| fsrs(&start, *grade, 0).interval_days)
+        .collect();
+    assert_eq!(intervals, vec![1, 1, 4, 14]);
+
+    let good = fsrs(&start, ReviewGrade::Good, 0);
+    assert!(close(good.stability, FSRS_WEIGHTS[2]));
+    assert!(close(good.difficulty, FSRS_WEIGHTS[4]));
+    assert_eq!(good.repetitions, 1);
+}
+
+#[test]
+fn fsrs_interval_matches_stability_at_ninety_percent_retention() {
+    assert_eq!(fsrs_interval(10.0), 10);
+    assert_eq!(fsrs_interval(0.2), 1);
+    assert!(close(fsrs_retrievability(10, 10.0), 0.9));
+    assert!(close(fsrs_retrievability(0, 10.0), 1.0));
+}
+
+#[test]
+fn fsrs_stability_grows_on_success_and_shrinks_on_failure() {
+    let learned = fsrs(&CardState::default(), ReviewGrade::Good, 0);
+
+    let recalled = fsrs(&learned, ReviewGrade::Good, 4);
+    assert!(recalled.stability > learned.stability);
+    assert!(recalled.interval_days > learned.interval_days);
+
+    let hard = fsrs(&learned, ReviewGrade::Hard, 4);
+    let easy = fsrs(&learned, ReviewGrade::Easy, 4);
+    assert!(hard.stability < recalled.stability);
+    assert!(easy.stability > recalled.stability);
+    assert!(easy.difficulty < recalled.difficulty);
+
+    let forgotten = fsrs(&recalled, ReviewGrade::Again, 10);
+    assert!(forgotten.stability < recalled.stability);
+    assert_eq!(forgotten.interval_days, 1);
+    assert_eq!(forgotten.lapses, 1);
+    assert_eq!(forgotten.repetitions, 0);
+}
+
+#[test]
+fn scheduling_is_deterministic() {
+    let grades = [ReviewGrade::Good, ReviewGrade::Easy, ReviewGrade::Hard, ReviewGrade::Again, ReviewGrade::Good];
+
+    for kind in [SchedulerKind::Sm2, SchedulerKind::Fsrs] {
+        let run = || {
+            let mut state = CardState::default();
+            grades
+                .iter()
+                .map(|grade| {
+                    state = review(kind, &state, *grade, state.interval_days);
+                    state
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+}
+
+#[test]
+fn switching_to_fsrs_keeps_the_sm2_history() {
+    let learned = *sm2_run(&[ReviewGrade::Good; 4]).last().unwrap();
+    assert_eq!((learned.interval_days, learned.stability), (38, 0.0));
+
+    let next = review(SchedulerKind::Fsrs, &learned, ReviewGrade::Good, 38);
+    assert!(next.stability > 38.0);
+    assert!(next.interval_days > 38);
+    assert!(close(next.difficulty, FSRS_WEIGHTS[4]));
+    assert_eq!(next.repetitions, 5);
+
+    let lapsed = review(SchedulerKind::Fsrs, &learned, ReviewGrade::Again, 38);
+    assert!(lapsed.stability > 0.0 && lapsed.stability < 38.0);
+    assert_eq!((lapsed.interval_days, lapsed.lapses, lapsed.repetitions), (1, 1, 0));
+}
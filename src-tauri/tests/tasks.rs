@@ -0,0 +1,256 @@
+use app_lib::commands::task_commands::{TaskCommandError, TaskCommands};
+use app_lib::db::migrations::run_migrations;
+use app_lib::db::models::task::{TaskNode, TaskProgress, TaskStatus};
+use app_lib::db::repositories::TaskRepository;
+use rusqlite::Connection;
+
+const STATUSES: [TaskStatus; 5] =
+    [TaskStatus::Todo, TaskStatus::InProgress, TaskStatus::Blocked, TaskStatus::Done, TaskStatus::Archived];
+
+fn setup() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn
+}
+
+/// Creates the tasks in order, so the first gets ID 1; each names its parent.
+fn create_tasks(commands: &mut TaskCommands, document_id: Option<i64>, tasks: &[(&str, Option<i32>)]) {
+    for (title, parent_id) in tasks {
+        commands.create_task_method(title.to_string(), None, None, document_id, *parent_id, Vec::new()).unwrap();
+    }
+}
+
+/// `(id, position)` of the given tasks, checking they all sit under `parent_id`.
+fn positions(commands: &TaskCommands, parent_id: Option<i32>, ids: &[i32]) -> Vec<(i32, i32)> {
+    let mut positions: Vec<(i32, i32)> = ids
+        .iter()
+        .map(|id| {
+            let task = commands.get_task_by_id_method(*id).unwrap().unwrap();
+            assert_eq!(task.parent_id, parent_id, "parent of task {}", id);
+            (task.id, task.position)
+        })
+        .collect();
+    positions.sort_by_key(|(_, position)| *position);
+    positions
+}
+
+#[test]
+fn statuses_use_snake_case_on_the_wire() {
+    for status in STATUSES {
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(json, format!("\"{}\"", status.as_str()));
+        assert_eq!(serde_json::from_str::<TaskStatus>(&json).unwrap(), status);
+    }
+    assert_eq!(serde_json::to_string(&TaskStatus::InProgress).unwrap(), "\"in_progress\"");
+}
+
+#[test]
+fn transitions_follow_the_status_table() {
+    // Rows are the current status, columns the target, both in STATUSES order.
+    let allowed = [
+        [true, true, true, true, true],
+        [true, true, true, true, true],
+        [true, true, true, false, true],
+        [true, true, false, true, true],
+        [true, false, false, false, true],
+    ];
+
+    for (from, row) in STATUSES.iter().zip(allowed) {
+        for (to, expected) in STATUSES.iter().zip(row) {
+            assert_eq!(from.can_move_to(*to), expected, "{:?} -> {:?}", from, to);
+        }
+    }
+}
+
+#[test]
+fn completion_time_is_set_kept_and_cleared() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    commands.create_task_method("Read chapter 3".to_string(), None, None, None, None, Vec::new()).unwrap();
+    let completed_at = |commands: &TaskCommands| commands.get_task_by_id_method(1).unwrap().unwrap().completed_at;
+
+    commands.set_task_status_method(1, "in_progress".to_string()).unwrap();
+    assert_eq!(completed_at(&commands), None);
+
+    commands.set_task_status_method(1, "done".to_string()).unwrap();
+    let first = completed_at(&commands).expect("completing a task records when");
+
+    commands.set_task_status_method(1, "done".to_string()).unwrap();
+    assert_eq!(completed_at(&commands), Some(first));
+
+    commands.set_task_status_method(1, "todo".to_string()).unwrap();
+    assert_eq!(completed_at(&commands), None);
+
+    commands.set_task_status_method(1, "done".to_string()).unwrap();
+    commands.set_task_status_method(1, "archived".to_string()).unwrap();
+    assert_eq!(completed_at(&commands), None);
+}
+
+#[test]
+fn disallowed_transitions_leave_the_task_alone() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    commands.create_task_method("Write summary".to_string(), None, None, None, None, Vec::new()).unwrap();
+
+    commands.set_task_status_method(1, "blocked".to_string()).unwrap();
+    let err = commands.set_task_status_method(1, "done".to_string()).unwrap_err();
+    assert!(matches!(err, TaskCommandError::InvalidTransition(_)));
+
+    let task = commands.get_task_by_id_method(1).unwrap().unwrap();
+    assert_eq!((task.status, task.completed_at), (TaskStatus::Blocked, None));
+}
+
+#[test]
+fn recurring_due_dates_stay_on_their_series() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    commands.create_task_method("Weekly review".to_string(), None, Some("2024-04-01".to_string()), None, None, Vec::new()).unwrap();
+    commands.set_task_recurrence_method(1, "weekly".to_string(), None, Vec::new(), None, None).unwrap();
+    let update_due = |commands: &mut TaskCommands, due_date: Option<&str>, clear: bool| {
+        commands.update_task_method(1, None, None, None, due_date.map(str::to_string), None, false, clear, false)
+    };
+
+    assert!(matches!(update_due(&mut commands, Some("2024-04-03"), false), Err(TaskCommandError::InvalidInput(_))));
+    assert!(matches!(update_due(&mut commands, None, true), Err(TaskCommandError::InvalidInput(_))));
+    assert_eq!(commands.get_task_by_id_method(1).unwrap().unwrap().due_date.as_deref(), Some("2024-04-01"));
+
+    update_due(&mut commands, Some("2024-04-15"), false).unwrap();
+    assert_eq!(commands.get_task_by_id_method(1).unwrap().unwrap().due_date.as_deref(), Some("2024-04-15"));
+
+    // Without a recurrence the due date is free again.
+    commands.clear_task_recurrence_method(1).unwrap();
+    update_due(&mut commands, Some("2024-04-17"), false).unwrap();
+    update_due(&mut commands, None, true).unwrap();
+}
+
+#[test]
+fn upcoming_occurrences_skip_tasks_with_unreadable_due_dates() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    for (title, due_date) in [("Broken", "2024-04-02"), ("Quiz", "2024-04-03"), ("Standup", "2024-04-01")] {
+        commands.create_task_method(title.to_string(), None, Some(due_date.to_string()), None, None, Vec::new()).unwrap();
+    }
+    commands.set_task_recurrence_method(3, "daily".to_string(), Some(2), Vec::new(), None, None).unwrap();
+    conn.execute("UPDATE tasks SET due_date = '04/02/2024' WHERE id = 1", []).unwrap();
+
+    let occurrences = TaskCommands::new(&mut conn)
+        .list_upcoming_occurrences_method("2024-04-01".to_string(), "2024-04-05".to_string())
+        .unwrap();
+    assert_eq!(
+        occurrences.into_iter().map(|o| (o.title, o.due_date, o.occurrence, o.materialized)).collect::<Vec<_>>(),
+        vec![
+            ("Standup".to_string(), "2024-04-01".to_string(), Some(1), true),
+            ("Quiz".to_string(), "2024-04-03".to_string(), None, true),
+            ("Standup".to_string(), "2024-04-03".to_string(), Some(2), false),
+            ("Standup".to_string(), "2024-04-05".to_string(), Some(3), false),
+        ]
+    );
+}
+
+#[test]
+fn moving_a_task_renumbers_its_new_siblings() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    create_tasks(&mut commands, None, &[("A", None), ("B", None), ("C", None), ("A1", Some(1)), ("A2", Some(1))]);
+    assert_eq!(positions(&commands, Some(1), &[4, 5]), vec![(4, 0), (5, 1)]);
+
+    commands.move_task_method(3, Some(1), Some(1)).unwrap();
+    assert_eq!(positions(&commands, Some(1), &[3, 4, 5]), vec![(4, 0), (3, 1), (5, 2)]);
+    assert_eq!(positions(&commands, None, &[1, 2]), vec![(1, 0), (2, 1)]);
+
+    // Without a position, and with one past the end, the task goes last.
+    commands.move_task_method(4, None, None).unwrap();
+    commands.move_task_method(2, None, Some(99)).unwrap();
+    assert_eq!(positions(&commands, None, &[1, 2, 4]), vec![(1, 0), (4, 1), (2, 2)]);
+
+    commands.move_task_method(5, None, Some(0)).unwrap();
+    assert_eq!(positions(&commands, None, &[1, 2, 4, 5]), vec![(5, 0), (1, 1), (4, 2), (2, 3)]);
+    assert_eq!(positions(&commands, Some(1), &[3]), vec![(3, 0)]);
+}
+
+#[test]
+fn tasks_cannot_move_under_themselves_or_archived_parents() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    create_tasks(&mut commands, None, &[("A", None), ("A1", Some(1)), ("A1a", Some(2)), ("Old", None)]);
+    commands.set_task_status_method(4, "archived".to_string()).unwrap();
+
+    for parent_id in [1, 2, 3, 4] {
+        let err = commands.move_task_method(1, Some(parent_id), None).unwrap_err();
+        assert!(matches!(err, TaskCommandError::InvalidInput(_)), "parent {}", parent_id);
+    }
+    assert!(commands.move_task_method(9, None, None).is_err());
+    assert_eq!(commands.get_task_by_id_method(1).unwrap().unwrap().parent_id, None);
+    assert_eq!(positions(&commands, Some(2), &[3]), vec![(3, 0)]);
+}
+
+#[test]
+fn archiving_a_task_archives_its_subtree() {
+    let mut conn = setup();
+    let mut commands = TaskCommands::new(&mut conn);
+    create_tasks(&mut commands, None, &[
+        ("Chapter", None),
+        ("Read", Some(1)),
+        ("Notes", Some(2)),
+        ("Skipped", Some(1)),
+        ("Other chapter", None),
+    ]);
+    commands.set_task_status_method(2, "done".to_string()).unwrap();
+    commands.set_task_status_method(4, "archived".to_string()).unwrap();
+
+    let mut repository = TaskRepository::new(&mut conn);
+    let mut chapter = repository.get_task_by_id(1).unwrap().unwrap();
+    chapter.status = TaskStatus::Archived;
+    assert_eq!(repository.archive_task_tree(&chapter).unwrap(), 2);
+
+    for id in 1..=4 {
+        let task = repository.get_task_by_id(id).unwrap().unwrap();
+        assert_eq!((task.status, task.completed_at), (TaskStatus::Archived, None), "task {}", id);
+    }
+    assert_eq!(repository.get_task_by_id(5).unwrap().unwrap().status, TaskStatus::Todo);
+}
+
+#[test]
+fn task_tree_rolls_up_progress_over_direct_subtasks() {
+    let mut conn = setup();
+    conn.execute(
+        "INSERT INTO documents (title, original_filename, stored_filename, file_path, file_size, mime_type, hash)
+         VALUES ('Algebra', 'algebra.pdf', 'h.pdf', '/library/h.pdf', '1024', 'application/pdf', 'h')",
+        [],
+    )
+    .unwrap();
+    let mut commands = TaskCommands::new(&mut conn);
+    create_tasks(&mut commands, Some(1), &[
+        ("Chapter 1", None),
+        ("Exercises", Some(1)),
+        ("Exercise 1", Some(2)),
+        ("Exercise 2", Some(2)),
+        ("Summary", Some(1)),
+        ("Old outline", Some(1)),
+        ("Chapter 2", None),
+    ]);
+    create_tasks(&mut commands, None, &[("Unrelated", None)]);
+    commands.set_task_status_method(3, "done".to_string()).unwrap();
+    commands.set_task_status_method(5, "done".to_string()).unwrap();
+    commands.set_task_status_method(6, "archived".to_string()).unwrap();
+
+    fn shape(node: &TaskNode) -> (String, Option<TaskProgress>, Vec<String>) {
+        let children = node.children.iter().map(|child| child.task.title.clone()).collect();
+        (node.task.title.clone(), node.progress, children)
+    }
+    let progress = |done, total| Some(TaskProgress { done, total });
+
+    let tree = commands.get_document_task_tree_method(1).unwrap();
+    assert_eq!(tree.iter().map(shape).collect::<Vec<_>>(), vec![
+        ("Chapter 1".to_string(), progress(1, 2), vec!["Exercises".to_string(), "Summary".to_string(), "Old outline".to_string()]),
+        ("Chapter 2".to_string(), None, Vec::new()),
+    ]);
+    // Progress only counts direct subtasks; the exercises roll up separately.
+    assert_eq!(shape(&tree[0].children[0]), ("Exercises".to_string(), progress(1, 2), vec!["Exercise 1".to_string(), "Exercise 2".to_string()]));
+    assert_eq!(tree[0].children[1].progress, None);
+
+    commands.set_task_status_method(4, "done".to_string()).unwrap();
+    commands.set_task_status_method(2, "done".to_string()).unwrap();
+    let tree = commands.get_document_task_tree_method(1).unwrap();
+    assert_eq!((tree[0].progress, tree[0].children[0].progress), (progress(2, 2), progress(2, 2)));
+}